@@ -0,0 +1,228 @@
+use std::sync::{Arc, Mutex};
+
+use futures::{StreamExt, TryStreamExt};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{ByteStream, Checksums};
+
+/// Computes every checksum of `Checksums` at once, as the content goes by.
+pub struct ChecksumHasher {
+    md5: md5::Context,
+    sha1: Sha1,
+    sha256: Sha256,
+    quick_xor: QuickXorHash,
+}
+
+impl Default for ChecksumHasher {
+    fn default() -> Self {
+        ChecksumHasher {
+            md5: md5::Context::new(),
+            sha1: Sha1::new(),
+            sha256: Sha256::new(),
+            quick_xor: QuickXorHash::default(),
+        }
+    }
+}
+
+impl ChecksumHasher {
+    pub fn update(&mut self, data: &[u8]) {
+        self.md5.consume(data);
+        self.sha1.update(data);
+        self.sha256.update(data);
+        self.quick_xor.update(data);
+    }
+
+    pub fn finish(self) -> Checksums {
+        Checksums {
+            md5: Some(hex::encode(self.md5.compute().0)),
+            sha1: Some(hex::encode(self.sha1.finalize())),
+            sha256: Some(hex::encode(self.sha256.finalize())),
+            quick_xor: Some(self.quick_xor.finish()),
+        }
+    }
+}
+
+/// Reads the whole of `content` to compute its checksums.
+pub async fn compute(content: ByteStream) -> Result<Checksums, CrossroadsError> {
+    let hasher = content.try_fold(ChecksumHasher::default(), |mut hasher, chunk| async move {
+        hasher.update(&chunk);
+        Ok(hasher)
+    }).await?;
+
+    Ok(hasher.finish())
+}
+
+/// Passes `content` through while feeding it to the returned hasher, to be finished once the stream is exhausted.
+pub(crate) fn hashing(content: ByteStream) -> (ByteStream, Arc<Mutex<ChecksumHasher>>) {
+    let hasher = Arc::new(Mutex::new(ChecksumHasher::default()));
+
+    let fed = hasher.clone();
+    let content = content.inspect(move |chunk| {
+        if let Ok(chunk) = chunk {
+            fed.lock().unwrap().update(chunk);
+        }
+    });
+
+    (Box::pin(content), hasher)
+}
+
+/// Takes the hasher back from `hashing` once the stream it fed on is dropped.
+pub(crate) fn finish(hasher: Arc<Mutex<ChecksumHasher>>) -> Checksums {
+    let hasher = std::mem::take(&mut *hasher.lock().unwrap());
+    hasher.finish()
+}
+
+/// Fails with `CrossroadsError::VerificationFailed` when one of the checksums known on both
+/// sides differs. Nothing is checked when they have no algorithm in common.
+pub fn verify(name: &str, expected: &Checksums, actual: &Checksums) -> Result<(), CrossroadsError> {
+    let pairs = [
+        ("MD5", &expected.md5, &actual.md5),
+        ("SHA-1", &expected.sha1, &actual.sha1),
+        ("SHA-256", &expected.sha256, &actual.sha256),
+        ("QuickXorHash", &expected.quick_xor, &actual.quick_xor),
+    ];
+
+    for (algorithm, expected, actual) in pairs {
+        if let (Some(expected), Some(actual)) = (expected, actual) {
+            // Providers disagree on the case of hexadecimal digests.
+            let matches = if algorithm == "QuickXorHash" { expected == actual } else { expected.eq_ignore_ascii_case(actual) };
+
+            if !matches {
+                return Err(CrossroadsError::VerificationFailed(format!("{} of {} is {} instead of {}", algorithm, name, actual, expected)))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+const QUICK_XOR_WIDTH: usize = 160;
+const QUICK_XOR_SHIFT: usize = 11;
+
+/// Hash of OneDrive for Business, which xors each byte into a 160 bits buffer at a position
+/// moving 11 bits forward per byte, then xors the length of the content into the result.
+#[derive(Default)]
+struct QuickXorHash {
+    cells: [u64; 3],
+    shift: usize,
+    length: u64,
+}
+
+impl QuickXorHash {
+    fn update(&mut self, data: &[u8]) {
+        let mut cell = self.shift / 64;
+        let mut offset = self.shift % 64;
+
+        // Bytes 160 positions apart land on the same bits, so each position is visited once.
+        for i in 0..data.len().min(QUICK_XOR_WIDTH) {
+            let is_last_cell = cell == self.cells.len() - 1;
+            let cell_bits = if is_last_cell { QUICK_XOR_WIDTH % 64 } else { 64 };
+
+            let xored = data[i..].iter().step_by(QUICK_XOR_WIDTH).fold(0u8, |xored, byte| xored ^ byte) as u64;
+
+            if offset <= cell_bits - 8 {
+                self.cells[cell] ^= xored << offset;
+            } else {
+                let next_cell = if is_last_cell { 0 } else { cell + 1 };
+                self.cells[cell] ^= xored << offset;
+                self.cells[next_cell] ^= xored >> (cell_bits - offset);
+            }
+
+            offset += QUICK_XOR_SHIFT;
+
+            while offset >= cell_bits {
+                cell = if is_last_cell { 0 } else { cell + 1 };
+                offset -= cell_bits;
+            }
+        }
+
+        self.shift = (self.shift + QUICK_XOR_SHIFT * (data.len() % QUICK_XOR_WIDTH)) % QUICK_XOR_WIDTH;
+        self.length += data.len() as u64;
+    }
+
+    fn finish(&self) -> String {
+        let mut hash = [0u8; QUICK_XOR_WIDTH / 8];
+
+        hash[..8].copy_from_slice(&self.cells[0].to_le_bytes());
+        hash[8..16].copy_from_slice(&self.cells[1].to_le_bytes());
+        hash[16..].copy_from_slice(&self.cells[2].to_le_bytes()[..4]);
+
+        for (byte, length_byte) in hash[QUICK_XOR_WIDTH / 8 - 8..].iter_mut().zip(self.length.to_le_bytes()) {
+            *byte ^= length_byte;
+        }
+
+        base64::encode(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::CrossroadsError;
+    use crate::interfaces::filesystem::Checksums;
+
+    use super::{verify, ChecksumHasher, QuickXorHash};
+
+    #[test]
+    fn checksums_do_not_depend_on_chunks() {
+        let content: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 251) as u8).collect();
+
+        let mut whole = ChecksumHasher::default();
+        whole.update(&content);
+
+        let mut chunked = ChecksumHasher::default();
+        for chunk in content.chunks(37) {
+            chunked.update(chunk);
+        }
+
+        let checksums = whole.finish();
+        assert_eq!(checksums, chunked.finish());
+
+        let mut hello = ChecksumHasher::default();
+        hello.update(b"hello world!");
+        let hello = hello.finish();
+        assert_eq!(hello.md5.as_deref(), Some("fc3ff98e8c6a0d3087d515c0473f8677"));
+        assert_eq!(hello.sha1.as_deref(), Some("430ce34d020724ed75a196dfc2ad67c77772d169"));
+
+        assert_eq!(QuickXorHash::default().finish(), "AAAAAAAAAAAAAAAAAAAAAAAAAAA=");
+    }
+
+    /// Values of the reference implementation of OneDrive.
+    #[test]
+    fn quick_xor_hash_known_answers() {
+        let hash = |content: &[u8], chunk_size: usize| {
+            let mut hash = QuickXorHash::default();
+            content.chunks(chunk_size).for_each(|chunk| hash.update(chunk));
+            hash.finish()
+        };
+
+        let long: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 251) as u8).collect();
+
+        let vectors: [(&[u8], &str); 4] = [
+            (b"J", "SgAAAAAAAAAAAAAAAQAAAAAAAAA="),
+            (b"Hello, World!", "SCgDG9jwBhaA4ApvnQMbyBACAAA="),
+            (b"The quick brown fox jumps over the lazy dog", "bMSlbysmxJL6S75XwfMcQZOpcr4="),
+            (&long, "hNsGCVKR90prHWP/ZJHOFVZ+TIk="),
+        ];
+
+        for (content, expected) in vectors {
+            assert_eq!(hash(content, content.len()), expected);
+            assert_eq!(hash(content, 7), expected);
+        }
+    }
+
+    #[test]
+    fn verify_compares_common_checksums() {
+        let expected = Checksums { md5: Some("abcdef".to_string()), sha1: Some("012345".to_string()), ..Default::default() };
+
+        let same = Checksums { md5: Some("ABCDEF".to_string()), ..Default::default() };
+        assert!(verify("file", &expected, &same).is_ok());
+
+        let unrelated = Checksums { sha256: Some("6789".to_string()), ..Default::default() };
+        assert!(verify("file", &expected, &unrelated).is_ok());
+
+        let different = Checksums { sha1: Some("543210".to_string()), ..Default::default() };
+        assert!(matches!(verify("file", &expected, &different), Err(CrossroadsError::VerificationFailed(_))));
+    }
+}
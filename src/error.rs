@@ -0,0 +1,128 @@
+use std::{error::Error, fmt, io, time::Duration};
+
+/// Error returned by every interface exposed by a provider.
+///
+/// Providers map their native errors into one of these variants so callers can
+/// react to a failure without knowing which backend produced it.
+#[derive(Debug)]
+pub enum CrossroadsError {
+    NotFound(String),
+    AlreadyExists(String),
+    PermissionDenied(String),
+    AuthenticationRequired,
+    RateLimited(Option<Duration>),
+    Conflict(String),
+    Unsupported(String),
+    InvalidInput(String),
+    /// The content written does not match the content read.
+    VerificationFailed(String),
+    Network(Box<dyn Error + Send + Sync>),
+    Io(io::Error),
+    Provider(Box<dyn Error + Send + Sync>),
+}
+
+impl CrossroadsError {
+    pub fn unsupported(operation: &str) -> Self {
+        CrossroadsError::Unsupported(operation.to_string())
+    }
+
+    /// Maps an HTTP status code returned by a remote provider to the matching variant.
+    pub fn from_status(status: u16, message: String) -> Self {
+        match status {
+            401 => CrossroadsError::AuthenticationRequired,
+            403 => CrossroadsError::PermissionDenied(message),
+            404 => CrossroadsError::NotFound(message),
+            409 | 412 | 423 => CrossroadsError::Conflict(message),
+            429 => CrossroadsError::RateLimited(None),
+            _ => CrossroadsError::Provider(format!("HTTP {}: {}", status, message).into()),
+        }
+    }
+}
+
+impl fmt::Display for CrossroadsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrossroadsError::NotFound(message) => write!(f, "Not found: {}", message),
+            CrossroadsError::AlreadyExists(message) => write!(f, "Already exists: {}", message),
+            CrossroadsError::PermissionDenied(message) => write!(f, "Permission denied: {}", message),
+            CrossroadsError::AuthenticationRequired => write!(f, "Authentication required"),
+            CrossroadsError::RateLimited(Some(delay)) => write!(f, "Rate limited, retry after {} seconds", delay.as_secs()),
+            CrossroadsError::RateLimited(None) => write!(f, "Rate limited"),
+            CrossroadsError::Conflict(message) => write!(f, "Conflict: {}", message),
+            CrossroadsError::Unsupported(operation) => write!(f, "Unsupported operation: {}", operation),
+            CrossroadsError::InvalidInput(message) => write!(f, "Invalid input: {}", message),
+            CrossroadsError::VerificationFailed(message) => write!(f, "Verification failed: {}", message),
+            CrossroadsError::Network(error) => write!(f, "Network error: {}", error),
+            CrossroadsError::Io(error) => write!(f, "IO error: {}", error),
+            CrossroadsError::Provider(error) => write!(f, "Provider error: {}", error),
+        }
+    }
+}
+
+impl Error for CrossroadsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CrossroadsError::Network(error) | CrossroadsError::Provider(error) => Some(error.as_ref()),
+            CrossroadsError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CrossroadsError {
+    fn from(error: io::Error) -> Self {
+        // Errors raised by a stream and carried through an `io::Read` adapter come back unchanged.
+        if error.get_ref().is_some_and(|inner| inner.is::<CrossroadsError>()) {
+            return *error.into_inner().unwrap().downcast::<CrossroadsError>().unwrap()
+        }
+
+        match error.kind() {
+            io::ErrorKind::NotFound => CrossroadsError::NotFound(error.to_string()),
+            io::ErrorKind::AlreadyExists => CrossroadsError::AlreadyExists(error.to_string()),
+            io::ErrorKind::PermissionDenied => CrossroadsError::PermissionDenied(error.to_string()),
+            io::ErrorKind::Unsupported => CrossroadsError::Unsupported(error.to_string()),
+            io::ErrorKind::InvalidInput => CrossroadsError::InvalidInput(error.to_string()),
+            _ => CrossroadsError::Io(error),
+        }
+    }
+}
+
+impl From<reqwest::Error> for CrossroadsError {
+    fn from(error: reqwest::Error) -> Self {
+        match error.status() {
+            Some(status) => CrossroadsError::from_status(status.as_u16(), error.to_string()),
+            None => CrossroadsError::Network(Box::new(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::CrossroadsError;
+
+    #[test]
+    fn io_errors_are_mapped_by_kind() {
+        let error: CrossroadsError = io::Error::new(io::ErrorKind::NotFound, "missing").into();
+        assert!(matches!(error, CrossroadsError::NotFound(_)));
+
+        let error: CrossroadsError = io::Error::new(io::ErrorKind::PermissionDenied, "denied").into();
+        assert!(matches!(error, CrossroadsError::PermissionDenied(_)));
+
+        let error: CrossroadsError = io::Error::other("other").into();
+        assert!(matches!(error, CrossroadsError::Io(_)));
+
+        let error: CrossroadsError = io::Error::other(CrossroadsError::AuthenticationRequired).into();
+        assert!(matches!(error, CrossroadsError::AuthenticationRequired));
+    }
+
+    #[test]
+    fn http_statuses_are_mapped() {
+        assert!(matches!(CrossroadsError::from_status(401, String::new()), CrossroadsError::AuthenticationRequired));
+        assert!(matches!(CrossroadsError::from_status(404, String::new()), CrossroadsError::NotFound(_)));
+        assert!(matches!(CrossroadsError::from_status(412, String::new()), CrossroadsError::Conflict(_)));
+        assert!(matches!(CrossroadsError::from_status(429, String::new()), CrossroadsError::RateLimited(None)));
+        assert!(matches!(CrossroadsError::from_status(500, String::new()), CrossroadsError::Provider(_)));
+    }
+}
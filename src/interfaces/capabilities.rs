@@ -0,0 +1,42 @@
+use serde::{Serialize, Deserialize};
+
+/// How a provider identifies its objects in an `ObjectId`.
+#[derive(Serialize, Deserialize, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum IdKind {
+    /// The id is the path of the object, relative to the provider's root.
+    Path,
+    /// The id is an identifier assigned by the provider that does not change when the object is renamed or moved.
+    Opaque,
+}
+
+/// Operations and constraints of a provider, so callers can disable what is not supported
+/// instead of calling a method and handling `CrossroadsError::Unsupported`. Each interface of
+/// `Provider` has a flag, which is false when its `as_*` accessor returns `None` and may be
+/// false as well when the interface is there but the provider cannot serve it.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Capabilities {
+    pub symlinks: bool,
+    pub trash: bool,
+    /// Whether former contents of files are kept, see `Versions`.
+    pub versions: bool,
+    /// Whether objects can be shared, see `Sharing`.
+    pub sharing: bool,
+    pub search: bool,
+    /// Whether changes can be followed, see `Watch`.
+    pub watch: bool,
+    pub quota: bool,
+    /// Whether thumbnails are rendered, see `Preview`.
+    pub preview: bool,
+    /// Whether objects can be locked, see `Locking`.
+    pub locking: bool,
+    /// Whether `LockMode::Shared` locks are taken, on top of exclusive ones.
+    pub shared_locks: bool,
+    pub server_side_copy: bool,
+    pub server_side_move: bool,
+    pub ranged_reads: bool,
+    pub case_sensitive: bool,
+    pub max_file_name_length: Option<usize>,
+    pub forbidden_characters: Vec<char>,
+    pub id_kind: IdKind,
+    pub empty_directories: bool,
+}
@@ -1,9 +1,17 @@
+use std::collections::BTreeMap;
 use std::fmt;
+use std::pin::Pin;
 
 use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{Stream, TryStreamExt, future::BoxFuture, stream};
 use serde::{Serialize, Deserialize};
 use chrono::prelude::{DateTime, Utc};
 
+use crate::checksum;
+use crate::mime;
+use crate::error::CrossroadsError;
+
 #[derive(Serialize, Deserialize, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum FileType {
     Directory,
@@ -78,9 +86,28 @@ pub struct  User {
     pub name: Option<String>,
 }
 
+/// Digests of the content of a file, hexadecimal except `quick_xor` which is base64 as
+/// OneDrive reports it. Only the ones known to the provider are set.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct Checksums {
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    pub sha256: Option<String>,
+    pub quick_xor: Option<String>,
+}
+
+impl Checksums {
+    pub fn is_empty(&self) -> bool {
+        self.md5.is_none() && self.sha1.is_none() && self.sha256.is_none() && self.quick_xor.is_none()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
 pub struct Metadata {
     pub mime_type: Option<String>,
+    /// Type the content is read as when it differs from `mime_type`, for files kept in a format
+    /// of the provider's own such as Google Docs.
+    pub export_mime_type: Option<String>,
     pub open_path: Option<String>,
     pub modified_at: Option<DateTime<Utc>>,
     pub created_at: Option<DateTime<Utc>>,
@@ -88,19 +115,312 @@ pub struct Metadata {
     pub accessed_at: Option<DateTime<Utc>>,
     pub size: Option<u64>,
     pub owner: Option<User>,
-    pub permissions: Option<Permissions>
+    pub permissions: Option<Permissions>,
+    /// Opaque token that changes whenever the content of the object does, to pass to
+    /// `write_file_stream_if_match`.
+    pub version: Option<String>,
+    pub checksums: Option<Checksums>,
+    /// Custom key/value metadata set with `set_properties`, `None` when the provider does not
+    /// return it along with the rest, in which case `get_properties` reads it.
+    pub properties: Option<BTreeMap<String, String>>,
 }
 
+/// Fields of the metadata changed by `set_metadata`, those left `None` being kept as they are.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct MetadataUpdate {
+    pub modified_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub accessed_at: Option<DateTime<Utc>>,
+    pub permissions: Option<Permissions>,
+    pub owner: Option<UserId>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum MetadataField {
+    ModifiedAt,
+    CreatedAt,
+    AccessedAt,
+    Permissions,
+    Owner,
+}
+
+impl MetadataUpdate {
+    pub fn fields(&self) -> Vec<MetadataField> {
+        [
+            (self.modified_at.is_some(), MetadataField::ModifiedAt),
+            (self.created_at.is_some(), MetadataField::CreatedAt),
+            (self.accessed_at.is_some(), MetadataField::AccessedAt),
+            (self.permissions.is_some(), MetadataField::Permissions),
+            (self.owner.is_some(), MetadataField::Owner),
+        ].into_iter().filter_map(|(set, field)| set.then_some(field)).collect()
+    }
+
+    /// Fails with `CrossroadsError::Unsupported`, naming the fields set outside of `supported`,
+    /// so that providers change nothing rather than part of the update.
+    pub fn check_supported(&self, provider: &str, supported: &[MetadataField]) -> Result<(), CrossroadsError> {
+        let unsupported: Vec<String> = self.fields().into_iter()
+            .filter(|field| !supported.contains(field))
+            .map(|field| format!("{:?}", field))
+            .collect();
+
+        if unsupported.is_empty() {
+            return Ok(())
+        }
+
+        Err(CrossroadsError::Unsupported(format!("setting {} on {}", unsupported.join(", "), provider)))
+    }
+}
+
+/// Bytes read by `read_file_range`, along with the size of the whole file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct FileRange {
+    pub content: Vec<u8>,
+    pub offset: u64,
+    pub total_size: u64,
+}
+
+/// What to do when the destination of an operation already holds an entry with the same name.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ConflictPolicy {
+    /// Give up with `CrossroadsError::AlreadyExists`.
+    Fail,
+    /// Delete the existing entry and take its place.
+    Replace,
+    /// Keep both, numbering the new entry as in `report (1).txt`.
+    Rename,
+}
+
+/// Part of the content of a directory, as returned by `read_directory_page`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct DirectoryPage {
+    pub files: Vec<File>,
+    /// Opaque token to pass back to `read_directory_page` to get the next page,
+    /// `None` once the last page is reached.
+    pub next_page_token: Option<String>,
+}
+
+/// Entry that `delete_recursive` could not delete, along with the reason.
+#[derive(Debug)]
+pub struct DeleteFailure {
+    pub object_id: ObjectId,
+    pub error: CrossroadsError,
+}
+
+/// Number of entries requested per page when a directory is listed as a whole.
+const DIRECTORY_PAGE_SIZE: usize = 1000;
+
+/// Chunks of a file's content, read from or written to a provider without
+/// holding the whole file in memory.
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, CrossroadsError>> + Send>>;
+
+/// Entries of a directory, fetched one page at a time as the stream is polled.
+pub type FileStream<'a> = Pin<Box<dyn Stream<Item = Result<File, CrossroadsError>> + Send + 'a>>;
+
 #[async_trait]
-pub trait FileSystem {
-    async fn read_file(&self, object_id: ObjectId) -> Result<Vec<u8>, Box<dyn std::error::Error>>;
-    async fn write_file(&self, object_id: ObjectId, content: Vec<u8>) -> Result<(), Box<dyn std::error::Error>>;
-    async fn delete(&self, object_id: ObjectId) -> Result<(), Box<dyn std::error::Error>>;
-    async fn move_to(&self, object_id: ObjectId, new_parent_id: ObjectId) -> Result<ObjectId, Box<dyn std::error::Error>>;
-    async fn rename(&self, object_id: ObjectId, new_name: String) -> Result<ObjectId, Box<dyn std::error::Error>>;
-    async fn read_directory(&self, object_id: ObjectId) -> Result<Vec<File>, Box<dyn std::error::Error>>;
-    async fn create(&self, parent_id: ObjectId, file: File) -> Result<(), Box<dyn std::error::Error>>;
-    async fn get_metadata(&self, object_id: ObjectId) -> Result<Metadata, Box<dyn std::error::Error>>;
-    async fn read_link(&self, object_id: ObjectId) -> Result<ObjectId, Box<dyn std::error::Error>>;
-    async fn create_link(&self, parent_id: ObjectId, name: &str, link_id: ObjectId) -> Result<ObjectId, Box<dyn std::error::Error>>;
-}
\ No newline at end of file
+pub trait FileSystem: Send + Sync {
+    async fn read_file(&self, object_id: ObjectId) -> Result<Vec<u8>, CrossroadsError> {
+        let stream = self.read_file_stream(object_id).await?;
+
+        stream.try_fold(Vec::new(), |mut content, chunk| async move {
+            content.extend_from_slice(&chunk);
+            Ok(content)
+        }).await
+    }
+
+    async fn write_file(&self, object_id: ObjectId, content: Vec<u8>) -> Result<(), CrossroadsError> {
+        let size = content.len() as u64;
+        let stream = stream::once(async move { Ok(Bytes::from(content)) });
+
+        self.write_file_stream(object_id, Box::pin(stream), Some(size)).await
+    }
+
+    async fn read_file_stream(&self, object_id: ObjectId) -> Result<ByteStream, CrossroadsError>;
+    /// `size` is the total length of `content` when it is known in advance. Providers
+    /// that must announce the length before uploading buffer the stream when it is `None`.
+    async fn write_file_stream(&self, object_id: ObjectId, content: ByteStream, size: Option<u64>) -> Result<(), CrossroadsError>;
+    /// Same as `write_file_stream`, but fails with `CrossroadsError::Conflict` without writing
+    /// anything if the object is no longer at `version`, as read from `Metadata::version`.
+    async fn write_file_stream_if_match(&self, _object_id: ObjectId, _content: ByteStream, _size: Option<u64>, _version: String) -> Result<(), CrossroadsError> {
+        Err(CrossroadsError::unsupported("write_file_stream_if_match"))
+    }
+    /// Same as `write_file_stream`, then fails with `CrossroadsError::VerificationFailed` if
+    /// the checksums of the written file differ from those of `content`.
+    async fn write_file_stream_verified(&self, object_id: ObjectId, content: ByteStream, size: Option<u64>) -> Result<(), CrossroadsError> {
+        let (content, hasher) = checksum::hashing(content);
+
+        self.write_file_stream(object_id.clone(), content, size).await?;
+
+        checksum::verify(object_id.as_str(), &checksum::finish(hasher), &self.checksums(object_id.clone()).await?)
+    }
+    /// Reads at most `length` bytes starting at `offset`. The returned content is empty
+    /// when `offset` is past the end of the file.
+    async fn read_file_range(&self, object_id: ObjectId, offset: u64, length: u64) -> Result<FileRange, CrossroadsError>;
+    /// Deletes a file or a directory. Some providers only delete empty directories,
+    /// `delete_recursive` deletes their content on every provider.
+    async fn delete(&self, object_id: ObjectId) -> Result<(), CrossroadsError>;
+    /// Deletes a file, or a directory with all its content. Entries that cannot be deleted
+    /// are reported instead of stopping the deletion, and the directories holding them are
+    /// left in place. An error is only returned when `object_id` itself cannot be deleted.
+    async fn delete_recursive(&self, object_id: ObjectId) -> Result<Vec<DeleteFailure>, CrossroadsError> {
+        if !object_id.is_directory() {
+            return self.delete(object_id).await.map(|_| vec![])
+        }
+
+        let mut failures = vec![];
+        delete_tree(self, object_id, &mut failures).await?;
+
+        Ok(failures)
+    }
+    async fn move_to(&self, object_id: ObjectId, new_parent_id: ObjectId) -> Result<ObjectId, CrossroadsError>;
+    /// Copies a file, or a directory with all its content, into `new_parent_id` under `name`
+    /// and returns the id of the copy. Providers copy on the server whenever they can.
+    async fn copy(&self, object_id: ObjectId, new_parent_id: ObjectId, name: &str, conflict: ConflictPolicy) -> Result<ObjectId, CrossroadsError>;
+    async fn rename(&self, object_id: ObjectId, new_name: String) -> Result<ObjectId, CrossroadsError>;
+    async fn read_directory(&self, object_id: ObjectId) -> Result<Vec<File>, CrossroadsError> {
+        self.read_directory_stream(object_id).try_collect().await
+    }
+
+    fn read_directory_stream(&self, object_id: ObjectId) -> FileStream<'_> {
+        let pages = stream::try_unfold(Some(None), move |page_token: Option<Option<String>>| {
+            let object_id = object_id.clone();
+
+            async move {
+                let Some(page_token) = page_token else {
+                    return Ok::<_, CrossroadsError>(None)
+                };
+
+                let page = self.read_directory_page(object_id, DIRECTORY_PAGE_SIZE, page_token).await?;
+                Ok(Some((page.files, page.next_page_token.map(Some))))
+            }
+        });
+
+        Box::pin(pages.map_ok(|files| stream::iter(files.into_iter().map(Ok::<_, CrossroadsError>))).try_flatten())
+    }
+
+    /// Lists at most `page_size` entries of a directory, starting where the page that
+    /// returned `page_token` stopped. Providers may return fewer entries than requested
+    /// even when more pages follow.
+    async fn read_directory_page(&self, object_id: ObjectId, page_size: usize, page_token: Option<String>) -> Result<DirectoryPage, CrossroadsError>;
+    /// Creates an empty file, or a directory when `file.id` is one, and returns its id.
+    /// Only the name and the type of `file` are required, its id being otherwise ignored.
+    async fn create(&self, parent_id: ObjectId, file: File) -> Result<ObjectId, CrossroadsError>;
+    async fn get_metadata(&self, object_id: ObjectId) -> Result<Metadata, CrossroadsError>;
+    /// Id of the entry at `path`, the names leading to it from the root separated by `/`.
+    /// Unless the provider knows better, each name is looked up in the listing of its parent.
+    async fn resolve_path(&self, path: &str) -> Result<ObjectId, CrossroadsError> {
+        let mut current = ObjectId::root();
+
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            let mut children = self.read_directory_stream(current.clone());
+            let mut found = None;
+
+            while let Some(child) = children.try_next().await? {
+                if child.name == name {
+                    found = Some(child.id);
+                    break
+                }
+            }
+
+            current = found.ok_or_else(|| CrossroadsError::NotFound(path.to_string()))?;
+        }
+
+        Ok(current)
+    }
+    /// Path of an entry from the root, as accepted by `resolve_path`.
+    async fn path_of(&self, _object_id: ObjectId) -> Result<String, CrossroadsError> {
+        Err(CrossroadsError::unsupported("path_of"))
+    }
+    /// Checksums reported by the provider, or computed from the content of the file when it reports none.
+    async fn checksums(&self, object_id: ObjectId) -> Result<Checksums, CrossroadsError> {
+        let reported = match self.get_metadata(object_id.clone()).await {
+            Ok(metadata) => metadata.checksums.filter(|checksums| !checksums.is_empty()),
+            Err(CrossroadsError::Unsupported(_)) => None,
+            Err(error) => return Err(error),
+        };
+
+        match reported {
+            Some(checksums) => Ok(checksums),
+            None => checksum::compute(self.read_file_stream(object_id).await?).await,
+        }
+    }
+    /// Type recognized from the start of the content, which listings leave out as it takes
+    /// reading every file. The type reported by the provider is kept for text formats and
+    /// others without magic bytes.
+    async fn detect_mime_type(&self, object_id: ObjectId) -> Result<Option<String>, CrossroadsError> {
+        if object_id.is_directory() {
+            return Ok(Some(mime::DIRECTORY.to_string()))
+        }
+
+        let metadata = match self.get_metadata(object_id.clone()).await {
+            Ok(metadata) => metadata,
+            Err(CrossroadsError::Unsupported(_)) => Metadata::default(),
+            Err(error) => return Err(error),
+        };
+
+        let reported = metadata.mime_type.filter(|mime_type| !mime::is_generic(mime_type));
+
+        if metadata.size == Some(0) {
+            return Ok(reported)
+        }
+
+        let start = self.read_file_range(object_id, 0, mime::SNIFF_LENGTH).await?;
+        Ok(mime::sniff(&start.content).or(reported))
+    }
+    async fn set_modified_at(&self, _object_id: ObjectId, _modified_at: DateTime<Utc>) -> Result<(), CrossroadsError> {
+        Err(CrossroadsError::unsupported("set_modified_at"))
+    }
+    /// Changes the fields set in `update`. Providers fail with `CrossroadsError::Unsupported`
+    /// when they cannot change one of them, before changing any.
+    async fn set_metadata(&self, object_id: ObjectId, update: MetadataUpdate) -> Result<(), CrossroadsError> {
+        update.check_supported("this provider", &[MetadataField::ModifiedAt])?;
+
+        if let Some(modified_at) = update.modified_at {
+            self.set_modified_at(object_id, modified_at).await?;
+        }
+
+        Ok(())
+    }
+    /// Custom key/value metadata of the object, empty when none was set.
+    async fn get_properties(&self, object_id: ObjectId) -> Result<BTreeMap<String, String>, CrossroadsError> {
+        self.get_metadata(object_id).await?.properties.ok_or_else(|| CrossroadsError::unsupported("get_properties"))
+    }
+    /// Adds `properties` to the custom metadata of the object, replacing the values of the
+    /// names already set.
+    async fn set_properties(&self, _object_id: ObjectId, _properties: BTreeMap<String, String>) -> Result<(), CrossroadsError> {
+        Err(CrossroadsError::unsupported("set_properties"))
+    }
+    /// Removes the properties called `names`, those that are not set being ignored.
+    async fn remove_properties(&self, _object_id: ObjectId, _names: &[String]) -> Result<(), CrossroadsError> {
+        Err(CrossroadsError::unsupported("remove_properties"))
+    }
+    async fn read_link(&self, object_id: ObjectId) -> Result<ObjectId, CrossroadsError>;
+    async fn create_link(&self, parent_id: ObjectId, name: &str, link_id: ObjectId) -> Result<ObjectId, CrossroadsError>;
+}
+
+/// Deletes the content of `object_id` one entry at a time, then the directory itself
+/// if all of it could be deleted.
+fn delete_tree<'a, F: FileSystem + ?Sized>(filesystem: &'a F, object_id: ObjectId, failures: &'a mut Vec<DeleteFailure>) -> BoxFuture<'a, Result<(), CrossroadsError>> {
+    Box::pin(async move {
+        // Listed up front, so that deletions do not shift the pages being read.
+        let children = filesystem.read_directory(object_id.clone()).await?;
+        let known_failures = failures.len();
+
+        for child in children {
+            let result = if child.id.is_directory() {
+                delete_tree(filesystem, child.id.clone(), failures).await
+            } else {
+                filesystem.delete(child.id.clone()).await
+            };
+
+            if let Err(error) = result {
+                failures.push(DeleteFailure { object_id: child.id, error });
+            }
+        }
+
+        if failures.len() == known_failures {
+            filesystem.delete(object_id).await?;
+        }
+
+        Ok(())
+    })
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::error::CrossroadsError;
+
+use super::filesystem::ObjectId;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LockMode {
+    /// Held by any number of holders at once, as long as no one holds an exclusive lock.
+    Shared,
+    Exclusive,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct LockOptions {
+    pub mode: LockMode,
+    /// How long to wait for the conflicting locks to be released, failing with
+    /// `CrossroadsError::Conflict` after that.
+    pub wait: Duration,
+    /// How long the lock lasts unless released, on providers where it can outlive its holder.
+    pub lease: Duration,
+}
+
+impl Default for LockOptions {
+    fn default() -> Self {
+        LockOptions { mode: LockMode::Exclusive, wait: Duration::ZERO, lease: Duration::from_secs(300) }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Lock {
+    pub object_id: ObjectId,
+    pub mode: LockMode,
+    /// Identifies the lock to `unlock`.
+    pub token: String,
+    /// When the lock is released by itself, for leased locks.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Advisory locks, which only hold against other holders taking a lock, not against writes.
+#[async_trait]
+pub trait Locking: Send + Sync {
+    async fn lock(&self, object_id: ObjectId, options: LockOptions) -> Result<Lock, CrossroadsError>;
+
+    async fn unlock(&self, lock: Lock) -> Result<(), CrossroadsError>;
+}
+
+/// Interval between two attempts of a lock waiting for conflicting ones to be released.
+pub(crate) const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Calls `attempt` until it takes the lock, or until `wait` has elapsed. Attempts return
+/// `None` while the lock is held by someone else.
+pub(crate) async fn retry_lock<F, Fut>(object_id: &ObjectId, wait: Duration, mut attempt: F) -> Result<Lock, CrossroadsError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<Option<Lock>, CrossroadsError>>,
+{
+    let deadline = tokio::time::Instant::now() + wait;
+
+    loop {
+        if let Some(lock) = attempt().await? {
+            return Ok(lock)
+        }
+
+        if tokio::time::Instant::now() >= deadline {
+            return Err(CrossroadsError::Conflict(format!("{} is locked", object_id)))
+        }
+
+        tokio::time::sleep(LOCK_RETRY_INTERVAL).await;
+    }
+}
@@ -1,7 +1,23 @@
+pub mod capabilities;
 pub mod filesystem;
+pub mod locking;
+pub mod preview;
+pub mod quota;
+pub mod search;
+pub mod sharing;
 pub mod trash;
+pub mod versions;
+pub mod watch;
 
 pub trait Provider {
     fn as_filesystem(& self) -> Option<& dyn filesystem::FileSystem>;
     fn as_trash(& self) -> Option<& dyn trash::Trash>;
+    fn as_search(& self) -> Option<& dyn search::Search>;
+    fn as_watch(& self) -> Option<& dyn watch::Watch>;
+    fn as_versions(& self) -> Option<& dyn versions::Versions>;
+    fn as_sharing(& self) -> Option<& dyn sharing::Sharing>;
+    fn as_quota(& self) -> Option<& dyn quota::Quota>;
+    fn as_preview(& self) -> Option<& dyn preview::Preview>;
+    fn as_locking(& self) -> Option<& dyn locking::Locking>;
+    fn capabilities(& self) -> capabilities::Capabilities;
 }
\ No newline at end of file
@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+
+use crate::error::CrossroadsError;
+
+use super::filesystem::ObjectId;
+
+/// Sizes of the freedesktop thumbnail specification, by the longest side of the thumbnail.
+#[derive(Serialize, Deserialize, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum ThumbnailSize {
+    Normal,
+    Large,
+    XLarge,
+    XXLarge,
+}
+
+impl ThumbnailSize {
+    pub fn pixels(&self) -> u32 {
+        match self {
+            ThumbnailSize::Normal => 128,
+            ThumbnailSize::Large => 256,
+            ThumbnailSize::XLarge => 512,
+            ThumbnailSize::XXLarge => 1024,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Thumbnail {
+    pub content: Vec<u8>,
+    pub mime_type: String,
+    /// Dimensions fit within the requested size, and are smaller for smaller originals.
+    pub width: u32,
+    pub height: u32,
+}
+
+#[async_trait]
+pub trait Preview: Send + Sync {
+    /// Thumbnail of the object, keeping its aspect ratio. Objects the provider cannot render,
+    /// such as folders, return `CrossroadsError::Unsupported`.
+    async fn thumbnail(&self, object_id: ObjectId, size: ThumbnailSize) -> Result<Thumbnail, CrossroadsError>;
+}
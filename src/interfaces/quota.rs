@@ -0,0 +1,21 @@
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+
+use crate::error::CrossroadsError;
+
+/// Space of a provider, in bytes. Amounts the provider does not report are `None`, such as
+/// the total of an unlimited storage.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct StorageQuota {
+    pub total: Option<u64>,
+    pub used: Option<u64>,
+    /// Space left to the user, which may be less than `total - used` when part of it is reserved.
+    pub free: Option<u64>,
+    /// Part of `used` taken by the trash.
+    pub trash: Option<u64>,
+}
+
+#[async_trait]
+pub trait Quota: Send + Sync {
+    async fn quota(&self) -> Result<StorageQuota, CrossroadsError>;
+}
@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Serialize, Deserialize};
+
+use crate::error::CrossroadsError;
+
+use super::filesystem::{File, FileStream};
+
+/// Criteria a file must all meet to be returned by `Search::search`. Criteria left to `None` match everything.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct SearchQuery {
+    /// Glob matched against the name of the file, ignoring case. A name without wildcards
+    /// matches every name containing it.
+    pub name: Option<String>,
+    pub mime_type: Option<String>,
+    pub modified_after: Option<DateTime<Utc>>,
+    pub modified_before: Option<DateTime<Utc>>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Words looked up in the content of the files, only on providers indexing it.
+    pub full_text: Option<String>,
+}
+
+impl SearchQuery {
+    /// Part of `name` before its first wildcard, which matching names contain.
+    pub fn name_literal(&self) -> Option<String> {
+        let name = self.name.as_deref()?;
+        let literal: String = name.chars().take_while(|character| !"*?[{\\".contains(*character)).collect();
+
+        if literal.is_empty() { None } else { Some(literal) }
+    }
+
+    /// Compiles the criteria checked on the files listed by a provider.
+    pub fn filter(&self) -> Result<SearchFilter, CrossroadsError> {
+        let name = match self.name.as_deref() {
+            Some(name) if name.contains(['*', '?', '[', '{']) => Some(name.to_string()),
+            Some(name) => Some(format!("*{}*", globset::escape(name))),
+            None => None,
+        };
+
+        let name = name.map(|pattern| GlobBuilder::new(&pattern).case_insensitive(true).build())
+            .transpose()
+            .map_err(|error| CrossroadsError::InvalidInput(error.to_string()))?
+            .map(|glob| glob.compile_matcher());
+
+        Ok(SearchFilter { name, query: self.clone() })
+    }
+}
+
+/// Criteria of a `SearchQuery` that can be checked on a `File`, that is all but `full_text`.
+/// A criterion on a piece of metadata the file lacks is not met.
+pub struct SearchFilter {
+    name: Option<GlobMatcher>,
+    query: SearchQuery,
+}
+
+impl SearchFilter {
+    pub fn matches(&self, file: &File) -> bool {
+        let metadata = file.metadata.as_ref();
+        let modified_at = metadata.and_then(|metadata| metadata.modified_at);
+        let size = metadata.and_then(|metadata| metadata.size);
+        let mime_type = metadata.and_then(|metadata| metadata.mime_type.as_deref());
+
+        self.name.as_ref().is_none_or(|name| name.is_match(&file.name))
+            && self.query.mime_type.as_deref().is_none_or(|expected| mime_type == Some(expected))
+            && self.query.modified_after.is_none_or(|after| modified_at.is_some_and(|date| date > after))
+            && self.query.modified_before.is_none_or(|before| modified_at.is_some_and(|date| date < before))
+            && self.query.min_size.is_none_or(|min| size.is_some_and(|size| size >= min))
+            && self.query.max_size.is_none_or(|max| size.is_some_and(|size| size <= max))
+    }
+}
+
+#[async_trait]
+pub trait Search: Send + Sync {
+    /// Streams the files of the whole provider meeting every criterion of `query`. Criteria
+    /// the provider cannot evaluate fail with `CrossroadsError::Unsupported`.
+    async fn search(&self, query: SearchQuery) -> Result<FileStream<'_>, CrossroadsError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::interfaces::filesystem::{File, Metadata, ObjectId};
+
+    use super::SearchQuery;
+
+    fn file(name: &str, size: u64) -> File {
+        File {
+            id: ObjectId::plain_text(name.to_string()),
+            name: name.to_string(),
+            metadata: Some(Metadata {
+                size: Some(size),
+                modified_at: Some(Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap()),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn search_filter_checks_every_criterion() {
+        let query = SearchQuery { name: Some("Report".to_string()), min_size: Some(10), ..Default::default() };
+        let filter = query.filter().unwrap();
+
+        assert!(filter.matches(&file("annual report.pdf", 20)));
+        assert!(!filter.matches(&file("annual report.pdf", 5)));
+        assert!(!filter.matches(&file("summary.pdf", 20)));
+
+        let query = SearchQuery {
+            name: Some("*.pdf".to_string()),
+            modified_after: Some(Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap()),
+            ..Default::default()
+        };
+        let filter = query.filter().unwrap();
+
+        assert!(filter.matches(&file("summary.PDF", 0)));
+        assert!(!filter.matches(&file("summary.pdf.txt", 0)));
+        assert_eq!(query.name_literal(), None);
+
+        assert!(SearchQuery { name: Some("[".to_string()), ..Default::default() }.filter().is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::error::CrossroadsError;
+
+use super::filesystem::ObjectId;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ShareRole {
+    Read,
+    /// Read and comment, on providers with comments.
+    Comment,
+    Write,
+    Owner,
+}
+
+/// Who a share grants access to.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum Grantee {
+    /// Anyone with the link, without signing in.
+    Anyone,
+    /// Anyone in the organization of the owner.
+    Organization,
+    /// Anyone in the given domain.
+    Domain(String),
+    /// A user, by email address.
+    User(String),
+    /// A group of users, by email address.
+    Group(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ShareRequest {
+    pub role: ShareRole,
+    pub grantee: Grantee,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Access granted on an object, whether through a link or a permission.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Share {
+    /// Identifier to pass to `revoke`, `None` when the share cannot be revoked.
+    pub id: Option<String>,
+    /// Link opening the object, which may also need the grantee to sign in.
+    pub url: Option<String>,
+    pub role: ShareRole,
+    pub grantee: Grantee,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[async_trait]
+pub trait Sharing: Send + Sync {
+    /// Grants access to `object_id`. Grants the provider cannot make fail with
+    /// `CrossroadsError::Unsupported`.
+    async fn share(&self, object_id: ObjectId, request: ShareRequest) -> Result<Share, CrossroadsError>;
+
+    async fn list_shares(&self, object_id: ObjectId) -> Result<Vec<Share>, CrossroadsError>;
+
+    async fn revoke_share(&self, object_id: ObjectId, share_id: &str) -> Result<(), CrossroadsError>;
+}
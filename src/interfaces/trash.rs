@@ -1,8 +1,34 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::error::CrossroadsError;
 
 use super::filesystem::ObjectId;
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct TrashedItem {
+    /// Identifier of the item in the trash, to pass to `restore_from_trash` and `delete_from_trash`.
+    pub id: String,
+    pub name: String,
+    /// Path of the directory the item was deleted from, relative to the root of the provider.
+    pub original_location: Option<String>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub size: Option<u64>,
+}
+
 #[async_trait]
 pub trait Trash {
-    async fn send_to_trash(&self, object_id: ObjectId) -> Result<(), Box<dyn std::error::Error>>;
-}
\ No newline at end of file
+    async fn send_to_trash(&self, object_id: ObjectId) -> Result<(), CrossroadsError>;
+
+    /// Items deleted from this provider that are still in the trash.
+    async fn list_trash(&self) -> Result<Vec<TrashedItem>, CrossroadsError>;
+
+    /// Puts the item back where it was deleted from.
+    async fn restore_from_trash(&self, trashed_id: &str) -> Result<(), CrossroadsError>;
+
+    async fn delete_from_trash(&self, trashed_id: &str) -> Result<(), CrossroadsError>;
+
+    /// Permanently deletes every item of `list_trash`.
+    async fn empty_trash(&self) -> Result<(), CrossroadsError>;
+}
@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::error::CrossroadsError;
+
+use super::filesystem::{ByteStream, ObjectId, User};
+
+/// Content a file had at some point, kept by the provider.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Revision {
+    /// Identifier of the revision for the provider, to pass to `read_revision` and `restore_revision`.
+    pub id: String,
+    pub modified_at: Option<DateTime<Utc>>,
+    pub size: Option<u64>,
+    /// Who wrote the content, when the provider records it.
+    pub author: Option<User>,
+    /// Whether this is the content the file has now.
+    pub is_current: bool,
+}
+
+#[async_trait]
+pub trait Versions: Send + Sync {
+    /// Revisions of `object_id`, newest first.
+    async fn list_revisions(&self, object_id: ObjectId) -> Result<Vec<Revision>, CrossroadsError>;
+
+    async fn read_revision(&self, object_id: ObjectId, revision_id: &str) -> Result<ByteStream, CrossroadsError>;
+
+    /// Makes the content of the revision current, which keeps the revisions in between.
+    async fn restore_revision(&self, object_id: ObjectId, revision_id: &str) -> Result<(), CrossroadsError>;
+}
@@ -0,0 +1,87 @@
+use std::pin::Pin;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::{stream, Stream};
+use serde::{Serialize, Deserialize};
+
+use crate::error::CrossroadsError;
+
+use super::filesystem::{File, ObjectId};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+    /// Renamed or moved, only told apart from a deletion followed by a creation on some providers.
+    Renamed,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ChangeEvent {
+    pub kind: ChangeKind,
+    pub object_id: ObjectId,
+    /// Entry as it is after the change, `None` once deleted.
+    pub file: Option<File>,
+    /// Id the entry had before a `ChangeKind::Renamed`, on providers whose ids are paths.
+    pub previous_id: Option<ObjectId>,
+}
+
+/// Position in the changes of a provider. It is opaque and only meant to be stored, so that
+/// a watcher can resume after a restart without missing or repeating changes.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct WatchCursor(pub String);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ChangeBatch {
+    pub events: Vec<ChangeEvent>,
+    /// Cursor to pass on to get the changes following these ones.
+    pub cursor: WatchCursor,
+}
+
+pub type ChangeStream<'a> = Pin<Box<dyn Stream<Item = Result<ChangeBatch, CrossroadsError>> + Send + 'a>>;
+
+#[async_trait]
+pub trait Watch: Send + Sync {
+    /// Cursor from which the changes made below `root` from now on are reported.
+    async fn watch_cursor(&self, root: ObjectId) -> Result<WatchCursor, CrossroadsError>;
+
+    /// Changes made below `root` since `cursor` was returned, which may be none.
+    async fn changes_since(&self, root: ObjectId, cursor: WatchCursor) -> Result<ChangeBatch, CrossroadsError>;
+
+    /// Streams the changes made below `root` since `cursor`, batch by batch. Providers without
+    /// notifications are asked for changes every `interval`. The stream ends at the first error,
+    /// the cursor of the last batch received being where to resume from.
+    fn watch(&self, root: ObjectId, cursor: WatchCursor, interval: Duration) -> ChangeStream<'_> {
+        poll(self, root, cursor, interval)
+    }
+}
+
+/// Calls `changes_since` every `interval` and yields the batches that are not empty.
+pub fn poll<W: Watch + ?Sized>(watch: &W, root: ObjectId, cursor: WatchCursor, interval: Duration) -> ChangeStream<'_> {
+    let changes = stream::try_unfold((cursor, true), move |(mut cursor, mut first)| {
+        let root = root.clone();
+
+        async move {
+            loop {
+                if !first {
+                    tokio::time::sleep(interval).await;
+                }
+
+                first = false;
+
+                let batch = watch.changes_since(root.clone(), cursor).await?;
+
+                if !batch.events.is_empty() {
+                    let next = batch.cursor.clone();
+                    return Ok(Some((batch, (next, false))))
+                }
+
+                cursor = batch.cursor;
+            }
+        }
+    });
+
+    Box::pin(changes)
+}
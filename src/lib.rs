@@ -1,10 +1,15 @@
 use std::fs;
 
+pub mod checksum;
+pub mod error;
 pub mod interfaces;
+pub mod mime;
 pub mod providers;
 pub mod storage;
+pub mod transfer;
+pub mod walker;
 
 pub fn read_token() -> String {
-    fs::read_to_string("./token".to_string())
+    fs::read_to_string("./token")
         .expect("Should have been able to read the file")
 }
\ No newline at end of file
@@ -0,0 +1,42 @@
+/// Type of directories, as the freedesktop shared MIME database names it.
+pub const DIRECTORY: &str = "inode/directory";
+pub const SYMLINK: &str = "inode/symlink";
+/// Generic type S3 and others give to content of an unknown type.
+pub const OCTET_STREAM: &str = "application/octet-stream";
+/// Bytes read from the start of a file to recognize its type.
+pub const SNIFF_LENGTH: u64 = 8192;
+
+/// Type guessed from the extension of `name`, which takes no reading.
+pub fn from_name(name: &str) -> Option<String> {
+    mime_guess::from_path(name).first_raw().map(str::to_string)
+}
+
+/// Type recognized from the magic bytes at the start of a file, given at least `SNIFF_LENGTH`
+/// bytes of it when it is that long. Text formats have none, and are left to `from_name`.
+pub fn sniff(start: &[u8]) -> Option<String> {
+    infer::get(start).map(|kind| kind.mime_type().to_string())
+}
+
+/// Whether `mime_type` tells nothing about the content, such as the type stored by default.
+pub fn is_generic(mime_type: &str) -> bool {
+    matches!(mime_type, OCTET_STREAM | "binary/octet-stream")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_name, is_generic, sniff};
+
+    #[test]
+    fn mime_types_from_names_and_content() {
+        assert_eq!(from_name("Reports/summary.PDF").as_deref(), Some("application/pdf"));
+        assert_eq!(from_name("notes.txt").as_deref(), Some("text/plain"));
+        assert_eq!(from_name("Makefile"), None);
+
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").as_deref(), Some("image/png"));
+        assert_eq!(sniff(b"%PDF-1.7\n").as_deref(), Some("application/pdf"));
+        assert_eq!(sniff(b"plain text"), None);
+
+        assert!(is_generic("binary/octet-stream"));
+        assert!(!is_generic("image/png"));
+    }
+}
@@ -0,0 +1,66 @@
+use bytes::{Bytes, BytesMut};
+use futures::{stream, TryStreamExt};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::ByteStream;
+
+/// Regroups the chunks of `content` into buffers of exactly `chunk_size` bytes,
+/// except for the last one which holds whatever remains. Upload sessions of
+/// remote providers only accept fragments that are a multiple of a fixed size.
+pub(crate) fn rechunk(content: ByteStream, chunk_size: usize) -> ByteStream {
+    let state = (content, BytesMut::with_capacity(chunk_size), false);
+
+    Box::pin(stream::try_unfold(state, move |(mut content, mut buffer, mut done)| async move {
+        while !done && buffer.len() < chunk_size {
+            match content.try_next().await? {
+                Some(chunk) => buffer.extend_from_slice(&chunk),
+                None => done = true,
+            }
+        }
+
+        if buffer.is_empty() {
+            return Ok(None)
+        }
+
+        let length = buffer.len().min(chunk_size);
+        let chunk = buffer.split_to(length).freeze();
+
+        Ok(Some((chunk, (content, buffer, done))))
+    }))
+}
+
+/// Reads the whole stream in memory, for providers that need the total size
+/// of an upload up front when the caller could not provide it.
+pub(crate) async fn collect(content: ByteStream) -> Result<Bytes, CrossroadsError> {
+    let buffer = content.try_fold(BytesMut::new(), |mut buffer, chunk| async move {
+        buffer.extend_from_slice(&chunk);
+        Ok(buffer)
+    }).await?;
+
+    Ok(buffer.freeze())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures::{stream, TryStreamExt};
+
+    use super::rechunk;
+
+    #[tokio::test]
+    async fn rechunk_produces_fixed_size_chunks() {
+        let content = stream::iter(vec![
+            Ok(Bytes::from_static(b"abc")),
+            Ok(Bytes::from_static(b"defgh")),
+            Ok(Bytes::from_static(b"ij")),
+        ]);
+
+        let chunks: Vec<Bytes> = rechunk(Box::pin(content), 4).try_collect().await.unwrap();
+
+        assert_eq!(chunks, vec![
+            Bytes::from_static(b"abcd"),
+            Bytes::from_static(b"efgh"),
+            Bytes::from_static(b"ij"),
+        ]);
+    }
+}
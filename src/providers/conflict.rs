@@ -0,0 +1,70 @@
+use std::future::Future;
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::ConflictPolicy;
+
+/// Picks the name under which a new entry is created in a directory, `exists` telling
+/// whether a name is already taken there. With `ConflictPolicy::Replace` the name is kept
+/// and the caller is responsible for removing the entry it replaces.
+pub(crate) async fn resolve_name<F, Fut>(name: &str, conflict: ConflictPolicy, mut exists: F) -> Result<String, CrossroadsError>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<bool, CrossroadsError>>,
+{
+    match conflict {
+        ConflictPolicy::Replace => Ok(name.to_string()),
+        ConflictPolicy::Fail => {
+            if exists(name.to_string()).await? {
+                Err(CrossroadsError::AlreadyExists(name.to_string()))
+            } else {
+                Ok(name.to_string())
+            }
+        }
+        ConflictPolicy::Rename => {
+            let mut candidate = name.to_string();
+            let mut number = 1;
+
+            while exists(candidate.clone()).await? {
+                candidate = numbered_name(name, number);
+                number += 1;
+            }
+
+            Ok(candidate)
+        }
+    }
+}
+
+/// Inserts the number before the extension, so that `report.txt` becomes `report (1).txt`.
+fn numbered_name(name: &str, number: usize) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{} ({}).{}", stem, number, extension),
+        _ => format!("{} ({})", name, number),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::CrossroadsError;
+    use crate::interfaces::filesystem::ConflictPolicy;
+
+    use super::{numbered_name, resolve_name};
+
+    #[test]
+    fn numbers_names_before_the_extension() {
+        assert_eq!(numbered_name("report.txt", 1), "report (1).txt");
+        assert_eq!(numbered_name("archive.tar.gz", 2), "archive.tar (2).gz");
+        assert_eq!(numbered_name(".profile", 1), ".profile (1)");
+        assert_eq!(numbered_name("folder", 3), "folder (3)");
+    }
+
+    #[tokio::test]
+    async fn resolves_names_according_to_the_policy() {
+        let taken = ["report.txt", "report (1).txt"];
+        let exists = |name: String| async move { Ok(taken.contains(&name.as_str())) };
+
+        assert_eq!(resolve_name("report.txt", ConflictPolicy::Rename, exists).await.unwrap(), "report (2).txt");
+        assert_eq!(resolve_name("report.txt", ConflictPolicy::Replace, exists).await.unwrap(), "report.txt");
+        assert_eq!(resolve_name("notes.txt", ConflictPolicy::Fail, exists).await.unwrap(), "notes.txt");
+        assert!(matches!(resolve_name("report.txt", ConflictPolicy::Fail, exists).await, Err(CrossroadsError::AlreadyExists(_))));
+    }
+}
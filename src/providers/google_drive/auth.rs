@@ -5,9 +5,14 @@ use std::future::Future;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use drive3::oauth2::storage::TokenInfo;
-use drive3::{DriveHub, oauth2, hyper, hyper_rustls};
+use drive3::{DriveHub, oauth2, hyper, hyper_rustls, api::Scope};
 use drive3::oauth2::authenticator_delegate::{DefaultInstalledFlowDelegate, InstalledFlowDelegate};
 
+use crate::error::CrossroadsError;
+use crate::providers::path_cache::PathCache;
+
+use super::conversion::ConversionOptions;
+
 use super::Token;
 use super::{GoogleDrive, token::TokenStorageStrategy};
 
@@ -31,8 +36,8 @@ impl InstalledFlowDelegate for InstalledFlowBrowserDelegate {
 }
 
 impl GoogleDrive {
-    pub async fn new(client_secret: String, tokens: HashMap<String, TokenInfo>) -> Result<GoogleDrive, Box<dyn std::error::Error>> {
-        let secret = oauth2::parse_application_secret(client_secret.as_str()).unwrap();
+    pub async fn new(client_secret: String, tokens: HashMap<String, TokenInfo>) -> Result<GoogleDrive, CrossroadsError> {
+        let secret = oauth2::parse_application_secret(client_secret.as_str())?;
 
         let mt_tokens = Arc::new(Mutex::new(tokens));
 
@@ -41,17 +46,29 @@ impl GoogleDrive {
         let auth = oauth2::InstalledFlowAuthenticator::builder(
             secret,
             oauth2::InstalledFlowReturnMethod::HTTPRedirect
-        ).with_storage(storage).flow_delegate(Box::new(InstalledFlowBrowserDelegate)).build().await.unwrap();
+        ).with_storage(storage).flow_delegate(Box::new(InstalledFlowBrowserDelegate)).build().await?;
 
         let hub = DriveHub::new(
             hyper::Client::builder().build(
-                hyper_rustls::HttpsConnectorBuilder::new().with_native_roots().https_or_http().enable_http1().enable_http2().build()),
+                hyper_rustls::HttpsConnectorBuilder::new().with_native_roots()?.https_or_http().enable_http1().enable_http2().build()),
                 auth);
 
-        Ok(GoogleDrive { hub, tokens: mt_tokens.clone() })
+        Ok(GoogleDrive { hub, tokens: mt_tokens.clone(), paths: Arc::new(PathCache::default()), conversion: ConversionOptions::default() })
+    }
+
+    pub fn with_conversion(self, conversion: ConversionOptions) -> GoogleDrive {
+        GoogleDrive { conversion, ..self }
     }
     
     pub fn tokens_map(&self) -> HashMap<String, Token> {
         (*self.tokens.as_ref().lock().unwrap()).clone()
     }
+
+    /// Token for requests the hub does not cover, such as resumable uploads.
+    pub(crate) async fn access_token(&self) -> Result<String, CrossroadsError> {
+        let token = self.hub.auth.get_token(&[Scope::Full.as_ref()]).await
+            .map_err(|_| CrossroadsError::AuthenticationRequired)?;
+
+        token.ok_or(CrossroadsError::AuthenticationRequired)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+
+pub const DOCUMENT_MIME_TYPE: &str = "application/vnd.google-apps.document";
+pub const SPREADSHEET_MIME_TYPE: &str = "application/vnd.google-apps.spreadsheet";
+pub const PRESENTATION_MIME_TYPE: &str = "application/vnd.google-apps.presentation";
+pub const DRAWING_MIME_TYPE: &str = "application/vnd.google-apps.drawing";
+/// Prefix of the types of Google native files, which have no content to download.
+pub(super) const NATIVE_MIME_TYPE_PREFIX: &str = "application/vnd.google-apps.";
+
+/// Format a Google native file is exported to when it is read.
+#[derive(Serialize, Deserialize, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum ExportFormat {
+    Docx,
+    Xlsx,
+    Pptx,
+    Pdf,
+    Odt,
+    Ods,
+    Odp,
+    PlainText,
+    Csv,
+    Png,
+}
+
+impl ExportFormat {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Docx => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            ExportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ExportFormat::Pptx => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+            ExportFormat::Pdf => "application/pdf",
+            ExportFormat::Odt => "application/vnd.oasis.opendocument.text",
+            ExportFormat::Ods => "application/vnd.oasis.opendocument.spreadsheet",
+            ExportFormat::Odp => "application/vnd.oasis.opendocument.presentation",
+            ExportFormat::PlainText => "text/plain",
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Png => "image/png",
+        }
+    }
+}
+
+/// How Google native files are converted, as they can only be read through an export.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ConversionOptions {
+    /// Export format of each Google native type. Files of other native types, such as forms,
+    /// cannot be read.
+    pub export_formats: HashMap<String, ExportFormat>,
+    /// Whether Office files are turned into their Google counterpart when created, so that the
+    /// content written to them is converted.
+    pub import_office_files: bool,
+}
+
+impl Default for ConversionOptions {
+    fn default() -> Self {
+        ConversionOptions {
+            export_formats: HashMap::from([
+                (DOCUMENT_MIME_TYPE.to_string(), ExportFormat::Docx),
+                (SPREADSHEET_MIME_TYPE.to_string(), ExportFormat::Xlsx),
+                (PRESENTATION_MIME_TYPE.to_string(), ExportFormat::Pptx),
+                (DRAWING_MIME_TYPE.to_string(), ExportFormat::Pdf),
+            ]),
+            import_office_files: false,
+        }
+    }
+}
+
+impl ConversionOptions {
+    /// Type the content of a file of `mime_type` is read as, `None` when it is not a native file.
+    pub fn export_mime_type(&self, mime_type: &str) -> Option<&'static str> {
+        self.export_formats.get(mime_type).map(ExportFormat::mime_type)
+    }
+}
+
+/// Google native type Office files of `mime_type` are imported as.
+pub(super) fn import_mime_type(mime_type: &str) -> Option<&'static str> {
+    match mime_type {
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" | "application/msword" => Some(DOCUMENT_MIME_TYPE),
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" | "application/vnd.ms-excel" => Some(SPREADSHEET_MIME_TYPE),
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation" | "application/vnd.ms-powerpoint" => Some(PRESENTATION_MIME_TYPE),
+        _ => None,
+    }
+}
+
+/// Type of the content uploaded to a Google native file, which Drive converts from.
+pub(super) fn upload_mime_type(native_mime_type: &str) -> Option<&'static str> {
+    match native_mime_type {
+        DOCUMENT_MIME_TYPE => Some(ExportFormat::Docx.mime_type()),
+        SPREADSHEET_MIME_TYPE => Some(ExportFormat::Xlsx.mime_type()),
+        PRESENTATION_MIME_TYPE => Some(ExportFormat::Pptx.mime_type()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{import_mime_type, upload_mime_type, ConversionOptions, ExportFormat, DOCUMENT_MIME_TYPE};
+
+    #[test]
+    fn conversion_formats_round_trip() {
+        let options = ConversionOptions::default();
+        assert_eq!(options.export_mime_type(DOCUMENT_MIME_TYPE), Some(ExportFormat::Docx.mime_type()));
+        assert_eq!(options.export_mime_type("application/vnd.google-apps.form"), None);
+        assert_eq!(options.export_mime_type("application/pdf"), None);
+
+        let docx = ExportFormat::Docx.mime_type();
+        assert_eq!(import_mime_type(docx), Some(DOCUMENT_MIME_TYPE));
+        assert_eq!(upload_mime_type(DOCUMENT_MIME_TYPE), Some(docx));
+        assert_eq!(import_mime_type("application/pdf"), None);
+    }
+}
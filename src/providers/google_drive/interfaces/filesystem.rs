@@ -1,84 +1,547 @@
 extern crate google_drive3 as drive3;
+use std::collections::BTreeMap;
+
 use async_trait::async_trait;
-use drive3::api::File as GoogleDriveFile;
+use chrono::{DateTime, Utc};
+use drive3::api::{File as GoogleDriveFile, Scope};
+use drive3::hyper::{body::HttpBody, header::LOCATION};
+use futures::{future::BoxFuture, stream, TryStreamExt};
+
+use crate::error::CrossroadsError;
+use crate::mime;
+use crate::interfaces::filesystem::{FileSystem, ObjectId, File, Metadata, MetadataField, MetadataUpdate, FileType, ByteStream, FileRange, DirectoryPage, ConflictPolicy, DeleteFailure, Checksums, self};
+use crate::providers::{chunked, conflict, path_cache, range};
 
-use crate::interfaces::filesystem::{FileSystem, ObjectId, File, Metadata, FileType, self};
+use super::super::conversion::{self, NATIVE_MIME_TYPE_PREFIX};
+use super::super::{quote, GoogleDrive};
 
-use super::super::GoogleDrive;
+const FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
+/// Fields of a file read into `Metadata`, the API only returns a few of them by default.
+pub(super) const FILE_FIELDS: &str = "id,name,mimeType,createdTime,modifiedTime,size,version,md5Checksum,sha1Checksum,sha256Checksum,properties";
 
 impl From<google_drive3::api::File> for filesystem::File {
     fn from(file: GoogleDriveFile) -> Self {
-        let mime_type = file.mime_type.clone();
-        let id;
-
-        if mime_type.clone().unwrap_or_default() == "application/vnd.google-apps.folder" {
-            id = ObjectId::directory(file.id.unwrap());
+        let mime_type = file.mime_type.clone().unwrap_or_default();
+        // Google native files take no space and have no size, only their exports do.
+        let native = mime_type.starts_with(NATIVE_MIME_TYPE_PREFIX);
+        let id = if mime_type == FOLDER_MIME_TYPE {
+            ObjectId::directory(file.id.unwrap())
         } else {
-            id = ObjectId::new(file.id.unwrap(), FileType::File);
-        }
+            ObjectId::new(file.id.unwrap(), FileType::File)
+        };
         File {
             id,
             name: file.name.unwrap(),
             metadata: Some(Metadata {
                 mime_type: file.mime_type,
+                // Depends on the options of the drive, see `GoogleDrive::file_from`.
+                export_mime_type: None,
                 created_at: file.created_time,
                 modified_at: file.modified_time,
                 meta_changed_at: None,
                 accessed_at: None,
-                size: Some(file.size.unwrap_or(0).unsigned_abs()),
+                size: if native { None } else { Some(file.size.unwrap_or(0).unsigned_abs()) },
                 open_path: None,
                 owner: None,
-                permissions: None
+                permissions: None,
+                version: file.version.map(|version| version.to_string()),
+                checksums: Some(Checksums {
+                    md5: file.md5_checksum,
+                    sha1: file.sha1_checksum,
+                    sha256: file.sha256_checksum,
+                    quick_xor: None,
+                }),
+                // Left out of the response when there are none.
+                properties: Some(file.properties.unwrap_or_default().into_iter().collect()),
             })
         }
     }
 }
 
+/// Query of the entries of `parent`, trashed files keeping their parents.
+fn children_query(parent: &str) -> String {
+    format!("'{}' in parents and trashed = false", parent)
+}
+
+impl GoogleDrive {
+    /// Converts `file`, with the type Google native files are exported to.
+    pub(super) fn file_from(&self, file: GoogleDriveFile) -> File {
+        let mut file = File::from(file);
+
+        if let Some(metadata) = file.metadata.as_mut() {
+            metadata.export_mime_type = metadata.mime_type.as_deref()
+                .and_then(|mime_type| self.conversion.export_mime_type(mime_type))
+                .map(str::to_string);
+        }
+
+        file
+    }
+
+    async fn mime_type_of(&self, object_id: &ObjectId) -> Result<String, CrossroadsError> {
+        let (_, file) = self.hub.files().get(object_id.as_str())
+            .param("fields", "mimeType")
+            .add_scope(Scope::Full)
+            .doit().await?;
+
+        Ok(file.mime_type.unwrap_or_default())
+    }
+
+    /// Type the content of `object_id` is exported to, `None` when it can be downloaded as it is.
+    /// Native files without an export format cannot be read.
+    async fn export_mime_type(&self, object_id: &ObjectId) -> Result<Option<&'static str>, CrossroadsError> {
+        let mime_type = self.mime_type_of(object_id).await?;
+
+        if !mime_type.starts_with(NATIVE_MIME_TYPE_PREFIX) || mime_type == FOLDER_MIME_TYPE {
+            return Ok(None)
+        }
+
+        match self.conversion.export_mime_type(&mime_type) {
+            Some(export_mime_type) => Ok(Some(export_mime_type)),
+            None => Err(CrossroadsError::Unsupported(format!("Google Drive export of {}", mime_type))),
+        }
+    }
+
+    /// Entries of `parent` called `name`, as several entries may share a name.
+    async fn children_named(&self, parent: &str, name: &str) -> Result<Vec<File>, CrossroadsError> {
+        let (_, list) = self.hub.files().list()
+            .q(format!("{} and name = {}", children_query(parent), quote(name)).as_str())
+            .param("fields", &format!("files({})", FILE_FIELDS))
+            .doit().await?;
+
+        Ok(list.files.unwrap_or_default().into_iter().map(|file| self.file_from(file)).collect())
+    }
+
+    /// Whether `folder` is `ancestor` or one of its descendants.
+    pub(super) async fn is_within(&self, folder: &str, ancestor: &str) -> Result<bool, CrossroadsError> {
+        let mut current = folder.to_string();
+
+        loop {
+            if current == ancestor {
+                return Ok(true)
+            }
+
+            let (_, file) = self.hub.files().get(&current).param("fields", "parents").doit().await?;
+
+            match file.parents.and_then(|parents| parents.into_iter().next()) {
+                Some(parent) => current = parent,
+                None => return Ok(false),
+            }
+        }
+    }
+
+    /// Creates an entry without content, which is a folder when `mime_type` is the folder one.
+    async fn create_entry(&self, parent: &str, name: &str, mime_type: Option<&str>) -> Result<String, CrossroadsError> {
+        let mut request = serde_json::json!({ "name": name, "parents": [parent] });
+
+        if let Some(mime_type) = mime_type {
+            request["mimeType"] = mime_type.into();
+        }
+
+        let response = reqwest::Client::new()
+            .post("https://www.googleapis.com/drive/v3/files")
+            .bearer_auth(self.access_token().await?)
+            .json(&request)
+            .send().await?
+            .error_for_status()?;
+
+        let entry: GoogleDriveFile = response.json().await?;
+
+        entry.id.ok_or_else(|| CrossroadsError::Provider("Google Drive did not return the id of the new entry".into()))
+    }
+
+    /// Sets the public `properties` of the file, which every app can read unlike `appProperties`.
+    /// A `null` value removes the property, which the typed client cannot send.
+    async fn update_properties(&self, object_id: &ObjectId, properties: serde_json::Map<String, serde_json::Value>) -> Result<(), CrossroadsError> {
+        reqwest::Client::new()
+            .patch(format!("https://www.googleapis.com/drive/v3/files/{}?fields=id", object_id))
+            .bearer_auth(self.access_token().await?)
+            .json(&serde_json::json!({ "properties": properties }))
+            .send().await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// `files.copy` only accepts files, so folders are recreated and their content copied one entry at a time.
+    fn copy_into(&self, object_id: ObjectId, parent: String, name: String) -> BoxFuture<'_, Result<ObjectId, CrossroadsError>> {
+        Box::pin(async move {
+            if !object_id.is_directory() {
+                let request = GoogleDriveFile { name: Some(name), parents: Some(vec![parent]), ..Default::default() };
+                let (_, file) = self.hub.files().copy(request, object_id.as_str()).add_scope(Scope::Full).doit().await?;
+
+                let id = file.id.ok_or_else(|| CrossroadsError::Provider("Google Drive did not return the id of the copy".into()))?;
+                return Ok(ObjectId::new(id, object_id.file_type()))
+            }
+
+            let folder = self.create_entry(&parent, &name, Some(FOLDER_MIME_TYPE)).await?;
+            let mut children = self.read_directory_stream(object_id);
+
+            while let Some(child) = children.try_next().await? {
+                self.copy_into(child.id, folder.clone(), child.name).await?;
+            }
+
+            Ok(ObjectId::directory(folder))
+        })
+    }
+}
+
 #[async_trait]
 impl FileSystem for GoogleDrive {
-    async fn read_file(&self, object_id: ObjectId) -> Result<Vec<u8>, Box<dyn std::error::Error>>
-    {
-        todo!()
+    /// Google native files are exported, in the format of `ConversionOptions`. Exports are
+    /// limited to 10 MB.
+    async fn read_file_stream(&self, object_id: ObjectId) -> Result<ByteStream, CrossroadsError> {
+        if let Some(export_mime_type) = self.export_mime_type(&object_id).await? {
+            let response = self.hub.files().export(object_id.as_str(), export_mime_type)
+                .add_scope(Scope::Full)
+                .doit().await?;
+
+            let content = stream::unfold(response.into_body(), |mut body| async move {
+                let chunk = body.data().await?;
+                Some((chunk.map_err(|error| CrossroadsError::Network(Box::new(error))), body))
+            });
+
+            return Ok(Box::pin(content))
+        }
+
+        let (response, _) = self.hub.files().get(object_id.as_str())
+            .param("alt", "media")
+            .add_scope(Scope::Full)
+            .doit().await?;
+
+        let content = stream::unfold(response.into_body(), |mut body| async move {
+            let chunk = body.data().await?;
+            Some((chunk.map_err(|error| CrossroadsError::Network(Box::new(error))), body))
+        });
+
+        Ok(Box::pin(content))
+    }
+
+    async fn write_file_stream(&self, object_id: ObjectId, content: ByteStream, size: Option<u64>) -> Result<(), CrossroadsError> {
+        let client = reqwest::Client::new();
+        let token = self.access_token().await?;
+
+        let mut request = client.patch(format!("https://www.googleapis.com/upload/drive/v3/files/{}?uploadType=resumable", object_id))
+            .bearer_auth(&token)
+            .json(&serde_json::json!({}));
+
+        // Content written to a Google native file is converted from its Office counterpart.
+        if self.conversion.import_office_files {
+            if let Some(upload_mime_type) = conversion::upload_mime_type(&self.mime_type_of(&object_id).await?) {
+                request = request.header("X-Upload-Content-Type", upload_mime_type);
+            }
+        }
+
+        if let Some(size) = size {
+            request = request.header("X-Upload-Content-Length", size);
+        }
+
+        let response = request.send().await?.error_for_status()?;
+
+        let session_url = response.headers().get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| CrossroadsError::Provider("Google Drive did not return an upload session".into()))?
+            .to_string();
+
+        // Every chunk but the last one must be a multiple of 256 KiB.
+        let mut chunks = chunked::rechunk(content, 262_144 * 32);
+        let mut offset: u64 = 0;
+        let mut next_chunk = chunks.try_next().await?;
+
+        if next_chunk.is_none() {
+            client.put(&session_url).header("Content-Range", "bytes */0").bearer_auth(&token)
+                .send().await?.error_for_status()?;
+        }
+
+        while let Some(chunk) = next_chunk {
+            next_chunk = chunks.try_next().await?;
+
+            let chunk_len = chunk.len() as u64;
+            // The total is only known once the stream is exhausted.
+            let total = if next_chunk.is_none() { (offset + chunk_len).to_string() } else { "*".to_string() };
+
+            client.put(&session_url)
+                .header("Content-Length", chunk_len)
+                .header("Content-Range", format!("bytes {}-{}/{}", offset, offset + chunk_len - 1, total))
+                .body(chunk)
+                .bearer_auth(&token)
+                .send().await?.error_for_status()?;
+
+            offset += chunk_len;
+        }
+
+        Ok(())
+    }
+
+    /// Drive has no conditional upload, so the version is compared right before uploading
+    /// and a write landing in between goes unnoticed.
+    async fn write_file_stream_if_match(&self, object_id: ObjectId, content: ByteStream, size: Option<u64>, version: String) -> Result<(), CrossroadsError> {
+        let (_, file) = self.hub.files().get(object_id.as_str())
+            .param("fields", "version")
+            .add_scope(Scope::Full)
+            .doit().await?;
+
+        if file.version.map(|version| version.to_string()) != Some(version.clone()) {
+            return Err(CrossroadsError::Conflict(format!("{} changed since version {}", object_id, version)))
+        }
+
+        self.write_file_stream(object_id, content, size).await
     }
 
-    async fn write_file(&self, object_id: ObjectId, content: Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
-        todo!()
+    /// Exports of Google native files are sliced here, as they have no ranges.
+    async fn read_file_range(&self, object_id: ObjectId, offset: u64, length: u64) -> Result<FileRange, CrossroadsError> {
+        let url = match self.export_mime_type(&object_id).await? {
+            Some(export_mime_type) => format!("https://www.googleapis.com/drive/v3/files/{}/export?mimeType={}", object_id, export_mime_type),
+            None => format!("https://www.googleapis.com/drive/v3/files/{}?alt=media", object_id),
+        };
+
+        let response = reqwest::Client::new()
+            .get(url)
+            .header(reqwest::header::RANGE, range::range_header(offset, length))
+            .bearer_auth(self.access_token().await?)
+            .send().await?;
+
+        range::range_from_response(response, offset, length).await
     }
 
-    async fn delete(&self, object_id: ObjectId) -> Result<(), Box<dyn std::error::Error>> {
-        todo!()
+    async fn delete(&self, object_id: ObjectId) -> Result<(), CrossroadsError> {
+        self.hub.files().delete(object_id.as_str()).add_scope(Scope::Full).doit().await?;
+        self.paths.invalidate(&object_id);
+        Ok(())
     }
 
-    async fn rename(&self, object_id: ObjectId, new_name: String) -> Result<ObjectId, Box<dyn std::error::Error>> {
-        todo!()
+    async fn delete_recursive(&self, object_id: ObjectId) -> Result<Vec<DeleteFailure>, CrossroadsError> {
+        // Folders are deleted along with their content in a single call.
+        self.delete(object_id).await.map(|_| vec![])
     }
 
-    async fn move_to(&self, object_id: ObjectId, new_parent_id: ObjectId) -> Result<ObjectId, Box<dyn std::error::Error>> {
-        todo!()
+    async fn rename(&self, _object_id: ObjectId, _new_name: String) -> Result<ObjectId, CrossroadsError> {
+        Err(CrossroadsError::unsupported("Google Drive rename"))
     }
 
-    async fn create(&self, parent_id: ObjectId, file: File) -> Result<(), Box<dyn std::error::Error>> {
-        todo!()
+    async fn move_to(&self, _object_id: ObjectId, _new_parent_id: ObjectId) -> Result<ObjectId, CrossroadsError> {
+        Err(CrossroadsError::unsupported("Google Drive move_to"))
     }
 
-    async fn read_directory(&self, object_id: ObjectId) -> Result<Vec<File>, Box<dyn std::error::Error>> {
-        let id = if object_id.to_string() == "".to_string() {"root".to_string()} else {object_id.to_string()};
-        let response = self.hub.files().list().q(format!("'{}' in parents", id).as_str()).doit().await?;
-        
-        let files: Vec<File> = response.1.files.unwrap().iter().map(|file| file.to_owned().into()).collect();
-        
-        Ok(files)
+    async fn copy(&self, object_id: ObjectId, new_parent_id: ObjectId, name: &str, conflict: ConflictPolicy) -> Result<ObjectId, CrossroadsError> {
+        let parent = if new_parent_id.as_str().is_empty() {"root".to_string()} else {new_parent_id.to_string()};
+
+        if object_id.is_directory() && self.is_within(&parent, object_id.as_str()).await? {
+            return Err(CrossroadsError::InvalidInput(format!("Cannot copy {} into itself", object_id)))
+        }
+
+        let name = conflict::resolve_name(name, conflict, |candidate| {
+            let parent = parent.clone();
+            async move { Ok(!self.children_named(&parent, &candidate).await?.is_empty()) }
+        }).await?;
+
+        let existing = match conflict {
+            ConflictPolicy::Replace => self.children_named(&parent, &name).await?,
+            _ => vec![],
+        };
+
+        if existing.iter().any(|file| file.id.as_str() == object_id.as_str()) {
+            return Err(CrossroadsError::InvalidInput(format!("Cannot copy {} into itself", object_id)))
+        }
+
+        // Names need not be unique, so the entries replaced are only deleted once the copy succeeded.
+        let copy = self.copy_into(object_id, parent, name).await?;
+
+        for file in existing {
+            self.delete(file.id).await?;
+        }
+
+        Ok(copy)
+    }
+
+    async fn create(&self, parent_id: ObjectId, file: File) -> Result<ObjectId, CrossroadsError> {
+        let parent = if parent_id.as_str().is_empty() {"root".to_string()} else {parent_id.to_string()};
+
+        let mime_type = if file.id.is_directory() {
+            Some(FOLDER_MIME_TYPE.to_string())
+        } else {
+            let mime_type = file.metadata.and_then(|metadata| metadata.mime_type).or_else(|| mime::from_name(&file.name));
+
+            // Google native types turn the upload of the content into a conversion.
+            match mime_type.as_deref().and_then(conversion::import_mime_type) {
+                Some(native_mime_type) if self.conversion.import_office_files => Some(native_mime_type.to_string()),
+                _ => mime_type.filter(|mime_type| !mime_type.starts_with(NATIVE_MIME_TYPE_PREFIX)),
+            }
+        };
+
+        let id = self.create_entry(&parent, &file.name, mime_type.as_deref()).await?;
+
+        Ok(ObjectId::new(id, file.id.file_type()))
     }
 
-    async fn get_metadata(&self, object_id: ObjectId) -> Result<Metadata, Box<dyn std::error::Error>> {
-        todo!()
+    async fn read_directory_page(&self, object_id: ObjectId, page_size: usize, page_token: Option<String>) -> Result<DirectoryPage, CrossroadsError> {
+        let id = if object_id.as_str().is_empty() {"root".to_string()} else {object_id.to_string()};
+
+        // The API accepts between 1 and 1000 entries per page.
+        let mut request = self.hub.files().list()
+            .q(children_query(&id).as_str())
+            .param("fields", &format!("nextPageToken,files({})", FILE_FIELDS))
+            .page_size(page_size.clamp(1, 1000) as i32);
+
+        if let Some(page_token) = page_token {
+            request = request.page_token(&page_token);
+        }
+
+        let response = request.doit().await?;
+
+        let files: Vec<File> = response.1.files.unwrap_or_default().into_iter().map(|file| self.file_from(file)).collect();
+
+        Ok(DirectoryPage { files, next_page_token: response.1.next_page_token })
     }
 
-    async fn read_link(&self, object_id: ObjectId) -> Result<ObjectId, Box<dyn std::error::Error>> {
-        todo!()
+    async fn get_metadata(&self, object_id: ObjectId) -> Result<Metadata, CrossroadsError> {
+        let (_, file) = self.hub.files().get(object_id.as_str())
+            .param("fields", FILE_FIELDS)
+            .add_scope(Scope::Full)
+            .doit().await?;
+
+        Ok(self.file_from(file).metadata.unwrap_or_default())
     }
 
-    async fn create_link(&self, parent_id: ObjectId, name: &str, link_id: ObjectId) -> Result<ObjectId, Box<dyn std::error::Error>> {
-        todo!()
+    /// Names are looked up one folder at a time from the deepest folder already resolved. When
+    /// several entries of a folder share a name, the first one returned is picked.
+    async fn resolve_path(&self, path: &str) -> Result<ObjectId, CrossroadsError> {
+        let path = path_cache::normalize(path);
+        let names: Vec<&str> = path.split('/').filter(|name| !name.is_empty()).collect();
+
+        let (mut resolved, mut current) = (1..=names.len()).rev()
+            .find_map(|length| self.paths.get(&names[..length].join("/")).map(|id| (length, id)))
+            .unwrap_or((0, ObjectId::root()));
+
+        while resolved < names.len() {
+            let parent = if current.as_str().is_empty() {"root".to_string()} else {current.to_string()};
+
+            current = self.children_named(&parent, names[resolved]).await?
+                .into_iter().next()
+                .ok_or_else(|| CrossroadsError::NotFound(path.clone()))?
+                .id;
+
+            resolved += 1;
+            self.paths.insert(names[..resolved].join("/"), current.clone());
+        }
+
+        Ok(current)
     }
-}
\ No newline at end of file
+
+    async fn path_of(&self, object_id: ObjectId) -> Result<String, CrossroadsError> {
+        if object_id.as_str().is_empty() {
+            return Ok(String::new())
+        }
+
+        if let Some(path) = self.paths.path_of(&object_id) {
+            return Ok(path)
+        }
+
+        let mut names = vec![];
+        let mut current = object_id.to_string();
+
+        // The root folder is the only one without a parent, and its name is not part of paths.
+        loop {
+            let (_, file) = self.hub.files().get(&current).param("fields", "name,parents").doit().await?;
+
+            match file.parents.and_then(|parents| parents.into_iter().next()) {
+                Some(parent) => {
+                    names.push(file.name.unwrap_or_default());
+                    current = parent;
+                },
+                None => break,
+            }
+        }
+
+        names.reverse();
+        let path = names.join("/");
+        self.paths.insert(path.clone(), object_id);
+
+        Ok(path)
+    }
+
+    /// Drive recognizes the content itself, and Google Docs have none to read.
+    async fn detect_mime_type(&self, object_id: ObjectId) -> Result<Option<String>, CrossroadsError> {
+        Ok(self.get_metadata(object_id).await?.mime_type)
+    }
+
+    async fn get_properties(&self, object_id: ObjectId) -> Result<BTreeMap<String, String>, CrossroadsError> {
+        let (_, file) = self.hub.files().get(object_id.as_str())
+            .param("fields", "properties")
+            .add_scope(Scope::Full)
+            .doit().await?;
+
+        Ok(file.properties.unwrap_or_default().into_iter().collect())
+    }
+
+    /// Drive limits the name and value of each property to 124 bytes together.
+    async fn set_properties(&self, object_id: ObjectId, properties: BTreeMap<String, String>) -> Result<(), CrossroadsError> {
+        let properties = properties.into_iter().map(|(name, value)| (name, value.into())).collect();
+        self.update_properties(&object_id, properties).await
+    }
+
+    async fn remove_properties(&self, object_id: ObjectId, names: &[String]) -> Result<(), CrossroadsError> {
+        let properties = names.iter().map(|name| (name.clone(), serde_json::Value::Null)).collect();
+        self.update_properties(&object_id, properties).await
+    }
+
+    async fn set_modified_at(&self, object_id: ObjectId, modified_at: DateTime<Utc>) -> Result<(), CrossroadsError> {
+        self.set_metadata(object_id, MetadataUpdate { modified_at: Some(modified_at), ..Default::default() }).await
+    }
+
+    /// Only `modifiedTime` can be changed, creation times being set when files are created.
+    async fn set_metadata(&self, object_id: ObjectId, update: MetadataUpdate) -> Result<(), CrossroadsError> {
+        update.check_supported("Google Drive", &[MetadataField::ModifiedAt])?;
+
+        let Some(modified_at) = update.modified_at else {
+            return Ok(())
+        };
+
+        let request = GoogleDriveFile { modified_time: Some(modified_at), ..Default::default() };
+
+        self.hub.files().update(request, object_id.as_str())
+            .param("fields", "id")
+            .add_scope(Scope::Full)
+            .doit_without_upload().await?;
+
+        Ok(())
+    }
+
+    async fn read_link(&self, _object_id: ObjectId) -> Result<ObjectId, CrossroadsError> {
+        Err(CrossroadsError::unsupported("Google Drive read_link"))
+    }
+
+    async fn create_link(&self, _parent_id: ObjectId, _name: &str, _link_id: ObjectId) -> Result<ObjectId, CrossroadsError> {
+        Err(CrossroadsError::unsupported("Google Drive create_link"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use google_drive3::api::File as GoogleDriveFile;
+
+    use crate::interfaces::filesystem::File;
+
+    use super::children_query;
+
+    #[test]
+    fn children_query_leaves_trashed_files_out() {
+        assert_eq!(children_query("root"), "'root' in parents and trashed = false");
+    }
+
+    #[test]
+    fn native_files_have_no_size() {
+        let file = |mime_type: &str, size| GoogleDriveFile {
+            id: Some("id".to_string()),
+            name: Some("name".to_string()),
+            mime_type: Some(mime_type.to_string()),
+            size,
+            ..Default::default()
+        };
+
+        let document = File::from(file("application/vnd.google-apps.document", None));
+        assert_eq!(document.metadata.unwrap().size, None);
+
+        let pdf = File::from(file("application/pdf", Some(1024)));
+        assert_eq!(pdf.metadata.unwrap().size, Some(1024));
+    }
+}
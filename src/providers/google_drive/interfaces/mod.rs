@@ -1,2 +1,8 @@
 pub mod filesystem;
-pub mod trash;
\ No newline at end of file
+pub mod search;
+pub mod trash;pub mod watch;
+pub mod versions;
+pub mod sharing;
+pub mod quota;
+pub mod preview;
+pub mod locking;
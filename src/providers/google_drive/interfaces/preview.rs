@@ -0,0 +1,57 @@
+extern crate google_drive3 as drive3;
+use async_trait::async_trait;
+use drive3::api::Scope;
+use reqwest::header::CONTENT_TYPE;
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::ObjectId;
+use crate::interfaces::preview::{Preview, Thumbnail, ThumbnailSize};
+use crate::providers::google_drive::GoogleDrive;
+use crate::providers::thumbnail;
+
+/// Size of the thumbnail in `thumbnailLink`, which ends with `=s` and the size in pixels.
+fn sized_link(link: &str, pixels: u32) -> String {
+    match link.rsplit_once("=s") {
+        Some((base, size)) if size.chars().all(|character| character.is_ascii_digit()) => format!("{}=s{}", base, pixels),
+        _ => link.to_string(),
+    }
+}
+
+/// Thumbnails are rendered by Drive for images, videos and documents, including Google Docs.
+#[async_trait]
+impl Preview for GoogleDrive {
+    async fn thumbnail(&self, object_id: ObjectId, size: ThumbnailSize) -> Result<Thumbnail, CrossroadsError> {
+        let (_, file) = self.hub.files().get(object_id.as_str())
+            .param("fields", "thumbnailLink")
+            .add_scope(Scope::Full)
+            .doit().await?;
+
+        let link = file.thumbnail_link
+            .ok_or_else(|| CrossroadsError::Unsupported(format!("Google Drive has no thumbnail of {}", object_id)))?;
+
+        // The link is short-lived, and only readable with the token for files that are not public.
+        let response = reqwest::Client::new()
+            .get(sized_link(&link, size.pixels()))
+            .bearer_auth(self.access_token().await?)
+            .send().await?
+            .error_for_status()?;
+
+        let mime_type = response.headers().get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("image/jpeg")
+            .to_string();
+
+        thumbnail::from_image(response.bytes().await?.to_vec(), mime_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sized_link;
+
+    #[test]
+    fn sized_link_replaces_the_size() {
+        assert_eq!(sized_link("https://lh3.googleusercontent.com/abc=s220", 512), "https://lh3.googleusercontent.com/abc=s512");
+        assert_eq!(sized_link("https://lh3.googleusercontent.com/abc", 512), "https://lh3.googleusercontent.com/abc");
+    }
+}
@@ -0,0 +1,32 @@
+extern crate google_drive3 as drive3;
+use async_trait::async_trait;
+use drive3::api::Scope;
+
+use crate::error::CrossroadsError;
+use crate::interfaces::quota::{Quota, StorageQuota};
+use crate::providers::google_drive::GoogleDrive;
+
+/// The quota is shared with the other Google services, such as Gmail and Photos, which count
+/// in `used`. Unlimited accounts have no total.
+#[async_trait]
+impl Quota for GoogleDrive {
+    async fn quota(&self) -> Result<StorageQuota, CrossroadsError> {
+        let (_, about) = self.hub.about().get()
+            .param("fields", "storageQuota")
+            .add_scope(Scope::Full)
+            .doit().await?;
+
+        let quota = about.storage_quota.unwrap_or_default();
+        let bytes = |amount: Option<i64>| amount.map(i64::unsigned_abs);
+
+        let total = bytes(quota.limit);
+        let used = bytes(quota.usage);
+
+        Ok(StorageQuota {
+            total,
+            used,
+            free: total.zip(used).map(|(total, used)| total.saturating_sub(used)),
+            trash: bytes(quota.usage_in_drive_trash),
+        })
+    }
+}
@@ -0,0 +1,104 @@
+extern crate google_drive3 as drive3;
+use async_trait::async_trait;
+use chrono::SecondsFormat;
+use drive3::api::Scope;
+use futures::{future, stream, TryStreamExt};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{File, FileStream};
+use crate::interfaces::search::{Search, SearchQuery};
+use crate::providers::google_drive::{quote, GoogleDrive};
+
+use super::filesystem::FILE_FIELDS;
+
+/// Most files the API returns per page.
+const SEARCH_PAGE_SIZE: i32 = 1000;
+
+/// Criteria of `query` in the query language of `files.list`. Drive matches names on the
+/// prefixes of their words, so the name is only used to narrow the search and checked here.
+fn drive_query(query: &SearchQuery) -> String {
+    let mut conditions = vec!["trashed = false".to_string()];
+
+    if let Some(literal) = query.name_literal() {
+        conditions.push(format!("name contains {}", quote(&literal)));
+    }
+
+    if let Some(mime_type) = &query.mime_type {
+        conditions.push(format!("mimeType = {}", quote(mime_type)));
+    }
+
+    if let Some(after) = query.modified_after {
+        conditions.push(format!("modifiedTime > '{}'", after.to_rfc3339_opts(SecondsFormat::Secs, true)));
+    }
+
+    if let Some(before) = query.modified_before {
+        conditions.push(format!("modifiedTime < '{}'", before.to_rfc3339_opts(SecondsFormat::Secs, true)));
+    }
+
+    if let Some(text) = &query.full_text {
+        conditions.push(format!("fullText contains {}", quote(text)));
+    }
+
+    conditions.join(" and ")
+}
+
+#[async_trait]
+impl Search for GoogleDrive {
+    async fn search(&self, query: SearchQuery) -> Result<FileStream<'_>, CrossroadsError> {
+        let filter = query.filter()?;
+        let drive_query = drive_query(&query);
+
+        let pages = stream::try_unfold(Some(None), move |page_token: Option<Option<String>>| {
+            let drive_query = drive_query.clone();
+
+            async move {
+                let Some(page_token) = page_token else {
+                    return Ok::<_, CrossroadsError>(None)
+                };
+
+                let mut request = self.hub.files().list()
+                    .q(&drive_query)
+                    .param("fields", &format!("nextPageToken,files({})", FILE_FIELDS))
+                    .page_size(SEARCH_PAGE_SIZE)
+                    .add_scope(Scope::Full);
+
+                if let Some(page_token) = page_token {
+                    request = request.page_token(&page_token);
+                }
+
+                let (_, list) = request.doit().await?;
+                let files: Vec<File> = list.files.unwrap_or_default().into_iter().map(|file| self.file_from(file)).collect();
+
+                Ok(Some((files, list.next_page_token.map(Some))))
+            }
+        });
+
+        let files = pages.map_ok(|files| stream::iter(files.into_iter().map(Ok::<_, CrossroadsError>))).try_flatten();
+
+        Ok(Box::pin(files.try_filter(move |file| future::ready(filter.matches(file)))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::interfaces::search::SearchQuery;
+
+    use super::drive_query;
+
+    #[test]
+    fn drive_query_combines_criteria() {
+        let query = SearchQuery {
+            name: Some("it's*.pdf".to_string()),
+            modified_after: Some(Utc.with_ymd_and_hms(2022, 1, 2, 3, 4, 5).unwrap()),
+            full_text: Some("budget".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            drive_query(&query),
+            "trashed = false and name contains 'it\\'s' and modifiedTime > '2022-01-02T03:04:05Z' and fullText contains 'budget'",
+        );
+    }
+}
@@ -0,0 +1,120 @@
+extern crate google_drive3 as drive3;
+use async_trait::async_trait;
+use drive3::api::{Permission, Scope};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::ObjectId;
+use crate::interfaces::sharing::{Grantee, Share, ShareRequest, ShareRole, Sharing};
+use crate::providers::google_drive::GoogleDrive;
+
+/// Fields of a permission read into a `Share`.
+const PERMISSION_FIELDS: &str = "id,type,role,emailAddress,domain,expirationTime";
+
+fn role_name(role: ShareRole) -> &'static str {
+    match role {
+        ShareRole::Read => "reader",
+        ShareRole::Comment => "commenter",
+        ShareRole::Write => "writer",
+        ShareRole::Owner => "owner",
+    }
+}
+
+/// Reads a Drive permission, `url` being the link of the file, which every grantee opens.
+fn share_of(permission: Permission, url: Option<String>) -> Share {
+    let role = match permission.role.as_deref() {
+        Some("owner") => ShareRole::Owner,
+        Some("organizer" | "fileOrganizer" | "writer") => ShareRole::Write,
+        Some("commenter") => ShareRole::Comment,
+        _ => ShareRole::Read,
+    };
+
+    let email = permission.email_address.unwrap_or_default();
+
+    let grantee = match permission.type_.as_deref() {
+        Some("anyone") => Grantee::Anyone,
+        Some("domain") => Grantee::Domain(permission.domain.unwrap_or_default()),
+        Some("group") => Grantee::Group(email),
+        _ => Grantee::User(email),
+    };
+
+    Share { id: permission.id, url, role, grantee, expires_at: permission.expiration_time }
+}
+
+impl GoogleDrive {
+    async fn web_view_link(&self, object_id: &ObjectId) -> Result<Option<String>, CrossroadsError> {
+        let (_, file) = self.hub.files().get(object_id.as_str())
+            .param("fields", "webViewLink")
+            .add_scope(Scope::Full)
+            .doit().await?;
+
+        Ok(file.web_view_link)
+    }
+}
+
+/// Drive grants the organization through its domain, so `Grantee::Organization` is not
+/// supported. Only users and groups get expiring grants, and none of them is emailed.
+#[async_trait]
+impl Sharing for GoogleDrive {
+    async fn share(&self, object_id: ObjectId, request: ShareRequest) -> Result<Share, CrossroadsError> {
+        let (permission_type, email_address, domain) = match request.grantee {
+            Grantee::Anyone => ("anyone", None, None),
+            Grantee::Domain(domain) => ("domain", None, Some(domain)),
+            Grantee::User(email) => ("user", Some(email), None),
+            Grantee::Group(email) => ("group", Some(email), None),
+            Grantee::Organization => return Err(CrossroadsError::unsupported("Google Drive shares with the organization, use its domain")),
+        };
+
+        let permission = Permission {
+            type_: Some(permission_type.to_string()),
+            role: Some(role_name(request.role).to_string()),
+            email_address,
+            domain,
+            expiration_time: request.expires_at,
+            ..Default::default()
+        };
+
+        let mut call = self.hub.permissions().create(permission, object_id.as_str())
+            .param("fields", PERMISSION_FIELDS)
+            .transfer_ownership(request.role == ShareRole::Owner)
+            .add_scope(Scope::Full);
+
+        // Only allowed for users and groups.
+        if matches!(permission_type, "user" | "group") {
+            call = call.send_notification_email(false);
+        }
+
+        let (_, permission) = call.doit().await?;
+
+        Ok(share_of(permission, self.web_view_link(&object_id).await?))
+    }
+
+    async fn list_shares(&self, object_id: ObjectId) -> Result<Vec<Share>, CrossroadsError> {
+        let url = self.web_view_link(&object_id).await?;
+        let mut shares = vec![];
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut request = self.hub.permissions().list(object_id.as_str())
+                .param("fields", &format!("nextPageToken,permissions({})", PERMISSION_FIELDS))
+                .add_scope(Scope::Full);
+
+            if let Some(page_token) = &page_token {
+                request = request.page_token(page_token);
+            }
+
+            let (_, list) = request.doit().await?;
+            shares.extend(list.permissions.unwrap_or_default().into_iter().map(|permission| share_of(permission, url.clone())));
+
+            match list.next_page_token {
+                Some(next_page_token) => page_token = Some(next_page_token),
+                None => return Ok(shares),
+            }
+        }
+    }
+
+    async fn revoke_share(&self, object_id: ObjectId, share_id: &str) -> Result<(), CrossroadsError> {
+        self.hub.permissions().delete(object_id.as_str(), share_id).add_scope(Scope::Full).doit().await?;
+
+        Ok(())
+    }
+}
@@ -1,10 +1,93 @@
+extern crate google_drive3 as drive3;
 use async_trait::async_trait;
+use drive3::api::{File as GoogleDriveFile, Scope};
 
-use crate::{interfaces::trash::Trash, providers::google_drive::GoogleDrive};
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{FileSystem, ObjectId};
+use crate::interfaces::trash::{Trash, TrashedItem};
+use crate::providers::google_drive::GoogleDrive;
 
+/// Most trashed files the API returns per page.
+const TRASH_PAGE_SIZE: i32 = 1000;
+
+impl GoogleDrive {
+    async fn set_trashed(&self, file_id: &str, trashed: bool) -> Result<(), CrossroadsError> {
+        let request = GoogleDriveFile { trashed: Some(trashed), ..Default::default() };
+
+        self.hub.files().update(request, file_id)
+            .param("fields", "id")
+            .add_scope(Scope::Full)
+            .doit_without_upload().await?;
+
+        // Trashed files cannot be reached by their path anymore.
+        self.paths.invalidate(&ObjectId::plain_text(file_id.to_string()));
+        Ok(())
+    }
+}
+
+/// Trashed files keep their parents, which locate them. The content of a trashed folder is
+/// trashed along with it and only the folder is listed.
 #[async_trait]
 impl Trash for GoogleDrive {
-    async fn send_to_trash(&self, object_id: crate::interfaces::filesystem::ObjectId) -> Result<(), Box<dyn std::error::Error>> {
-        todo!()
+    async fn send_to_trash(&self, object_id: ObjectId) -> Result<(), CrossroadsError> {
+        self.set_trashed(object_id.as_str(), true).await
+    }
+
+    async fn list_trash(&self) -> Result<Vec<TrashedItem>, CrossroadsError> {
+        let mut items = vec![];
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut request = self.hub.files().list()
+                .q("trashed = true and 'me' in owners")
+                .param("fields", "nextPageToken,files(id,name,size,trashedTime,explicitlyTrashed,parents)")
+                .page_size(TRASH_PAGE_SIZE)
+                .add_scope(Scope::Full);
+
+            if let Some(page_token) = &page_token {
+                request = request.page_token(page_token);
+            }
+
+            let (_, list) = request.doit().await?;
+
+            for file in list.files.unwrap_or_default() {
+                if !file.explicitly_trashed.unwrap_or_default() {
+                    continue
+                }
+
+                let original_location = match file.parents.as_ref().and_then(|parents| parents.first()) {
+                    Some(parent) => self.path_of(ObjectId::directory(parent.clone())).await.ok(),
+                    None => None,
+                };
+
+                items.push(TrashedItem {
+                    id: file.id.unwrap_or_default(),
+                    name: file.name.unwrap_or_default(),
+                    original_location,
+                    deleted_at: file.trashed_time,
+                    size: file.size.map(|size| size.unsigned_abs()),
+                });
+            }
+
+            match list.next_page_token {
+                Some(next_page_token) => page_token = Some(next_page_token),
+                None => return Ok(items),
+            }
+        }
+    }
+
+    async fn restore_from_trash(&self, trashed_id: &str) -> Result<(), CrossroadsError> {
+        self.set_trashed(trashed_id, false).await
+    }
+
+    async fn delete_from_trash(&self, trashed_id: &str) -> Result<(), CrossroadsError> {
+        self.hub.files().delete(trashed_id).add_scope(Scope::Full).doit().await?;
+        Ok(())
+    }
+
+    /// Drive only empties the files owned by the user, which are the ones listed.
+    async fn empty_trash(&self) -> Result<(), CrossroadsError> {
+        self.hub.files().empty_trash().add_scope(Scope::Full).doit().await?;
+        Ok(())
     }
-}
\ No newline at end of file
+}
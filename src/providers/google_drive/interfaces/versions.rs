@@ -0,0 +1,93 @@
+extern crate google_drive3 as drive3;
+use async_trait::async_trait;
+use drive3::api::Scope;
+use drive3::hyper::body::HttpBody;
+use futures::stream;
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{ByteStream, FileSystem, ObjectId, User, UserId};
+use crate::interfaces::versions::{Revision, Versions};
+use crate::providers::google_drive::GoogleDrive;
+
+/// Most revisions the API returns per page.
+const REVISIONS_PAGE_SIZE: i32 = 1000;
+
+impl From<drive3::api::Revision> for Revision {
+    fn from(revision: drive3::api::Revision) -> Self {
+        let author = revision.last_modifying_user.map(|user| User {
+            id: user.permission_id.or(user.email_address).map_or(UserId::NotApplicable, UserId::UniqueId),
+            name: user.display_name,
+        });
+
+        Revision {
+            id: revision.id.unwrap_or_default(),
+            modified_at: revision.modified_time,
+            size: revision.size.map(|size| size.unsigned_abs()),
+            author,
+            is_current: false,
+        }
+    }
+}
+
+/// Revisions of Google Docs files only have exports, which cannot be read or restored here.
+#[async_trait]
+impl Versions for GoogleDrive {
+    async fn list_revisions(&self, object_id: ObjectId) -> Result<Vec<Revision>, CrossroadsError> {
+        let mut revisions: Vec<Revision> = vec![];
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut request = self.hub.revisions().list(object_id.as_str())
+                .param("fields", "nextPageToken,revisions(id,modifiedTime,size,lastModifyingUser(displayName,permissionId,emailAddress))")
+                .page_size(REVISIONS_PAGE_SIZE)
+                .add_scope(Scope::Full);
+
+            if let Some(page_token) = &page_token {
+                request = request.page_token(page_token);
+            }
+
+            let (_, list) = request.doit().await?;
+            revisions.extend(list.revisions.unwrap_or_default().into_iter().map(Revision::from));
+
+            match list.next_page_token {
+                Some(next_page_token) => page_token = Some(next_page_token),
+                None => break,
+            }
+        }
+
+        // Drive lists the revisions oldest first, ending with the current content.
+        revisions.reverse();
+
+        if let Some(current) = revisions.first_mut() {
+            current.is_current = true;
+        }
+
+        Ok(revisions)
+    }
+
+    async fn read_revision(&self, object_id: ObjectId, revision_id: &str) -> Result<ByteStream, CrossroadsError> {
+        let (response, _) = self.hub.revisions().get(object_id.as_str(), revision_id)
+            .param("alt", "media")
+            .add_scope(Scope::Full)
+            .doit().await?;
+
+        let content = stream::unfold(response.into_body(), |mut body| async move {
+            let chunk = body.data().await?;
+            Some((chunk.map_err(|error| CrossroadsError::Network(Box::new(error))), body))
+        });
+
+        Ok(Box::pin(content))
+    }
+
+    /// Drive has no call to restore a revision, so its content is uploaded again as a new one.
+    async fn restore_revision(&self, object_id: ObjectId, revision_id: &str) -> Result<(), CrossroadsError> {
+        let (_, revision) = self.hub.revisions().get(object_id.as_str(), revision_id)
+            .param("fields", "size")
+            .add_scope(Scope::Full)
+            .doit().await?;
+
+        let content = self.read_revision(object_id.clone(), revision_id).await?;
+
+        self.write_file_stream(object_id, content, revision.size.map(|size| size.unsigned_abs())).await
+    }
+}
@@ -0,0 +1,141 @@
+extern crate google_drive3 as drive3;
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use drive3::api::Scope;
+use serde::{Serialize, Deserialize};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{FileType, ObjectId};
+use crate::interfaces::watch::{ChangeBatch, ChangeEvent, ChangeKind, Watch, WatchCursor};
+use crate::providers::google_drive::GoogleDrive;
+
+use super::filesystem::FILE_FIELDS;
+
+/// Most changes the API returns per page.
+const CHANGES_PAGE_SIZE: i32 = 1000;
+
+/// Page token of `changes.list`, along with when it was issued, as the feed does not tell
+/// created files from modified ones.
+#[derive(Serialize, Deserialize)]
+struct DriveCursor {
+    page_token: String,
+    since: DateTime<Utc>,
+}
+
+impl DriveCursor {
+    fn encode(&self) -> Result<WatchCursor, CrossroadsError> {
+        serde_json::to_string(self)
+            .map(WatchCursor)
+            .map_err(|error| CrossroadsError::Provider(Box::new(error)))
+    }
+
+    fn decode(cursor: &WatchCursor) -> Result<DriveCursor, CrossroadsError> {
+        serde_json::from_str(&cursor.0).map_err(|error| CrossroadsError::InvalidInput(format!("Invalid watch cursor: {}", error)))
+    }
+}
+
+#[async_trait]
+impl Watch for GoogleDrive {
+    async fn watch_cursor(&self, _root: ObjectId) -> Result<WatchCursor, CrossroadsError> {
+        let since = Utc::now();
+        let (_, token) = self.hub.changes().get_start_page_token().add_scope(Scope::Full).doit().await?;
+
+        let page_token = token.start_page_token
+            .ok_or_else(|| CrossroadsError::Provider("Google Drive did not return a start page token".into()))?;
+
+        DriveCursor { page_token, since }.encode()
+    }
+
+    /// Changes are read from the feed of the whole drive and those outside of `root` are left
+    /// out, which takes looking up the ancestors of their parents. Removed files cannot be
+    /// placed anymore, so their deletion is reported whatever `root` is. Renames are reported
+    /// as modifications.
+    async fn changes_since(&self, root: ObjectId, cursor: WatchCursor) -> Result<ChangeBatch, CrossroadsError> {
+        let cursor = DriveCursor::decode(&cursor)?;
+        let now = Utc::now();
+        let root = if root.as_str().is_empty() { None } else { Some(root.as_str().to_string()) };
+
+        let mut page_token = cursor.page_token;
+        let mut within: HashMap<String, bool> = HashMap::new();
+        let mut events = vec![];
+
+        loop {
+            let (_, list) = self.hub.changes().list(&page_token)
+                .param("fields", &format!("nextPageToken,newStartPageToken,changes(fileId,removed,file({},parents,trashed))", FILE_FIELDS))
+                .page_size(CHANGES_PAGE_SIZE)
+                .include_removed(true)
+                .add_scope(Scope::Full)
+                .doit().await?;
+
+            for change in list.changes.unwrap_or_default() {
+                let Some(file_id) = change.file_id else {
+                    continue
+                };
+
+                let file = match change.file {
+                    Some(file) if !change.removed.unwrap_or_default() => file,
+                    _ => {
+                        // The kind of a removed file is unknown.
+                        self.paths.invalidate(&ObjectId::new(file_id.clone(), FileType::File));
+                        events.push(ChangeEvent { kind: ChangeKind::Deleted, object_id: ObjectId::new(file_id, FileType::File), file: None, previous_id: None });
+                        continue
+                    }
+                };
+
+                if let Some(root) = &root {
+                    let parent = file.parents.as_ref().and_then(|parents| parents.first()).cloned();
+
+                    let included = match parent {
+                        _ if file_id == *root => true,
+                        Some(parent) => match within.get(&parent) {
+                            Some(included) => *included,
+                            None => {
+                                let included = self.is_within(&parent, root).await?;
+                                within.insert(parent, included);
+                                included
+                            }
+                        },
+                        None => false,
+                    };
+
+                    if !included {
+                        continue
+                    }
+                }
+
+                let trashed = file.trashed.unwrap_or_default();
+                let created_at = file.created_time;
+                let file = self.file_from(file);
+
+                // Its path may have changed.
+                self.paths.invalidate(&file.id);
+
+                let kind = if trashed {
+                    ChangeKind::Deleted
+                } else if created_at.is_some_and(|created_at| created_at > cursor.since) {
+                    ChangeKind::Created
+                } else {
+                    ChangeKind::Modified
+                };
+
+                events.push(ChangeEvent {
+                    kind,
+                    object_id: file.id.clone(),
+                    file: if trashed { None } else { Some(file) },
+                    previous_id: None,
+                });
+            }
+
+            match (list.next_page_token, list.new_start_page_token) {
+                (Some(next_page_token), _) => page_token = next_page_token,
+                (None, Some(new_start_page_token)) => {
+                    let cursor = DriveCursor { page_token: new_start_page_token, since: now };
+                    return Ok(ChangeBatch { events, cursor: cursor.encode()? })
+                }
+                (None, None) => return Err(CrossroadsError::Provider("Google Drive did not return the next page token of the changes".into())),
+            }
+        }
+    }
+}
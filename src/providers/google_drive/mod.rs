@@ -1,14 +1,21 @@
 mod auth;
 mod token;
 mod interfaces;
+pub mod conversion;
 
 extern crate google_drive3 as drive3;
 
+use std::sync::Arc;
+
 use hyper::client::HttpConnector;
 use hyper_rustls::HttpsConnector;
 use drive3::{DriveHub, hyper, hyper_rustls, oauth2::storage::TokenInfo};
 
-use crate::interfaces::{filesystem::FileSystem, Provider};
+use crate::error::CrossroadsError;
+use crate::providers::path_cache::PathCache;
+
+use self::conversion::ConversionOptions;
+use crate::interfaces::{capabilities::{Capabilities, IdKind}, filesystem::FileSystem, locking::Locking, preview::Preview, search::Search, quota::Quota, sharing::Sharing, versions::Versions, watch::Watch, Provider};
 
 pub type Token = TokenInfo;
 
@@ -16,6 +23,49 @@ pub type Token = TokenInfo;
 pub struct GoogleDrive {
     hub: DriveHub<HttpsConnector<HttpConnector>>,
     tokens: token::MtTokenMap,
+    /// Shared between clones, which talk to the same drive.
+    paths: Arc<PathCache>,
+    conversion: ConversionOptions,
+}
+
+/// Quotes `value` as a string of the query language of `files.list`.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Maps the `error` object of a Drive API response body to the matching variant.
+fn error_from_body(body: &serde_json::Value) -> CrossroadsError {
+    let status = body["error"]["code"].as_u64().unwrap_or_default() as u16;
+    let message = body["error"]["message"].as_str().unwrap_or_default().to_string();
+    let reason = body["error"]["errors"][0]["reason"].as_str().unwrap_or_default();
+
+    match reason {
+        "rateLimitExceeded" | "userRateLimitExceeded" => CrossroadsError::RateLimited(None),
+        _ => CrossroadsError::from_status(status, message),
+    }
+}
+
+impl From<drive3::Error> for CrossroadsError {
+    fn from(error: drive3::Error) -> Self {
+        match error {
+            drive3::Error::HttpError(error) => CrossroadsError::Network(Box::new(error)),
+            drive3::Error::MissingAPIKey | drive3::Error::MissingToken(_) => CrossroadsError::AuthenticationRequired,
+            drive3::Error::Io(error) => error.into(),
+            drive3::Error::BadRequest(body) => error_from_body(&body),
+            drive3::Error::Failure(response) => {
+                let retry_after = response.headers().get(hyper::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse().ok())
+                    .map(std::time::Duration::from_secs);
+
+                match response.status().as_u16() {
+                    429 => CrossroadsError::RateLimited(retry_after),
+                    status => CrossroadsError::from_status(status, response.status().to_string()),
+                }
+            },
+            error => CrossroadsError::Provider(error.to_string().into()),
+        }
+    }
 }
 
 impl Provider for GoogleDrive {
@@ -24,8 +74,58 @@ impl Provider for GoogleDrive {
     }
 
     fn as_trash(&self) -> Option<& dyn crate::interfaces::trash::Trash> {
-        // Some(self)
-        todo!()
+        Some(self)
+    }
+
+    fn as_search(&self) -> Option<& dyn Search> {
+        Some(self)
+    }
+
+    fn as_watch(&self) -> Option<& dyn Watch> {
+        Some(self)
+    }
+
+    fn as_versions(&self) -> Option<& dyn Versions> {
+        Some(self)
+    }
+
+    fn as_sharing(&self) -> Option<& dyn Sharing> {
+        Some(self)
+    }
+
+    fn as_quota(&self) -> Option<& dyn Quota> {
+        Some(self)
+    }
+
+    fn as_preview(&self) -> Option<& dyn Preview> {
+        Some(self)
+    }
+
+    fn as_locking(&self) -> Option<& dyn Locking> {
+        Some(self)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            symlinks: false,
+            trash: true,
+            versions: true,
+            sharing: true,
+            search: true,
+            watch: true,
+            quota: true,
+            preview: true,
+            locking: true,
+            shared_locks: false,
+            server_side_copy: true,
+            server_side_move: false,
+            ranged_reads: true,
+            case_sensitive: true,
+            max_file_name_length: None,
+            forbidden_characters: vec![],
+            id_kind: IdKind::Opaque,
+            empty_directories: true,
+        }
     }
 }
 
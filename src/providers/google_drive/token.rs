@@ -20,6 +20,7 @@ impl TokenStorage for TokenStorageStrategy {
         Ok(())
     }
 
+    #[allow(clippy::borrow_deref_ref, clippy::clone_on_copy, clippy::unnecessary_unwrap)]
     async fn get(&self, target_scopes: &[&str]) -> Option<Token> {
         let token_map_mutex = &*Arc::as_ref(&self.token);
         let token_map = token_map_mutex.lock().unwrap();
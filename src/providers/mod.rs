@@ -1,4 +1,11 @@
 pub mod s3;
 pub mod google_drive;
 pub mod native_fs;
-pub mod onedrive;
\ No newline at end of file
+pub mod onedrive;
+
+mod chunked;
+pub(crate) mod conflict;
+mod path_cache;
+mod range;
+mod snapshot;
+mod thumbnail;
\ No newline at end of file
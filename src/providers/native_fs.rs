@@ -1,27 +1,261 @@
 use async_trait::async_trait;
-use chrono::Utc;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use eyre::Result;
+use futures::{future, stream, TryStreamExt};
+#[cfg(target_os = "linux")]
+use futures::{FutureExt, StreamExt};
+#[cfg(target_os = "linux")]
+use inotify::{EventMask, EventStream, Inotify, WatchDescriptor, WatchMask};
+#[cfg(target_os = "linux")]
+use std::collections::{BTreeSet, HashMap};
+use std::collections::BTreeMap;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use serde::{Serialize, Deserialize};
 use trash;
 use std::fs::File as NativeFile;
 use std::os::unix::fs::{MetadataExt, PermissionsExt, symlink};
+use std::time::Duration;
 
-use crate::interfaces::filesystem::{User, UserId, Permissions, FileType};
-use crate::interfaces::{filesystem::{FileSystem, ObjectId, File, Metadata}, Provider, trash::Trash};
+use crate::error::CrossroadsError;
+use crate::mime;
+use crate::interfaces::capabilities::{Capabilities, IdKind};
+use crate::interfaces::filesystem::{User, UserId, Permissions, FileType, ConflictPolicy, MetadataField, MetadataUpdate};
+use crate::providers::conflict;
+use crate::interfaces::{filesystem::{FileSystem, ObjectId, File, Metadata, ByteStream, FileRange, DirectoryPage, DeleteFailure, FileStream}, search::{Search, SearchQuery}, Provider, trash::{Trash, TrashedItem}};
+use crate::interfaces::locking::{self, Lock, LockMode, LockOptions, Locking};
+use crate::interfaces::preview::{Preview, Thumbnail, ThumbnailSize};
+use crate::interfaces::quota::{Quota, StorageQuota};
+use crate::interfaces::sharing::Sharing;
+use crate::interfaces::versions::{Revision, Versions};
+use crate::interfaces::watch::{ChangeBatch, ChangeStream, Watch, WatchCursor};
+use crate::providers::snapshot::{self, Snapshot};
+use crate::providers::thumbnail;
+use crate::transfer;
+use crate::walker::{self, WalkOptions};
 
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+/// Directories listed at the same time by `search`, which is bound by disk latency.
+const SEARCH_CONCURRENCY: usize = 8;
+/// Time given to a burst of changes to end, so that it is reported in a single batch.
+#[cfg(target_os = "linux")]
+const SETTLE_DELAY: Duration = Duration::from_millis(100);
+#[cfg(target_os = "linux")]
+const INOTIFY_BUFFER_SIZE: usize = 4096;
+
+/// Makes the names of the temporary files of concurrent writes unique.
+static TEMPORARY_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+/// Makes the tokens of locks unique.
+static LOCK_COUNTER: AtomicU64 = AtomicU64::new(0);
+/// Open files whose descriptions hold the locks taken, by token. Locks are released as their
+/// file is closed, and are shared by the clones of a `NativeFs`.
+static HELD_LOCKS: Mutex<BTreeMap<String, NativeFile>> = Mutex::new(BTreeMap::new());
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NativeFs {
     pub root: String,
+    /// Writes into the file itself rather than replacing it once written, which spares copying
+    /// large files that are only appended to, but leaves them truncated after a crash.
+    #[serde(default)]
+    pub in_place_writes: bool,
+    /// Reads the properties of every entry listed, which takes a few more system calls per
+    /// entry. They are left out of listings otherwise, `get_metadata` reading them anyway.
+    #[serde(default)]
+    pub listed_properties: bool,
 }
 
 impl NativeFs {
     pub fn new(root: String) -> NativeFs{
       NativeFs {
-        root
+        root,
+        in_place_writes: false,
+        listed_properties: false,
       }
     }
+
+    pub fn with_in_place_writes(self) -> NativeFs {
+        NativeFs { in_place_writes: true, ..self }
+    }
+
+    pub fn with_listed_properties(self) -> NativeFs {
+        NativeFs { listed_properties: true, ..self }
+    }
+
+    /// Writes `content` to a temporary file next to `path`, synced to disk and given the
+    /// permissions, ownership and properties of the file it replaces, then renames it over
+    /// `path`. A crash leaves either the former content or the new one. Other hard links to
+    /// the file keep the former content.
+    async fn write_atomically(&self, path: &Path, mut content: ByteStream) -> Result<(), CrossroadsError> {
+        // The target of a link is replaced, not the link.
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+        let name = path.file_name()
+            .ok_or_else(|| CrossroadsError::InvalidInput(format!("Cannot write to {}", path.display())))?;
+
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let counter = TEMPORARY_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let temporary = directory.join(format!(".{}.{}-{}.tmp", name.to_string_lossy(), std::process::id(), counter));
+
+        let written = async {
+            let mut file = tokio::fs::OpenOptions::new().write(true).create_new(true).open(&temporary).await?;
+
+            while let Some(chunk) = content.try_next().await? {
+                file.write_all(&chunk).await?;
+            }
+
+            file.flush().await?;
+            let file = file.into_std().await;
+
+            if let Ok(original) = fs::metadata(&path) {
+                copy_attributes(&path, &original, &temporary, &file)?;
+            }
+
+            file.sync_all()?;
+            fs::rename(&temporary, &path)?;
+
+            Ok::<_, CrossroadsError>(())
+        }.await;
+
+        if written.is_err() {
+            let _ = fs::remove_file(&temporary);
+        }
+
+        written?;
+
+        // The rename itself only survives a crash once the directory is synced.
+        NativeFile::open(&directory)?.sync_all()?;
+        Ok(())
+    }
+
+    fn file_from_entry(&self, entry: &fs::DirEntry) -> File {
+        // Ids are strings, so those of names that are not UTF-8 cannot be opened again.
+        let full_path = entry.path().to_string_lossy().to_string();
+        let mut file_type = FileType::File;
+        let mut created_at = None;
+        let mut modified_at = None;
+        let mut owner = None;
+        let mut meta_changed_at = None;
+        let mut accessed_at = None;
+        let mut permissions = None;
+        let mut version = None;
+        if let Ok(metadata) = entry.metadata() {
+            version = Some(version_of(&metadata));
+
+            file_type = if metadata.is_dir() {
+                FileType::Directory
+            } else if metadata.is_symlink() {
+                FileType::Symlink
+            } else {
+                FileType::File
+            };
+
+            if let Ok(time) = metadata.created() {
+                created_at = Some(chrono::DateTime::from(time));
+            } else {
+                created_at = None;
+            }
+
+            if let Ok(time) = metadata.modified() {
+                modified_at = Some(chrono::DateTime::from(time));
+            } else {
+                modified_at = None;
+            }
+
+            owner = Some(User {
+                #[cfg(target_family = "unix")]
+                id: UserId::UserAndGroup(metadata.uid(), metadata.gid()),
+                #[cfg(target_family = "windows")]
+                id: UserId::NotApplicable,
+                name: None,
+            });
+
+            #[cfg(target_family = "unix")]
+            {
+                permissions = Some(Permissions::Unix(metadata.permissions().mode()));
+
+                meta_changed_at = chrono::DateTime::<Utc>::from_timestamp(metadata.ctime(), 0);
+                accessed_at = chrono::DateTime::<Utc>::from_timestamp(metadata.atime(), 0);
+            }
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        // Guessed from the name only, the content being read on request by `detect_mime_type`.
+        let mime_type = match file_type {
+            FileType::Directory => Some(mime::DIRECTORY.to_string()),
+            FileType::Symlink => Some(mime::SYMLINK.to_string()),
+            FileType::File => mime::from_name(&name),
+        };
+
+        File {
+            id: ObjectId::new(full_path.strip_prefix(&self.root.clone()).unwrap().to_string(), file_type.clone()),
+            name,
+            metadata: Some(Metadata {
+                mime_type,
+                export_mime_type: None,
+                created_at,
+                modified_at,
+                meta_changed_at,
+                accessed_at,
+                size: entry.metadata().ok().map(|metadata| metadata.len()),
+                open_path: None,
+                owner,
+                permissions,
+                version,
+                // Computed on demand by `checksums`, as it takes reading the whole file.
+                checksums: None,
+                properties: if self.listed_properties { properties_of(&entry.path()) } else { None },
+            })
+        }
+    }
+
+    /// Removes the content of `path` entry by entry, recording every entry that cannot be
+    /// removed, then `path` itself if nothing was left behind.
+    fn remove_entries(&self, path: &Path, failures: &mut Vec<DeleteFailure>) -> std::io::Result<()> {
+        let known_failures = failures.len();
+
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let is_directory = entry.file_type()?.is_dir();
+
+            let result = if is_directory {
+                self.remove_entries(&entry.path(), failures)
+            } else {
+                fs::remove_file(entry.path())
+            };
+
+            if let Err(error) = result {
+                let full_path = entry.path().to_string_lossy().to_string();
+                let id = full_path.strip_prefix(&self.root).unwrap_or(&full_path).to_string();
+                let file_type = if is_directory { FileType::Directory } else { FileType::File };
+
+                failures.push(DeleteFailure { object_id: ObjectId::new(id, file_type), error: error.into() });
+            }
+        }
+
+        if failures.len() == known_failures {
+            fs::remove_dir(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<trash::Error> for CrossroadsError {
+    fn from(error: trash::Error) -> Self {
+        match error {
+            trash::Error::RestoreCollision { ref path, .. } => CrossroadsError::AlreadyExists(path.to_string_lossy().to_string()),
+            error => CrossroadsError::Provider(Box::new(error)),
+        }
+    }
 }
 
 impl Provider for NativeFs {
@@ -32,23 +266,127 @@ impl Provider for NativeFs {
     fn as_trash(&self) -> Option<&dyn crate::interfaces::trash::Trash> {
         Some(self)
     }
+
+    fn as_search(&self) -> Option<&dyn Search> {
+        Some(self)
+    }
+
+    fn as_watch(&self) -> Option<&dyn Watch> {
+        Some(self)
+    }
+
+    fn as_versions(&self) -> Option<&dyn Versions> {
+        Some(self)
+    }
+
+    fn as_sharing(&self) -> Option<&dyn Sharing> {
+        None
+    }
+
+    fn as_quota(&self) -> Option<&dyn Quota> {
+        Some(self)
+    }
+
+    fn as_preview(&self) -> Option<&dyn Preview> {
+        Some(self)
+    }
+
+    fn as_locking(&self) -> Option<&dyn Locking> {
+        Some(self)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            symlinks: true,
+            trash: true,
+            versions: false,
+            sharing: false,
+            search: true,
+            watch: true,
+            quota: true,
+            preview: true,
+            locking: true,
+            shared_locks: true,
+            server_side_copy: true,
+            server_side_move: true,
+            ranged_reads: true,
+            // Default file systems of macOS and Windows preserve case but ignore it when comparing names.
+            case_sensitive: !cfg!(any(target_os = "macos", target_os = "windows")),
+            max_file_name_length: Some(255),
+            forbidden_characters: vec!['/', '\0'],
+            id_kind: IdKind::Path,
+            empty_directories: true,
+        }
+    }
 }
 
 #[async_trait]
 impl FileSystem for NativeFs {
-    async fn read_file(&self, object_id: ObjectId) -> Result<Vec<u8>, Box<dyn std::error::Error>>
+    async fn read_file(&self, object_id: ObjectId) -> Result<Vec<u8>, CrossroadsError>
     {
         let content = fs::read(self.root.clone() + object_id.as_str())?;
 
         Ok(content)
     }
 
-    async fn write_file(&self, object_id: ObjectId, content: Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
-        fs::write(self.root.clone() + object_id.as_str(), content)?;
+    async fn read_file_stream(&self, object_id: ObjectId) -> Result<ByteStream, CrossroadsError> {
+        let file = tokio::fs::File::open(self.root.clone() + object_id.as_str()).await?;
+
+        let content = stream::try_unfold(file, |mut file| async move {
+            let mut buffer = vec![0; READ_CHUNK_SIZE];
+            let length = file.read(&mut buffer).await?;
+
+            if length == 0 {
+                return Ok(None)
+            }
+
+            buffer.truncate(length);
+            Ok(Some((Bytes::from(buffer), file)))
+        });
+
+        Ok(Box::pin(content))
+    }
+
+    /// Replaces the file once the content is written, see `in_place_writes`.
+    async fn write_file_stream(&self, object_id: ObjectId, mut content: ByteStream, _size: Option<u64>) -> Result<(), CrossroadsError> {
+        if !self.in_place_writes {
+            return self.write_atomically(Path::new(&(self.root.clone() + object_id.as_str())), content).await
+        }
+
+        let mut file = tokio::fs::File::create(self.root.clone() + object_id.as_str()).await?;
+
+        while let Some(chunk) = content.try_next().await? {
+            file.write_all(&chunk).await?;
+        }
+
+        file.flush().await?;
         Ok(())
     }
 
-    async fn delete(&self, object_id: ObjectId) -> Result<(), Box<dyn std::error::Error>> {
+    /// The version is checked right before the file is opened, a write landing in between goes unnoticed.
+    async fn write_file_stream_if_match(&self, object_id: ObjectId, content: ByteStream, size: Option<u64>, version: String) -> Result<(), CrossroadsError> {
+        let metadata = fs::metadata(self.root.clone() + object_id.as_str())?;
+
+        if version_of(&metadata) != version {
+            return Err(CrossroadsError::Conflict(format!("{} changed since version {}", object_id, version)))
+        }
+
+        self.write_file_stream(object_id, content, size).await
+    }
+
+    async fn read_file_range(&self, object_id: ObjectId, offset: u64, length: u64) -> Result<FileRange, CrossroadsError> {
+        let mut file = tokio::fs::File::open(self.root.clone() + object_id.as_str()).await?;
+        let total_size = file.metadata().await?.len();
+
+        file.seek(std::io::SeekFrom::Start(offset)).await?;
+
+        let mut content = Vec::new();
+        file.take(length).read_to_end(&mut content).await?;
+
+        Ok(FileRange { content, offset, total_size })
+    }
+
+    async fn delete(&self, object_id: ObjectId) -> Result<(), CrossroadsError> {
         if object_id.is_directory() {
             fs::remove_dir(self.root.clone() + object_id.as_str())?;
         } else {
@@ -57,117 +395,162 @@ impl FileSystem for NativeFs {
         Ok(())
     }
 
-    async fn rename(&self, object_id: ObjectId, new_name: String) -> Result<ObjectId, Box<dyn std::error::Error>> {
+    async fn delete_recursive(&self, object_id: ObjectId) -> Result<Vec<DeleteFailure>, CrossroadsError> {
+        let path = PathBuf::from(self.root.clone() + object_id.as_str());
+
+        // Links to directories are deleted, not what they point to.
+        if !fs::symlink_metadata(&path)?.is_dir() {
+            fs::remove_file(&path)?;
+            return Ok(vec![])
+        }
+
+        if fs::remove_dir_all(&path).is_ok() {
+            return Ok(vec![])
+        }
+
+        // `remove_dir_all` gives up at the first error, so whatever it left is gone through
+        // again to remove everything that can be and report everything that cannot.
+        let mut failures = vec![];
+        self.remove_entries(&path, &mut failures)?;
+
+        Ok(failures)
+    }
+
+    async fn rename(&self, object_id: ObjectId, new_name: String) -> Result<ObjectId, CrossroadsError> {
         let new_path = std::path::Path::new(object_id.as_str()).parent().unwrap().join(new_name);
         fs::rename(self.root.clone() + object_id.as_str(), self.root.clone() + new_path.to_str().unwrap())?;
         Ok(ObjectId::new(new_path.to_str().unwrap().to_string(), object_id.file_type()))
     }
 
-    async fn move_to(&self, object_id: ObjectId, new_parent_id: ObjectId) -> Result<ObjectId, Box<dyn std::error::Error>> {
+    async fn move_to(&self, object_id: ObjectId, new_parent_id: ObjectId) -> Result<ObjectId, CrossroadsError> {
         let object_id_split: Vec<&str> = object_id.as_str().split("/").collect();
-        let new_path = self.root.clone() + new_parent_id.as_str() + "/" + object_id_split[object_id_split.len() - 1];
-        fs::rename(self.root.clone() + object_id.as_str(), new_path.clone())?;
-        Ok(ObjectId::new(new_path, object_id.file_type()))
+        let name = object_id_split[object_id_split.len() - 1];
+        let new_path = self.root.clone() + new_parent_id.as_str() + "/" + name;
+        fs::rename(self.root.clone() + object_id.as_str(), new_path)?;
+
+        // Ids are relative to the root like those of `read_directory`, which start with a
+        // separator when the root does not end with one.
+        let separator = if self.root.ends_with('/') { "" } else { "/" };
+        let parent = new_parent_id.as_str().trim_matches('/');
+        let id = if parent.is_empty() { format!("{}{}", separator, name) } else { format!("{}{}/{}", separator, parent, name) };
+        Ok(ObjectId::new(id, object_id.file_type()))
+    }
+
+    async fn copy(&self, object_id: ObjectId, new_parent_id: ObjectId, name: &str, conflict: ConflictPolicy) -> Result<ObjectId, CrossroadsError> {
+        let source = PathBuf::from(self.root.clone() + object_id.as_str());
+        let parent = PathBuf::from(self.root.clone() + new_parent_id.as_str());
+
+        let name = conflict::resolve_name(name, conflict, |candidate| {
+            let taken = fs::symlink_metadata(parent.join(candidate)).is_ok();
+            async move { Ok(taken) }
+        }).await?;
+
+        let destination = parent.join(&name);
+
+        let canonical_source = fs::canonicalize(&source)?;
+
+        if fs::canonicalize(&parent)?.starts_with(&canonical_source) || fs::canonicalize(&destination).ok() == Some(canonical_source) {
+            return Err(CrossroadsError::InvalidInput(format!("Cannot copy {} into itself", object_id)))
+        }
+
+        // The copy is made under a temporary name, so that the entry it replaces is only
+        // removed once it succeeded.
+        let temporary = parent.join(transfer::temporary_name(&name));
+
+        if let Err(error) = copy_recursively(&source, &temporary) {
+            let _ = remove_entry(&temporary);
+            return Err(error.into())
+        }
+
+        // Only reached with `ConflictPolicy::Replace`. A file is replaced by the rename itself.
+        if let Ok(existing) = fs::symlink_metadata(&destination) {
+            if existing.is_dir() || fs::symlink_metadata(&temporary)?.is_dir() {
+                remove_entry(&destination)?;
+            }
+        }
+
+        fs::rename(&temporary, &destination)?;
+
+        let id = Path::new(new_parent_id.as_str()).join(&name);
+        Ok(ObjectId::new(id.to_string_lossy().to_string(), object_id.file_type()))
     }
 
-    async fn create(&self, parent_id: ObjectId, file: File) -> Result<(), Box<dyn std::error::Error>> {
-        if file.metadata.unwrap().mime_type == Some("directory".to_string()) {
+    async fn create(&self, parent_id: ObjectId, file: File) -> Result<ObjectId, CrossroadsError> {
+        let mime_type = file.metadata.and_then(|metadata| metadata.mime_type);
+
+        let file_type = if file.id.is_directory() || mime_type.as_deref() == Some(mime::DIRECTORY) {
             fs::create_dir(self.root.clone() + parent_id.as_str() + "/" + file.name.as_str())?;
+            FileType::Directory
         } else {
             NativeFile::create(self.root.clone() + parent_id.as_str() + "/" + file.name.as_str())?;
-        }
-        Ok(())
+            FileType::File
+        };
+
+        let id = Path::new(parent_id.as_str()).join(&file.name);
+        Ok(ObjectId::new(id.to_string_lossy().to_string(), file_type))
     }
 
-    async fn read_directory(&self, object_id: ObjectId) -> Result<Vec<File>, Box<dyn std::error::Error>> {
-        let dir_content = fs::read_dir(self.root.clone() + object_id.as_str())?;
+    /// Reads the directory once, where pages would each read it again.
+    fn read_directory_stream(&self, object_id: ObjectId) -> FileStream<'_> {
+        let files = stream::once(async move {
+            let mut entries = fs::read_dir(self.root.clone() + object_id.as_str())?.collect::<Result<Vec<_>, _>>()?;
+            entries.sort_by_key(|entry| entry.file_name());
 
-        let mut files = vec![];
+            let files: Vec<Result<File, CrossroadsError>> = entries.iter().map(|entry| Ok(self.file_from_entry(entry))).collect();
+            Ok::<_, CrossroadsError>(stream::iter(files))
+        });
 
-        for file in dir_content {
-            let entry = file.unwrap();
-            let full_path = entry.path().as_os_str().to_str().unwrap().to_string();
-            let mut file_type = FileType::File;
-            let mut created_at = None;
-            let mut modified_at = None;
-            let mut owner = None;
-            let mut meta_changed_at = None;
-            let mut accessed_at = None;
-            let mut permissions = None;
-            if let Ok(metadata) = entry.metadata() {
-                file_type = if metadata.is_dir() {
-                    FileType::Directory
-                } else if metadata.is_symlink() {
-                    FileType::Symlink
-                } else {
-                    FileType::File
-                };
+        Box::pin(files.try_flatten())
+    }
 
-                if let Ok(time) = metadata.created() {
-                    created_at = Some(chrono::DateTime::from(time));
-                } else {
-                    created_at = None;
-                }
-    
-                if let Ok(time) = metadata.modified() {
-                    modified_at = Some(chrono::DateTime::from(time));
-                } else {
-                    modified_at = None;
-                }
-    
-                owner = Some(User {
-                    #[cfg(target_family = "unix")]
-                    id: UserId::UserAndGroup(metadata.uid(), metadata.gid()),
-                    #[cfg(target_family = "windows")]
-                    id: UserId::NotApplicable,
-                    name: None,
-                });
-                
-                #[cfg(target_family = "unix")]
-                {
-                    permissions = Some(Permissions::Unix(metadata.permissions().mode()));
+    /// The order of `read_dir` is unspecified, so pages are cut in the order of the names and
+    /// the token is the last name returned, as hexadecimal bytes since names need not be UTF-8.
+    async fn read_directory_page(&self, object_id: ObjectId, page_size: usize, page_token: Option<String>) -> Result<DirectoryPage, CrossroadsError> {
+        use std::os::unix::ffi::{OsStrExt, OsStringExt};
 
-                    let ctime = chrono::NaiveDateTime::from_timestamp_opt(metadata.ctime(), 0);
-                    if let Some(ctime) = ctime {
-                        meta_changed_at = Some(chrono::DateTime::<Utc>::from_utc(ctime, Utc));
-                    }
+        let last_name = match page_token {
+            Some(token) => Some(std::ffi::OsString::from_vec(
+                hex::decode(&token).map_err(|_| CrossroadsError::InvalidInput(format!("Invalid page token {}", token)))?,
+            )),
+            None => None,
+        };
 
-                    let atime = chrono::NaiveDateTime::from_timestamp_opt(metadata.atime(), 0);
-                    if let Some(atime) = atime {
-                        accessed_at = Some(chrono::DateTime::<Utc>::from_utc(atime, Utc));
-                    }
-                }
-            }
+        let mut entries: Vec<fs::DirEntry> = fs::read_dir(self.root.clone() + object_id.as_str())?
+            .filter(|entry| match (entry, &last_name) {
+                (Ok(entry), Some(last_name)) => entry.file_name() > *last_name,
+                _ => true,
+            })
+            .collect::<Result<_, _>>()?;
 
-            files.push(File {
-                id: ObjectId::new(full_path.strip_prefix(&self.root.clone()).unwrap().to_string(), file_type.clone()),
-                name: entry.file_name().to_string_lossy().to_string(),
-                metadata: Some(Metadata {
-                    mime_type: None,
-                    created_at,
-                    modified_at,
-                    meta_changed_at,
-                    accessed_at,
-                    size: Some(entry.metadata().unwrap().len()),
-                    open_path: None,
-                    owner,
-                    permissions,
-                })
-            });
+        let page_size = page_size.max(1);
+        let more = entries.len() > page_size;
+
+        // Only the entries of the page are sorted, the others being put after them.
+        if more {
+            entries.select_nth_unstable_by_key(page_size, |entry| entry.file_name());
+            entries.truncate(page_size);
         }
-        Ok(files)
+
+        entries.sort_by_key(|entry| entry.file_name());
+
+        let next_page_token = match entries.last() {
+            Some(last) if more => Some(hex::encode(last.file_name().as_bytes())),
+            _ => None,
+        };
+
+        let files = entries.iter().map(|entry| self.file_from_entry(entry)).collect();
+
+        Ok(DirectoryPage { files, next_page_token })
     }
 
-    async fn get_metadata(&self, object_id: ObjectId) -> Result<crate::interfaces::filesystem::Metadata, Box<dyn std::error::Error>> {
-        let metadata = std::fs::metadata(self.root.clone() + object_id.as_str()).unwrap();
+    async fn get_metadata(&self, object_id: ObjectId) -> Result<crate::interfaces::filesystem::Metadata, CrossroadsError> {
+        let metadata = std::fs::metadata(self.root.clone() + object_id.as_str())?;
         let open_path = Some(self.root.clone() + object_id.as_str());
 
         metadata.permissions().mode();
 
         let created_at;
         let modified_at;
-        let mut meta_changed_at = None;
-        let mut accessed_at = None;
         let mut permissions = None;
 
         let size = Some(metadata.len());
@@ -196,82 +579,1285 @@ impl FileSystem for NativeFs {
             permissions = Some(Permissions::Unix(metadata.permissions().mode()));
         }
         
-        #[cfg(target_family = "unix")]
-        {
-            let ctime = chrono::NaiveDateTime::from_timestamp_opt(metadata.ctime(), 0);
-            if let Some(ctime) = ctime {
-                meta_changed_at = Some(chrono::DateTime::<Utc>::from_utc(ctime, Utc));
-            }
+        let meta_changed_at = chrono::DateTime::<Utc>::from_timestamp(metadata.ctime(), 0);
+        let accessed_at = chrono::DateTime::<Utc>::from_timestamp(metadata.atime(), 0);
 
-            let atime = chrono::NaiveDateTime::from_timestamp_opt(metadata.atime(), 0);
-            if let Some(atime) = atime {
-                accessed_at = Some(chrono::DateTime::<Utc>::from_utc(atime, Utc));
-            }
-        }
+        // Reading the start of a single local file is cheap enough to recognize its content.
+        let mime_type = if metadata.is_dir() {
+            Some(mime::DIRECTORY.to_string())
+        } else {
+            sniff_mime_type(Path::new(&(self.root.clone() + object_id.as_str())))
+        };
 
         Ok(Metadata {
             modified_at,
             created_at,
             meta_changed_at,
             accessed_at,
-            mime_type: None,
+            mime_type,
+            export_mime_type: None,
             open_path,
             size,
             owner,
             permissions,
+            version: Some(version_of(&metadata)),
+            checksums: None,
+            properties: properties_of(Path::new(&(self.root.clone() + object_id.as_str()))),
         })
     }
 
-    async fn read_link(&self, object_id: ObjectId) -> Result<ObjectId, Box<dyn std::error::Error>> {
-        let link = std::fs::read_link(self.root.clone() + object_id.as_str()).unwrap();
-        let symlink = ObjectId::new(link.to_str().unwrap().to_string(), FileType::Symlink);
-        Ok(symlink)
+    async fn resolve_path(&self, path: &str) -> Result<ObjectId, CrossroadsError> {
+        let path = path.trim_matches('/');
+        let metadata = fs::symlink_metadata(self.root.clone() + path)?;
+
+        let file_type = if metadata.is_symlink() {
+            FileType::Symlink
+        } else if metadata.is_dir() {
+            FileType::Directory
+        } else {
+            FileType::File
+        };
+
+        Ok(ObjectId::new(path.to_string(), file_type))
+    }
+
+    async fn path_of(&self, object_id: ObjectId) -> Result<String, CrossroadsError> {
+        Ok(object_id.as_str().trim_matches('/').to_string())
+    }
+
+    /// Already recognized by `get_metadata`.
+    async fn detect_mime_type(&self, object_id: ObjectId) -> Result<Option<String>, CrossroadsError> {
+        Ok(self.get_metadata(object_id).await?.mime_type)
+    }
+
+    async fn set_modified_at(&self, object_id: ObjectId, modified_at: DateTime<Utc>) -> Result<(), CrossroadsError> {
+        let file = NativeFile::open(self.root.clone() + object_id.as_str())?;
+        file.set_modified(modified_at.into())?;
+        Ok(())
+    }
+
+    /// Times are set with `futimens` and ownership with `chown`, which only root may give to
+    /// another user. Unix keeps no settable creation time.
+    async fn set_metadata(&self, object_id: ObjectId, update: MetadataUpdate) -> Result<(), CrossroadsError> {
+        update.check_supported("NativeFs", &[MetadataField::ModifiedAt, MetadataField::AccessedAt, MetadataField::Permissions, MetadataField::Owner])?;
+
+        let owner = match update.owner {
+            Some(UserId::UserAndGroup(uid, gid)) => Some((uid, gid)),
+            Some(owner) => return Err(CrossroadsError::InvalidInput(format!("NativeFs owners are a uid and gid, not {:?}", owner))),
+            None => None,
+        };
+
+        let path = self.root.clone() + object_id.as_str();
+
+        // Changing the owner clears the setuid and setgid bits, which the mode may set again.
+        if let Some((uid, gid)) = owner {
+            std::os::unix::fs::chown(&path, Some(uid), Some(gid))?;
+        }
+
+        if let Some(Permissions::Unix(mode)) = update.permissions {
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+        }
+
+        if update.modified_at.is_some() || update.accessed_at.is_some() {
+            let mut times = fs::FileTimes::new();
+
+            if let Some(modified_at) = update.modified_at {
+                times = times.set_modified(modified_at.into());
+            }
+
+            if let Some(accessed_at) = update.accessed_at {
+                times = times.set_accessed(accessed_at.into());
+            }
+
+            NativeFile::open(&path)?.set_times(times)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn get_properties(&self, object_id: ObjectId) -> Result<BTreeMap<String, String>, CrossroadsError> {
+        Ok(xattr::properties(Path::new(&(self.root.clone() + object_id.as_str())))?)
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn set_properties(&self, object_id: ObjectId, properties: BTreeMap<String, String>) -> Result<(), CrossroadsError> {
+        let path = self.root.clone() + object_id.as_str();
+
+        for (name, value) in properties {
+            xattr::set(Path::new(&path), &name, &value)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn remove_properties(&self, object_id: ObjectId, names: &[String]) -> Result<(), CrossroadsError> {
+        let path = self.root.clone() + object_id.as_str();
+
+        for name in names {
+            xattr::remove(Path::new(&path), name)?;
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the whole chain of links and returns the id of the final target, relative
+    /// to the root, so that two links pointing to the same entry resolve to the same id.
+    async fn read_link(&self, object_id: ObjectId) -> Result<ObjectId, CrossroadsError> {
+        let target = fs::canonicalize(self.root.clone() + object_id.as_str())?;
+        let file_type = if target.is_dir() { FileType::Directory } else { FileType::File };
+
+        if self.root.is_empty() {
+            return Ok(ObjectId::new(target.to_string_lossy().to_string(), file_type))
+        }
+
+        let root = fs::canonicalize(&self.root)?;
+        let relative = target.strip_prefix(&root)
+            .map_err(|_| CrossroadsError::PermissionDenied(format!("{} points outside of {}", object_id, self.root)))?;
+
+        // Ids produced by `read_directory` start with a separator when the root does not end with one.
+        let separator = if self.root.ends_with('/') || relative.as_os_str().is_empty() { "" } else { "/" };
+        Ok(ObjectId::new(separator.to_string() + &relative.to_string_lossy(), file_type))
     }
 
-    async fn create_link(&self, parent_id: ObjectId, name: &str, link_id: ObjectId) -> Result<ObjectId, Box<dyn std::error::Error>> {
-        symlink(link_id.as_str(), self.root.clone() + parent_id.as_str() + "/" + name).unwrap();
+    async fn create_link(&self, parent_id: ObjectId, name: &str, link_id: ObjectId) -> Result<ObjectId, CrossroadsError> {
+        symlink(link_id.as_str(), self.root.clone() + parent_id.as_str() + "/" + name)?;
         Ok(ObjectId::new(parent_id.as_str().to_string() + "/" + name, FileType::Symlink))
     }
 }
 
-#[async_trait]
-impl Trash for NativeFs {
-    async fn send_to_trash(&self, object_id: crate::interfaces::filesystem::ObjectId) -> Result<(), Box<dyn std::error::Error>> {
-        dbg!(self.root.clone() + object_id.as_str());
-        trash::delete(self.root.clone() + object_id.as_str()).unwrap();
-        Ok(())
+/// Gives the file being written at `temporary` what the one it replaces at `path` had. Only the
+/// superuser can give a file away, so other users keep the group and owner of their own.
+fn copy_attributes(path: &Path, original: &fs::Metadata, temporary: &Path, file: &NativeFile) -> std::io::Result<()> {
+    #[cfg(target_os = "linux")]
+    for (name, value) in xattr::properties(path).unwrap_or_default() {
+        xattr::set(temporary, &name, &value)?;
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    let _ = (path, temporary);
+
+    match std::os::unix::fs::fchown(file, Some(original.uid()), Some(original.gid())) {
+        Err(error) if error.kind() == std::io::ErrorKind::PermissionDenied => {}
+        result => result?,
     }
+
+    // After the change of owner, which clears the setuid and setgid bits.
+    file.set_permissions(original.permissions())
 }
 
+/// Type of the file at `path` from its first bytes, or from its name when they are not recognized.
+fn sniff_mime_type(path: &Path) -> Option<String> {
+    use std::io::Read;
 
-#[cfg(test)]
-mod tests {
-    use crate::providers::native_fs::*;
-    use crate::interfaces::filesystem::FileSystem;
+    let mut start = vec![];
+    let sniffed = NativeFile::open(path)
+        .and_then(|file| file.take(mime::SNIFF_LENGTH).read_to_end(&mut start))
+        .ok()
+        .and_then(|_| mime::sniff(&start));
 
-    #[tokio::test]
-    async fn native_fs_request_works() {
-        let x = NativeFs {
-            root: "./sandbox/".to_string()
-        };
-        let object_id = ObjectId::new(String::from("hello-world.txt"), FileType::File);
-        let result = x.read_file(object_id).await;
-        assert!(result.is_ok());
-        assert_eq!(String::from_utf8(result.unwrap().to_vec()).unwrap(), String::from("hello world!"));
+    sniffed.or_else(|| mime::from_name(&path.to_string_lossy()))
+}
+
+/// Properties of the entry at `path`, `None` where extended attributes cannot be read.
+#[cfg(target_os = "linux")]
+fn properties_of(path: &Path) -> Option<BTreeMap<String, String>> {
+    xattr::properties(path).ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn properties_of(_path: &Path) -> Option<BTreeMap<String, String>> {
+    None
+}
+
+/// Properties are the extended attributes of the `user` namespace, the one open to every
+/// process allowed to write to the file. Values that are not UTF-8 are left out.
+#[cfg(target_os = "linux")]
+mod xattr {
+    use std::collections::BTreeMap;
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    const NAMESPACE: &str = "user.";
+
+    fn c_string(bytes: &[u8]) -> io::Result<CString> {
+        CString::new(bytes).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
     }
 
-    #[tokio::test]
-    async fn native_fs_list_folder_content() {
-        let x = NativeFs {
-            root: "./sandbox/".to_string()
-        };
+    fn attribute_name(name: &str) -> io::Result<CString> {
+        c_string(format!("{}{}", NAMESPACE, name).as_bytes())
+    }
 
-        let object_id = ObjectId::new(String::from(""), FileType::Directory);
+    /// Calls `read` with a buffer of the size it asks for, again if the value grew in between.
+    fn read_buffer(read: impl Fn(*mut libc::c_void, usize) -> isize) -> io::Result<Vec<u8>> {
+        loop {
+            let size = read(std::ptr::null_mut(), 0);
 
-        let result = x.read_directory(object_id).await;
+            if size < 0 {
+                return Err(io::Error::last_os_error())
+            }
 
-        assert!(result.is_ok());
+            let mut buffer = vec![0u8; size as usize];
+            let read_size = read(buffer.as_mut_ptr().cast(), buffer.len());
 
-        assert_eq!("hello-world.txt", result.as_ref().unwrap()[0].name);
+            if read_size >= 0 {
+                buffer.truncate(read_size as usize);
+                return Ok(buffer)
+            }
+
+            let error = io::Error::last_os_error();
+
+            if error.raw_os_error() != Some(libc::ERANGE) {
+                return Err(error)
+            }
+        }
+    }
+
+    pub(super) fn properties(path: &Path) -> io::Result<BTreeMap<String, String>> {
+        let path = c_string(path.as_os_str().as_bytes())?;
+
+        // SAFETY: `path` is a valid C string and at most `size` bytes are written to `buffer`.
+        let names = read_buffer(|buffer, size| unsafe { libc::listxattr(path.as_ptr(), buffer.cast(), size) })?;
+        let mut properties = BTreeMap::new();
+
+        // Names are separated by null bytes.
+        for name in names.split(|byte| *byte == 0) {
+            let Some(property) = std::str::from_utf8(name).ok().and_then(|name| name.strip_prefix(NAMESPACE)) else {
+                continue
+            };
+
+            let name = c_string(name)?;
+
+            // SAFETY: as above, with `name` a valid C string too.
+            let value = match read_buffer(|buffer, size| unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), buffer, size) }) {
+                Ok(value) => value,
+                // Removed since the names were listed.
+                Err(error) if error.raw_os_error() == Some(libc::ENODATA) => continue,
+                Err(error) => return Err(error),
+            };
+
+            if let Ok(value) = String::from_utf8(value) {
+                properties.insert(property.to_string(), value);
+            }
+        }
+
+        Ok(properties)
+    }
+
+    pub(super) fn set(path: &Path, name: &str, value: &str) -> io::Result<()> {
+        let path = c_string(path.as_os_str().as_bytes())?;
+        let name = attribute_name(name)?;
+
+        // SAFETY: both strings are valid C strings and `value` is read up to its length.
+        if unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value.as_ptr().cast(), value.len(), 0) } != 0 {
+            return Err(io::Error::last_os_error())
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn remove(path: &Path, name: &str) -> io::Result<()> {
+        let path = c_string(path.as_os_str().as_bytes())?;
+        let name = attribute_name(name)?;
+
+        // SAFETY: both strings are valid C strings.
+        if unsafe { libc::removexattr(path.as_ptr(), name.as_ptr()) } != 0 {
+            let error = io::Error::last_os_error();
+
+            if error.raw_os_error() != Some(libc::ENODATA) {
+                return Err(error)
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Changes whenever the file is written to or replaced by another one.
+fn version_of(metadata: &fs::Metadata) -> String {
+    format!("{}-{}.{}-{}", metadata.ino(), metadata.mtime(), metadata.mtime_nsec(), metadata.len())
+}
+
+/// Removes the entry at `path`, with its content when it is a directory.
+fn remove_entry(path: &Path) -> std::io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Links are copied as links. `fs::copy` leaves the copy of the data to the kernel, which
+/// clones the extents on file systems supporting reflinks (`copy_file_range` on Linux,
+/// `clonefile` on macOS).
+fn copy_recursively(source: &Path, destination: &Path) -> std::io::Result<()> {
+    let metadata = fs::symlink_metadata(source)?;
+
+    if metadata.is_symlink() {
+        symlink(fs::read_link(source)?, destination)
+    } else if metadata.is_dir() {
+        fs::create_dir(destination)?;
+
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &destination.join(entry.file_name()))?;
+        }
+
+        Ok(())
+    } else {
+        fs::copy(source, destination).map(|_| ())
+    }
+}
+
+#[async_trait]
+impl Search for NativeFs {
+    /// Walks the whole tree, listing several directories at once. MIME types are matched
+    /// against the ones guessed from the names.
+    async fn search(&self, query: SearchQuery) -> Result<FileStream<'_>, CrossroadsError> {
+        if query.full_text.is_some() {
+            return Err(CrossroadsError::unsupported("NativeFs search by content"))
+        }
+
+        let filter = query.filter()?;
+        let options = WalkOptions { concurrency: SEARCH_CONCURRENCY, ..Default::default() };
+
+        let files = walker::walk(self, ObjectId::root(), options)?
+            .map_ok(|entry| entry.file)
+            .try_filter(move |file| future::ready(filter.matches(file)));
+
+        Ok(Box::pin(files))
+    }
+}
+
+/// Events that may change the snapshot of a watched directory.
+#[cfg(target_os = "linux")]
+const WATCHED_EVENTS: WatchMask = WatchMask::CREATE.union(WatchMask::DELETE)
+    .union(WatchMask::MODIFY)
+    .union(WatchMask::ATTRIB)
+    .union(WatchMask::MOVED_FROM)
+    .union(WatchMask::MOVED_TO)
+    .union(WatchMask::DELETE_SELF);
+
+/// The trash of the system is read through the freedesktop specification, which macOS does not follow.
+#[cfg(not(target_os = "macos"))]
+impl NativeFs {
+    /// Items of the trash deleted from below the root, along with the path they were deleted
+    /// from relative to the root.
+    fn trashed_items(&self) -> Result<Vec<(trash::TrashItem, String)>, CrossroadsError> {
+        let root = fs::canonicalize(&self.root)?;
+
+        let items = trash::os_limited::list()?.into_iter()
+            .filter_map(|item| {
+                let original_location = item.original_parent.strip_prefix(&root).ok()?.to_string_lossy().to_string();
+                Some((item, original_location))
+            })
+            .collect();
+
+        Ok(items)
+    }
+
+    fn trashed_item(&self, trashed_id: &str) -> Result<trash::TrashItem, CrossroadsError> {
+        self.trashed_items()?.into_iter()
+            .map(|(item, _)| item)
+            .find(|item| item.id.to_string_lossy() == trashed_id)
+            .ok_or_else(|| CrossroadsError::NotFound(format!("{} is not in the trash", trashed_id)))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl NativeFs {
+    /// Lists the directories inotify reports an event in again, along with the directories
+    /// that appeared in them, or walks the whole tree after `interval` otherwise. The snapshot
+    /// of the cursor is kept up to date in between, and only serialized along with a batch.
+    /// Directories are watched before being listed, so that nothing happening in between goes
+    /// unnoticed.
+    fn notified_changes(&self, root: ObjectId, cursor: WatchCursor, interval: Duration) -> ChangeStream<'_> {
+        type Watcher = (Snapshot, EventStream<[u8; INOTIFY_BUFFER_SIZE]>, HashMap<WatchDescriptor, String>);
+
+        let changes = stream::try_unfold((cursor, None), move |(cursor, watcher): (WatchCursor, Option<Watcher>)| {
+            let root = root.clone();
+
+            async move {
+                let (mut snapshot, mut events, mut directories) = match watcher {
+                    Some(watcher) => watcher,
+                    None => (Snapshot::from_cursor(&cursor)?, Inotify::init()?.into_event_stream([0; INOTIFY_BUFFER_SIZE])?, HashMap::new()),
+                };
+
+                // Nothing is known of what happened before the stream started, so it starts with a walk.
+                let mut notified = None;
+
+                loop {
+                    let (listed, files) = self.list_watched(&root, notified.take(), &snapshot, &mut events, &mut directories).await?;
+                    let changes = snapshot.update(&listed, files);
+
+                    if !changes.is_empty() {
+                        let batch = ChangeBatch { events: changes, cursor: snapshot.to_cursor()? };
+                        let next = batch.cursor.clone();
+                        return Ok(Some((batch, (next, Some((snapshot, events, directories))))))
+                    }
+
+                    if let Ok(Some(event)) = tokio::time::timeout(interval, events.next()).await {
+                        let mut pending = vec![event?];
+                        tokio::time::sleep(SETTLE_DELAY).await;
+
+                        while let Some(Some(event)) = events.next().now_or_never() {
+                            pending.push(event?);
+                        }
+
+                        let mut changed = BTreeSet::new();
+
+                        for event in pending {
+                            if event.mask.contains(EventMask::Q_OVERFLOW) {
+                                // Events were lost, so the whole tree is walked again.
+                                changed.clear();
+                                break
+                            }
+
+                            if event.mask.contains(EventMask::IGNORED) {
+                                directories.remove(&event.wd);
+                            } else if let Some(directory) = directories.get(&event.wd) {
+                                changed.insert(directory.clone());
+                            }
+                        }
+
+                        notified = Some(changed).filter(|changed| !changed.is_empty());
+                    }
+                }
+            }
+        });
+
+        Box::pin(changes)
+    }
+
+    /// Lists the `notified` directories and the directories appearing in them, or all those
+    /// below `root`, watching each one first. Returns the directories listed, and their entries
+    /// along with their own.
+    async fn list_watched(
+        &self,
+        root: &ObjectId,
+        notified: Option<BTreeSet<String>>,
+        snapshot: &Snapshot,
+        events: &mut EventStream<[u8; INOTIFY_BUFFER_SIZE]>,
+        directories: &mut HashMap<WatchDescriptor, String>,
+    ) -> Result<(BTreeSet<String>, Vec<File>), CrossroadsError> {
+        let walk = notified.is_none();
+        let known: BTreeSet<&str> = if walk { BTreeSet::new() } else { snapshot.directories().collect() };
+
+        let mut pending: Vec<String> = notified.unwrap_or_else(|| BTreeSet::from([root.as_str().to_string()])).into_iter().collect();
+        let mut listed = BTreeSet::new();
+        let mut files = vec![];
+        let mut watches = events.watches();
+
+        while let Some(directory) = pending.pop() {
+            if !listed.insert(directory.clone()) {
+                continue
+            }
+
+            // Directories that cannot be watched anymore have been deleted, which listing their parent reports.
+            if let Ok(descriptor) = watches.add(self.root.clone() + &directory, WATCHED_EVENTS) {
+                directories.insert(descriptor, directory.clone());
+            }
+
+            let entries = match self.read_directory(ObjectId::directory(directory.clone())).await {
+                Ok(entries) => entries,
+                Err(CrossroadsError::NotFound(_)) => {
+                    listed.remove(&directory);
+                    continue
+                }
+                Err(error) => return Err(error),
+            };
+
+            pending.extend(entries.iter()
+                .filter(|file| file.id.file_type() == FileType::Directory && (walk || !known.contains(file.id.as_str())))
+                .map(|file| file.id.as_str().to_string()));
+
+            files.extend(entries);
+        }
+
+        // The modification time of a notified directory changes with its content, but is not
+        // part of it.
+        for directory in &listed {
+            if directory != root.as_str() && !files.iter().any(|file| file.id.as_str() == directory) {
+                let id = ObjectId::directory(directory.clone());
+                let metadata = self.get_metadata(id.clone()).await?;
+                let name = directory.rsplit('/').next().unwrap_or_default().to_string();
+
+                files.push(File { id, name, metadata: Some(metadata) });
+            }
+        }
+
+        Ok((listed, files))
+    }
+}
+
+/// Changes are found by comparing the tree with the snapshot held by the cursor. Entries keep
+/// their version when renamed, so renames are told apart from deletions.
+#[async_trait]
+impl Watch for NativeFs {
+    async fn watch_cursor(&self, root: ObjectId) -> Result<WatchCursor, CrossroadsError> {
+        Snapshot::of(&snapshot::walk(self, root).await?).to_cursor()
+    }
+
+    async fn changes_since(&self, root: ObjectId, cursor: WatchCursor) -> Result<ChangeBatch, CrossroadsError> {
+        Snapshot::from_cursor(&cursor)?.diff(snapshot::walk(self, root).await?)
+    }
+
+    /// Uses inotify on Linux, `interval` only bounding the wait for changes it may have missed.
+    #[cfg(target_os = "linux")]
+    fn watch(&self, root: ObjectId, cursor: WatchCursor, interval: Duration) -> ChangeStream<'_> {
+        self.notified_changes(root, cursor, interval)
+    }
+}
+
+/// Space of the file system holding the root, which other directories share. The trash of the
+/// system is not told apart from the rest of the used space.
+#[async_trait]
+impl Quota for NativeFs {
+    async fn quota(&self) -> Result<StorageQuota, CrossroadsError> {
+        // An empty root makes ids paths relative to the current directory.
+        let root = if self.root.is_empty() { "." } else { self.root.as_str() };
+        let root = std::ffi::CString::new(root)
+            .map_err(|error| CrossroadsError::InvalidInput(error.to_string()))?;
+
+        let mut stats = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+
+        // SAFETY: `root` is a valid C string and `stats` is only read once filled in.
+        let stats = unsafe {
+            if libc::statvfs(root.as_ptr(), stats.as_mut_ptr()) != 0 {
+                return Err(std::io::Error::last_os_error().into())
+            }
+
+            stats.assume_init()
+        };
+
+        let block_size = stats.f_frsize as u64;
+
+        Ok(StorageQuota {
+            total: Some(stats.f_blocks as u64 * block_size),
+            used: Some((stats.f_blocks - stats.f_bfree) as u64 * block_size),
+            // Blocks reserved to the superuser are left out.
+            free: Some(stats.f_bavail as u64 * block_size),
+            trash: None,
+        })
+    }
+}
+
+/// Open file description locks on Linux, which unlike `flock` are also seen by other clients of
+/// network file systems, and `flock` elsewhere. Both only last as long as the process and take
+/// no lease. Locks are taken on a `.<name>.lock` file next to the file, as writes replace the
+/// file itself unless `in_place_writes` is set. It is left behind once unlocked, since removing
+/// it would let two holders lock different files.
+#[async_trait]
+impl Locking for NativeFs {
+    async fn lock(&self, object_id: ObjectId, options: LockOptions) -> Result<Lock, CrossroadsError> {
+        if object_id.is_directory() {
+            return Err(CrossroadsError::unsupported("NativeFs locks on directories"))
+        }
+
+        let path = PathBuf::from(self.root.clone() + object_id.as_str());
+        fs::metadata(&path)?;
+
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(lock_path(&path))?;
+
+        let token = format!("{}-{}", std::process::id(), LOCK_COUNTER.fetch_add(1, Ordering::Relaxed));
+
+        let lock = locking::retry_lock(&object_id, options.wait, || {
+            let attempt = try_lock(&file, options.mode).map_err(CrossroadsError::from).map(|locked| locked.then(|| Lock {
+                object_id: object_id.clone(),
+                mode: options.mode,
+                token: token.clone(),
+                expires_at: None,
+            }));
+
+            future::ready(attempt)
+        }).await?;
+
+        HELD_LOCKS.lock().unwrap().insert(token, file);
+
+        Ok(lock)
+    }
+
+    async fn unlock(&self, lock: Lock) -> Result<(), CrossroadsError> {
+        match HELD_LOCKS.lock().unwrap().remove(&lock.token) {
+            Some(_) => Ok(()),
+            None => Err(CrossroadsError::NotFound(format!("lock {} on {}", lock.token, lock.object_id))),
+        }
+    }
+}
+
+/// Path of the file whose locks stand for those of the file at `path`.
+fn lock_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.lock", name))
+}
+
+/// Takes a lock on the whole of `file` without waiting, `false` when a conflicting one is held.
+fn try_lock(file: &NativeFile, mode: LockMode) -> std::io::Result<bool> {
+    use std::os::fd::AsRawFd;
+
+    #[cfg(target_os = "linux")]
+    let result = {
+        // SAFETY: all zeroes is a valid `flock`, whose length of 0 covers the file however it grows.
+        let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+        lock.l_type = match mode {
+            LockMode::Shared => libc::F_RDLCK,
+            LockMode::Exclusive => libc::F_WRLCK,
+        } as libc::c_short;
+        lock.l_whence = libc::SEEK_SET as libc::c_short;
+
+        // SAFETY: the descriptor stays open for the call, and `lock` outlives it.
+        unsafe { libc::fcntl(file.as_raw_fd(), libc::F_OFD_SETLK, &lock) }
+    };
+
+    #[cfg(not(target_os = "linux"))]
+    let result = {
+        let operation = match mode {
+            LockMode::Shared => libc::LOCK_SH,
+            LockMode::Exclusive => libc::LOCK_EX,
+        };
+
+        // SAFETY: the descriptor stays open for the call.
+        unsafe { libc::flock(file.as_raw_fd(), operation | libc::LOCK_NB) }
+    };
+
+    if result == 0 {
+        return Ok(true)
+    }
+
+    let error = std::io::Error::last_os_error();
+
+    match error.raw_os_error() {
+        Some(libc::EAGAIN | libc::EACCES) => Ok(false),
+        _ => Err(error),
+    }
+}
+
+/// Thumbnails of images are rendered here, see `thumbnail::generate`.
+#[async_trait]
+impl Preview for NativeFs {
+    async fn thumbnail(&self, object_id: ObjectId, size: ThumbnailSize) -> Result<Thumbnail, CrossroadsError> {
+        let path = fs::canonicalize(self.root.clone() + object_id.as_str())?;
+        thumbnail::generate(self, object_id, &thumbnail::file_uri(&path), size).await
+    }
+}
+
+/// Local file systems keep no former contents, see `Capabilities::versions`.
+#[async_trait]
+impl Versions for NativeFs {
+    async fn list_revisions(&self, _object_id: ObjectId) -> Result<Vec<Revision>, CrossroadsError> {
+        Err(CrossroadsError::unsupported("NativeFs file versions"))
+    }
+
+    async fn read_revision(&self, _object_id: ObjectId, _revision_id: &str) -> Result<ByteStream, CrossroadsError> {
+        Err(CrossroadsError::unsupported("NativeFs file versions"))
+    }
+
+    async fn restore_revision(&self, _object_id: ObjectId, _revision_id: &str) -> Result<(), CrossroadsError> {
+        Err(CrossroadsError::unsupported("NativeFs file versions"))
+    }
+}
+
+#[async_trait]
+impl Trash for NativeFs {
+    async fn send_to_trash(&self, object_id: crate::interfaces::filesystem::ObjectId) -> Result<(), CrossroadsError> {
+        dbg!(self.root.clone() + object_id.as_str());
+        trash::delete(self.root.clone() + object_id.as_str())?;
+        Ok(())
+    }
+
+    async fn list_trash(&self) -> Result<Vec<TrashedItem>, CrossroadsError> {
+        #[cfg(not(target_os = "macos"))]
+        {
+            let mut items = vec![];
+
+            for (item, original_location) in self.trashed_items()? {
+                let size = match trash::os_limited::metadata(&item).map(|metadata| metadata.size) {
+                    Ok(trash::TrashItemSize::Bytes(size)) => Some(size),
+                    _ => None,
+                };
+
+                items.push(TrashedItem {
+                    id: item.id.to_string_lossy().to_string(),
+                    name: item.name,
+                    original_location: Some(original_location),
+                    deleted_at: DateTime::<Utc>::from_timestamp(item.time_deleted, 0),
+                    size,
+                });
+            }
+
+            Ok(items)
+        }
+
+        #[cfg(target_os = "macos")]
+        Err(CrossroadsError::unsupported("NativeFs trash listing on macOS"))
+    }
+
+    async fn restore_from_trash(&self, trashed_id: &str) -> Result<(), CrossroadsError> {
+        #[cfg(not(target_os = "macos"))]
+        {
+            trash::os_limited::restore_all([self.trashed_item(trashed_id)?])?;
+            Ok(())
+        }
+
+        #[cfg(target_os = "macos")]
+        Err(CrossroadsError::unsupported("NativeFs trash restore on macOS"))
+    }
+
+    async fn delete_from_trash(&self, trashed_id: &str) -> Result<(), CrossroadsError> {
+        #[cfg(not(target_os = "macos"))]
+        {
+            trash::os_limited::purge_all([self.trashed_item(trashed_id)?])?;
+            Ok(())
+        }
+
+        #[cfg(target_os = "macos")]
+        Err(CrossroadsError::unsupported("NativeFs trash deletion on macOS"))
+    }
+
+    /// Only empties the items deleted from below the root, the trash being shared by the whole system.
+    async fn empty_trash(&self) -> Result<(), CrossroadsError> {
+        #[cfg(not(target_os = "macos"))]
+        {
+            let items: Vec<trash::TrashItem> = self.trashed_items()?.into_iter().map(|(item, _)| item).collect();
+            trash::os_limited::purge_all(items)?;
+            Ok(())
+        }
+
+        #[cfg(target_os = "macos")]
+        Err(CrossroadsError::unsupported("NativeFs trash emptying on macOS"))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::providers::native_fs::*;
+    use crate::interfaces::filesystem::FileSystem;
+
+    #[tokio::test]
+    async fn native_fs_request_works() {
+        let x = NativeFs::new("./sandbox/".to_string());
+        let object_id = ObjectId::new(String::from("hello-world.txt"), FileType::File);
+        let result = x.read_file(object_id).await;
+        assert!(result.is_ok());
+        assert_eq!(String::from_utf8(result.unwrap().to_vec()).unwrap(), String::from("hello world!"));
+    }
+
+    #[tokio::test]
+    async fn native_fs_list_folder_content() {
+        let x = NativeFs::new("./sandbox/".to_string());
+
+        let object_id = ObjectId::new(String::from(""), FileType::Directory);
+
+        let result = x.read_directory(object_id).await;
+
+        assert!(result.is_ok());
+
+        assert_eq!("hello-world.txt", result.as_ref().unwrap()[0].name);
+    }
+
+    fn temporary_fs(name: &str) -> NativeFs {
+        let root = std::env::temp_dir().join(format!("crossroads-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        NativeFs::new(root.to_string_lossy().to_string() + "/")
+    }
+
+    #[tokio::test]
+    async fn native_fs_stream_round_trip() {
+        let x = temporary_fs("stream");
+
+        let object_id = ObjectId::plain_text(String::from("streamed.txt"));
+        let chunks = vec![Ok(Bytes::from_static(b"hello ")), Ok(Bytes::from_static(b"world!"))];
+
+        let result = x.write_file_stream(object_id.clone(), Box::pin(stream::iter(chunks)), None).await;
+        assert!(result.is_ok());
+
+        let content: Vec<Bytes> = x.read_file_stream(object_id).await.unwrap().try_collect().await.unwrap();
+        assert_eq!(content.concat(), b"hello world!".to_vec());
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_read_range() {
+        let x = temporary_fs("range");
+
+        let object_id = ObjectId::plain_text(String::from("range.txt"));
+        x.write_file(object_id.clone(), b"hello world!".to_vec()).await.unwrap();
+
+        let range = x.read_file_range(object_id.clone(), 6, 5).await.unwrap();
+        assert_eq!(range.content, b"world".to_vec());
+        assert_eq!(range.total_size, 12);
+
+        let range = x.read_file_range(object_id, 20, 5).await.unwrap();
+        assert!(range.content.is_empty());
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_read_directory_pages() {
+        let x = temporary_fs("pages");
+
+        for name in ["c.txt", "a.txt", "e.txt", "b.txt", "d.txt"] {
+            x.write_file(ObjectId::plain_text(name.to_string()), vec![]).await.unwrap();
+        }
+
+        let first = x.read_directory_page(ObjectId::root(), 2, None).await.unwrap();
+        assert_eq!(first.files.iter().map(|file| file.name.as_str()).collect::<Vec<_>>(), vec!["a.txt", "b.txt"]);
+
+        let second = x.read_directory_page(ObjectId::root(), 2, first.next_page_token).await.unwrap();
+        assert_eq!(second.files.iter().map(|file| file.name.as_str()).collect::<Vec<_>>(), vec!["c.txt", "d.txt"]);
+
+        let last = x.read_directory_page(ObjectId::root(), 2, second.next_page_token).await.unwrap();
+        assert_eq!(last.files.len(), 1);
+        assert!(last.next_page_token.is_none());
+
+        let streamed: Vec<File> = x.read_directory_stream(ObjectId::root()).try_collect().await.unwrap();
+        assert_eq!(streamed.len(), 5);
+
+        // Names that are not UTF-8 are neither skipped nor repeated.
+        use std::os::unix::ffi::OsStrExt;
+        fs::write(Path::new(&x.root).join(std::ffi::OsStr::from_bytes(b"c\xff.txt")), "").unwrap();
+        fs::write(Path::new(&x.root).join(std::ffi::OsStr::from_bytes(b"c\xfe.txt")), "").unwrap();
+
+        let mut page_token = None;
+        let mut count = 0;
+
+        loop {
+            let page = x.read_directory_page(ObjectId::root(), 1, page_token).await.unwrap();
+            count += page.files.len();
+            page_token = page.next_page_token;
+
+            if page_token.is_none() {
+                break
+            }
+        }
+
+        assert_eq!(count, 7);
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_copy_with_conflicts() {
+        let x = temporary_fs("copy");
+
+        fs::create_dir_all(x.root.clone() + "folder/nested").unwrap();
+        fs::write(x.root.clone() + "folder/nested/file.txt", "content").unwrap();
+        fs::create_dir(x.root.clone() + "destination").unwrap();
+
+        let folder = ObjectId::directory("folder".to_string());
+        let destination = ObjectId::directory("destination".to_string());
+
+        let copy = x.copy(folder.clone(), destination.clone(), "folder", ConflictPolicy::Fail).await.unwrap();
+        assert_eq!(copy, ObjectId::directory("destination/folder".to_string()));
+        assert_eq!(fs::read_to_string(x.root.clone() + "destination/folder/nested/file.txt").unwrap(), "content");
+
+        let result = x.copy(folder.clone(), destination.clone(), "folder", ConflictPolicy::Fail).await;
+        assert!(matches!(result, Err(CrossroadsError::AlreadyExists(_))));
+
+        let copy = x.copy(folder.clone(), destination.clone(), "folder", ConflictPolicy::Rename).await.unwrap();
+        assert_eq!(copy.as_str(), "destination/folder (1)");
+
+        let file = ObjectId::plain_text("folder/nested/file.txt".to_string());
+        fs::write(x.root.clone() + "destination/file.txt", "old").unwrap();
+        x.copy(file, destination.clone(), "file.txt", ConflictPolicy::Replace).await.unwrap();
+        assert_eq!(fs::read_to_string(x.root.clone() + "destination/file.txt").unwrap(), "content");
+
+        // A copy that fails leaves the entry it would have replaced, and nothing else.
+        fs::create_dir(x.root.clone() + "broken").unwrap();
+        fs::write(x.root.clone() + "broken/file.txt", "content").unwrap();
+        let _socket = std::os::unix::net::UnixListener::bind(x.root.clone() + "broken/socket").unwrap();
+        fs::create_dir(x.root.clone() + "destination/broken").unwrap();
+        fs::write(x.root.clone() + "destination/broken/kept.txt", "kept").unwrap();
+
+        let result = x.copy(ObjectId::directory("broken".to_string()), destination.clone(), "broken", ConflictPolicy::Replace).await;
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(x.root.clone() + "destination/broken/kept.txt").unwrap(), "kept");
+        assert_eq!(fs::read_dir(x.root.clone() + "destination").unwrap().count(), 4);
+
+        x.copy(folder.clone(), destination, "broken", ConflictPolicy::Replace).await.unwrap();
+        assert!(fs::metadata(x.root.clone() + "destination/broken/kept.txt").is_err());
+        assert_eq!(fs::read_to_string(x.root.clone() + "destination/broken/nested/file.txt").unwrap(), "content");
+
+        let result = x.copy(folder.clone(), ObjectId::directory("folder/nested".to_string()), "folder", ConflictPolicy::Rename).await;
+        assert!(matches!(result, Err(CrossroadsError::InvalidInput(_))));
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_delete_recursive() {
+        let x = temporary_fs("delete");
+
+        fs::create_dir_all(x.root.clone() + "folder/nested/deeper").unwrap();
+        fs::write(x.root.clone() + "folder/file.txt", "content").unwrap();
+        fs::write(x.root.clone() + "folder/nested/deeper/file.txt", "content").unwrap();
+        fs::create_dir(x.root.clone() + "kept").unwrap();
+        fs::write(x.root.clone() + "kept/file.txt", "content").unwrap();
+        symlink(x.root.clone() + "kept", x.root.clone() + "folder/link").unwrap();
+
+        let folder = ObjectId::directory("folder".to_string());
+
+        let result = x.delete(folder.clone()).await;
+        assert!(result.is_err());
+
+        let failures = x.delete_recursive(folder.clone()).await.unwrap();
+        assert!(failures.is_empty());
+        assert!(fs::symlink_metadata(x.root.clone() + "folder").is_err());
+        assert_eq!(fs::read_to_string(x.root.clone() + "kept/file.txt").unwrap(), "content");
+
+        let result = x.delete_recursive(folder).await;
+        assert!(matches!(result, Err(CrossroadsError::NotFound(_))));
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_write_if_match() {
+        let x = temporary_fs("if-match");
+
+        let object_id = ObjectId::plain_text("shared.txt".to_string());
+        x.write_file(object_id.clone(), b"first".to_vec()).await.unwrap();
+
+        let version = x.get_metadata(object_id.clone()).await.unwrap().version.unwrap();
+        let listed = x.read_directory(ObjectId::root()).await.unwrap();
+        assert_eq!(listed[0].metadata.as_ref().unwrap().version.as_ref(), Some(&version));
+
+        let content = Box::pin(stream::iter(vec![Ok(Bytes::from_static(b"second"))]));
+        x.write_file_stream_if_match(object_id.clone(), content, None, version.clone()).await.unwrap();
+        assert_eq!(fs::read_to_string(x.root.clone() + "shared.txt").unwrap(), "second");
+
+        let content = Box::pin(stream::iter(vec![Ok(Bytes::from_static(b"third"))]));
+        let result = x.write_file_stream_if_match(object_id, content, None, version).await;
+        assert!(matches!(result, Err(CrossroadsError::Conflict(_))));
+        assert_eq!(fs::read_to_string(x.root.clone() + "shared.txt").unwrap(), "second");
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_resolve_path() {
+        let x = temporary_fs("resolve");
+
+        fs::create_dir_all(x.root.clone() + "Documents/Reports").unwrap();
+        fs::write(x.root.clone() + "Documents/Reports/report.pdf", "report").unwrap();
+
+        let report = x.resolve_path("/Documents/Reports/report.pdf").await.unwrap();
+        assert_eq!(report, ObjectId::plain_text("Documents/Reports/report.pdf".to_string()));
+        assert_eq!(x.path_of(report).await.unwrap(), "Documents/Reports/report.pdf");
+
+        let reports = x.resolve_path("Documents/Reports/").await.unwrap();
+        assert!(reports.is_directory());
+
+        let result = x.resolve_path("/Documents/missing.pdf").await;
+        assert!(matches!(result, Err(CrossroadsError::NotFound(_))));
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_search() {
+        let x = temporary_fs("search");
+
+        fs::create_dir_all(x.root.clone() + "Documents/Reports").unwrap();
+        fs::write(x.root.clone() + "Documents/Reports/Annual report.pdf", "annual report").unwrap();
+        fs::write(x.root.clone() + "Documents/report.txt", "report").unwrap();
+        fs::write(x.root.clone() + "notes.pdf", "notes").unwrap();
+
+        let query = SearchQuery { name: Some("report".to_string()), min_size: Some(1), ..Default::default() };
+        let mut names: Vec<String> = x.search(query).await.unwrap().map_ok(|file| file.name).try_collect().await.unwrap();
+        names.sort();
+        assert_eq!(names, vec!["Annual report.pdf", "Reports", "report.txt"]);
+
+        let query = SearchQuery { name: Some("*.pdf".to_string()), max_size: Some(6), ..Default::default() };
+        let files: Vec<File> = x.search(query).await.unwrap().try_collect().await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].id.as_str(), "notes.pdf");
+
+        let query = SearchQuery { full_text: Some("report".to_string()), ..Default::default() };
+        assert!(matches!(x.search(query).await, Err(CrossroadsError::Unsupported(_))));
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_watch() {
+        use crate::interfaces::watch::ChangeKind;
+        use futures::StreamExt;
+
+        let x = temporary_fs("watch");
+
+        fs::create_dir(x.root.clone() + "folder").unwrap();
+        fs::write(x.root.clone() + "folder/edited.txt", "first").unwrap();
+        fs::write(x.root.clone() + "folder/old.txt", "renamed").unwrap();
+        fs::write(x.root.clone() + "gone.txt", "deleted").unwrap();
+
+        let cursor = x.watch_cursor(ObjectId::root()).await.unwrap();
+
+        fs::write(x.root.clone() + "folder/edited.txt", "second").unwrap();
+        fs::rename(x.root.clone() + "folder/old.txt", x.root.clone() + "new.txt").unwrap();
+        fs::remove_file(x.root.clone() + "gone.txt").unwrap();
+
+        let batch = x.changes_since(ObjectId::root(), cursor).await.unwrap();
+        let mut events: Vec<(ChangeKind, &str)> = batch.events.iter().map(|event| (event.kind, event.object_id.as_str())).collect();
+        events.sort_by_key(|(_, id)| id.to_string());
+
+        // The folder lost an entry, which changes its modification time.
+        assert!(events.contains(&(ChangeKind::Modified, "folder/edited.txt")));
+        assert!(events.contains(&(ChangeKind::Deleted, "gone.txt")));
+        assert!(events.contains(&(ChangeKind::Renamed, "new.txt")));
+
+        // Only inotify can report the file before the interval is over.
+        let mut changes = x.watch(ObjectId::root(), batch.cursor, Duration::from_secs(60));
+
+        let (batch, _) = tokio::join!(tokio::time::timeout(Duration::from_secs(10), changes.next()), async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            fs::write(x.root.clone() + "folder/added.txt", "created").unwrap();
+        });
+
+        let batch = batch.unwrap().unwrap().unwrap();
+        let created = batch.events.iter().find(|event| event.kind == ChangeKind::Created).unwrap();
+        assert_eq!(created.object_id.as_str(), "folder/added.txt");
+        assert_eq!(created.file.as_ref().unwrap().name, "added.txt");
+
+        // Cursors of the stream hold the whole snapshot, like those of `changes_since`.
+        drop(changes);
+        assert!(x.changes_since(ObjectId::root(), batch.cursor).await.unwrap().events.is_empty());
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_versions_unsupported() {
+        let x = NativeFs::new("./sandbox/".to_string());
+
+        assert!(!x.capabilities().versions);
+        assert!(!x.capabilities().sharing && x.as_sharing().is_none());
+
+        let versions = x.as_versions().unwrap();
+        let result = versions.list_revisions(ObjectId::plain_text("hello-world.txt".to_string())).await;
+        assert!(matches!(result, Err(CrossroadsError::Unsupported(_))));
+    }
+
+    #[tokio::test]
+    async fn native_fs_trash() {
+        let x = temporary_fs("trash");
+
+        fs::create_dir(x.root.clone() + "folder").unwrap();
+        fs::write(x.root.clone() + "folder/trashed.txt", "trashed").unwrap();
+        fs::write(x.root.clone() + "folder/purged.txt", "purged").unwrap();
+
+        x.send_to_trash(ObjectId::plain_text("folder/trashed.txt".to_string())).await.unwrap();
+        x.send_to_trash(ObjectId::plain_text("folder/purged.txt".to_string())).await.unwrap();
+
+        let mut items = x.list_trash().await.unwrap();
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(items.iter().map(|item| item.name.as_str()).collect::<Vec<_>>(), vec!["purged.txt", "trashed.txt"]);
+        assert_eq!(items[1].original_location.as_deref(), Some("folder"));
+        assert_eq!(items[1].size, Some(7));
+
+        x.restore_from_trash(&items[1].id).await.unwrap();
+        assert_eq!(fs::read_to_string(x.root.clone() + "folder/trashed.txt").unwrap(), "trashed");
+
+        x.empty_trash().await.unwrap();
+        assert!(x.list_trash().await.unwrap().is_empty());
+
+        let result = x.delete_from_trash(&items[0].id).await;
+        assert!(matches!(result, Err(CrossroadsError::NotFound(_))));
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_quota() {
+        let x = temporary_fs("quota");
+
+        let quota = x.quota().await.unwrap();
+        assert!(quota.total.unwrap() >= quota.used.unwrap());
+        assert!(quota.total.unwrap() >= quota.free.unwrap());
+
+        fs::remove_dir_all(x.root.clone()).unwrap();
+
+        let result = x.quota().await;
+        assert!(matches!(result, Err(CrossroadsError::NotFound(_))));
+
+        let x = NativeFs { root: String::new(), ..x };
+        assert!(x.quota().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn native_fs_atomic_writes() {
+        let x = temporary_fs("atomic-writes");
+
+        fs::write(x.root.clone() + "report.txt", "former").unwrap();
+        fs::set_permissions(x.root.clone() + "report.txt", fs::Permissions::from_mode(0o640)).unwrap();
+        symlink(x.root.clone() + "report.txt", x.root.clone() + "link.txt").unwrap();
+
+        #[cfg(target_os = "linux")]
+        xattr::set(Path::new(&(x.root.clone() + "report.txt")), "project", "apollo").unwrap();
+
+        x.write_file(ObjectId::plain_text("link.txt".to_string()), b"replaced".to_vec()).await.unwrap();
+
+        assert_eq!(fs::read_to_string(x.root.clone() + "report.txt").unwrap(), "replaced");
+        assert!(fs::symlink_metadata(x.root.clone() + "link.txt").unwrap().is_symlink());
+        assert_eq!(fs::metadata(x.root.clone() + "report.txt").unwrap().permissions().mode() & 0o777, 0o640);
+
+        #[cfg(target_os = "linux")]
+        assert_eq!(x.get_properties(ObjectId::plain_text("report.txt".to_string())).await.unwrap()["project"], "apollo");
+
+        // Nothing is left of the temporary file.
+        assert_eq!(fs::read_dir(&x.root).unwrap().count(), 2);
+
+        let x = x.with_in_place_writes();
+        x.write_file(ObjectId::plain_text("report.txt".to_string()), b"in place".to_vec()).await.unwrap();
+        assert_eq!(fs::read_to_string(x.root.clone() + "report.txt").unwrap(), "in place");
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_locks() {
+        use crate::interfaces::locking::{LockMode, LockOptions, Locking};
+        use crate::error::CrossroadsError;
+
+        let x = temporary_fs("locks");
+
+        fs::write(x.root.clone() + "report.txt", "locked").unwrap();
+        let report = ObjectId::plain_text("report.txt".to_string());
+        let shared = LockOptions { mode: LockMode::Shared, ..Default::default() };
+
+        // Locks of open file descriptions conflict within a process too.
+        let lock = x.lock(report.clone(), LockOptions::default()).await.unwrap();
+        assert!(matches!(x.lock(report.clone(), LockOptions::default()).await, Err(CrossroadsError::Conflict(_))));
+        assert!(matches!(x.lock(report.clone(), shared.clone()).await, Err(CrossroadsError::Conflict(_))));
+
+        x.unlock(lock.clone()).await.unwrap();
+        assert!(matches!(x.unlock(lock).await, Err(CrossroadsError::NotFound(_))));
+
+        let first = x.lock(report.clone(), shared.clone()).await.unwrap();
+        let second = x.lock(report.clone(), shared).await.unwrap();
+        assert!(matches!(x.lock(report.clone(), LockOptions::default()).await, Err(CrossroadsError::Conflict(_))));
+        x.unlock(first).await.unwrap();
+        x.unlock(second).await.unwrap();
+
+        // Writes replace the file, but not the one locked.
+        let lock = x.lock(report.clone(), LockOptions::default()).await.unwrap();
+        let content: ByteStream = Box::pin(futures::stream::once(async { Ok(Bytes::from_static(b"rewritten")) }));
+        x.write_file_stream(report.clone(), content, None).await.unwrap();
+        assert!(matches!(x.lock(report.clone(), LockOptions::default()).await, Err(CrossroadsError::Conflict(_))));
+        x.unlock(lock).await.unwrap();
+
+        assert!(x.lock(report, LockOptions::default()).await.is_ok());
+        assert!(matches!(x.lock(ObjectId::plain_text("missing.txt".to_string()), LockOptions::default()).await, Err(CrossroadsError::NotFound(_))));
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_set_metadata() {
+        use chrono::TimeZone;
+        use crate::error::CrossroadsError;
+
+        let x = temporary_fs("set-metadata");
+
+        fs::write(x.root.clone() + "report.txt", "report").unwrap();
+        let report = ObjectId::plain_text("report.txt".to_string());
+        let owner = fs::metadata(x.root.clone() + "report.txt").unwrap();
+        let modified_at = Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap();
+        let accessed_at = Utc.with_ymd_and_hms(2021, 6, 7, 8, 9, 10).unwrap();
+
+        x.set_metadata(report.clone(), MetadataUpdate {
+            modified_at: Some(modified_at),
+            accessed_at: Some(accessed_at),
+            permissions: Some(Permissions::Unix(0o600)),
+            owner: Some(UserId::UserAndGroup(owner.uid(), owner.gid())),
+            ..Default::default()
+        }).await.unwrap();
+
+        let metadata = x.get_metadata(report.clone()).await.unwrap();
+        assert_eq!(metadata.modified_at, Some(modified_at));
+        assert_eq!(metadata.accessed_at, Some(accessed_at));
+        assert_eq!(metadata.permissions, Some(Permissions::Unix(0o100600)));
+
+        // Nothing is changed when a field is unsupported.
+        let update = MetadataUpdate { created_at: Some(modified_at), permissions: Some(Permissions::Unix(0o644)), ..Default::default() };
+        assert!(matches!(x.set_metadata(report.clone(), update).await, Err(CrossroadsError::Unsupported(message)) if message.contains("CreatedAt")));
+        assert_eq!(x.get_metadata(report).await.unwrap().permissions, Some(Permissions::Unix(0o100600)));
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn native_fs_mime_types() {
+        use futures::TryStreamExt;
+
+        let x = temporary_fs("mime");
+
+        // A PNG named as a JPEG, which only reading the content tells.
+        fs::write(x.root.clone() + "photo.jpg", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        fs::write(x.root.clone() + "notes.txt", "text").unwrap();
+
+        let folder = File {
+            id: ObjectId::plain_text(String::new()),
+            name: "folder".to_string(),
+            metadata: Some(Metadata { mime_type: Some(crate::mime::DIRECTORY.to_string()), ..Default::default() }),
+        };
+        assert!(x.create(ObjectId::root(), folder).await.unwrap().is_directory());
+
+        let files = x.read_directory(ObjectId::root()).await.unwrap();
+        let mut listed: Vec<(String, Option<String>)> = files.into_iter().map(|file| (file.name, file.metadata.unwrap().mime_type)).collect();
+        listed.sort();
+
+        assert_eq!(listed, vec![
+            ("folder".to_string(), Some("inode/directory".to_string())),
+            ("notes.txt".to_string(), Some("text/plain".to_string())),
+            ("photo.jpg".to_string(), Some("image/jpeg".to_string())),
+        ]);
+
+        let photo = ObjectId::plain_text("photo.jpg".to_string());
+        assert_eq!(x.get_metadata(photo.clone()).await.unwrap().mime_type.as_deref(), Some("image/png"));
+        assert_eq!(x.detect_mime_type(photo).await.unwrap().as_deref(), Some("image/png"));
+
+        let query = SearchQuery { mime_type: Some("text/plain".to_string()), ..Default::default() };
+        let found: Vec<File> = x.search(query).await.unwrap().try_collect().await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "notes.txt");
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn native_fs_properties() {
+        let x = temporary_fs("properties");
+
+        fs::write(x.root.clone() + "report.txt", "content").unwrap();
+        let object_id = ObjectId::plain_text("report.txt".to_string());
+
+        let properties = BTreeMap::from([("project".to_string(), "apollo".to_string()), ("reviewed".to_string(), "yes".to_string())]);
+        x.set_properties(object_id.clone(), properties.clone()).await.unwrap();
+        assert_eq!(x.get_properties(object_id.clone()).await.unwrap(), properties);
+
+        x.remove_properties(object_id.clone(), &["reviewed".to_string(), "missing".to_string()]).await.unwrap();
+
+        let files = x.read_directory(ObjectId::root()).await.unwrap();
+        assert_eq!(files[0].metadata.as_ref().unwrap().properties, None);
+
+        let x = x.with_listed_properties();
+        let files = x.read_directory(ObjectId::root()).await.unwrap();
+        let listed = files[0].metadata.as_ref().and_then(|metadata| metadata.properties.clone());
+        assert_eq!(listed, Some(BTreeMap::from([("project".to_string(), "apollo".to_string())])));
+
+        fs::remove_dir_all(x.root).unwrap();
     }
 }
\ No newline at end of file
@@ -2,15 +2,20 @@ use oauth2::basic::{BasicClient, BasicTokenType, BasicErrorResponseType};
 use oauth2::reqwest::async_http_client;
 use oauth2::{
     AuthType, AuthUrl, AuthorizationCode, ClientId, CsrfToken, PkceCodeChallenge,
-    RedirectUrl, Scope, TokenUrl, StandardTokenResponse, EmptyExtraTokenFields, PkceCodeVerifier, TokenResponse, StandardErrorResponse, Client, StandardTokenIntrospectionResponse, StandardRevocableToken, RevocationErrorResponseType,
+    RedirectUrl, Scope, TokenUrl, StandardTokenResponse, EmptyExtraTokenFields, PkceCodeVerifier, TokenResponse, StandardErrorResponse, Client, StandardTokenIntrospectionResponse, StandardRevocableToken, RevocationErrorResponseType, RequestTokenError,
 };
 use std::io::{BufRead, BufReader, Write};
 use std::net::TcpListener;
+use std::sync::Arc;
 use oauth2::url::Url;
 
+use crate::error::CrossroadsError;
+use crate::providers::path_cache::PathCache;
+
 use super::token::{TokenStorage, OneDriveToken};
 use super::{OneDrive};
 
+#[allow(clippy::manual_flatten, clippy::needless_borrowed_reference)]
 async fn listen_for_token(client: BasicClient, csrf_state: CsrfToken, pkce_code_verifier: PkceCodeVerifier) -> Result<StandardTokenResponse<EmptyExtraTokenFields, BasicTokenType>, ()> {
     // A very naive implementation of the redirect server.
     let listener = TcpListener::bind("127.0.0.1:3003").unwrap();
@@ -87,6 +92,7 @@ async fn listen_for_token(client: BasicClient, csrf_state: CsrfToken, pkce_code_
 }
 
 impl OneDrive {
+    #[allow(clippy::type_complexity)]
     fn new_client(client_id: String) -> Client<StandardErrorResponse<BasicErrorResponseType>, StandardTokenResponse<EmptyExtraTokenFields, BasicTokenType>, BasicTokenType, StandardTokenIntrospectionResponse<EmptyExtraTokenFields, BasicTokenType>, StandardRevocableToken, StandardErrorResponse<RevocationErrorResponseType>>
     {
         let graph_client_id = ClientId::new(client_id);
@@ -112,7 +118,8 @@ impl OneDrive {
         )
     }
 
-    pub async fn fetch_credentials(&self) -> Result<(), Box<dyn std::error::Error>> {
+    #[allow(clippy::to_string_in_format_args)]
+    pub async fn fetch_credentials(&self) -> Result<(), CrossroadsError> {
         let client = Self::new_client(self.client_id.clone());
     
         // Microsoft Graph supports Proof Key for Code Exchange (PKCE - https://oauth.net/2/pkce/).
@@ -135,9 +142,10 @@ impl OneDrive {
             authorize_url.to_string()
         );
 
-        open::that(authorize_url.to_string()).expect("Could not open browser to authenticate to OneDrive.");
+        open::that(authorize_url.to_string())?;
 
-        let token = listen_for_token(client, csrf_state, pkce_code_verifier).await.expect("Error fetching OneDrive access token");
+        let token = listen_for_token(client, csrf_state, pkce_code_verifier).await
+            .map_err(|_| CrossroadsError::AuthenticationRequired)?;
 
         self.token.set(Some(token)).await;
 
@@ -145,13 +153,18 @@ impl OneDrive {
     }
 
     pub fn new (token: Option<OneDriveToken>, client_id: String) -> OneDrive {
-        OneDrive { token: TokenStorage::new(token), client_id }
+        OneDrive { token: TokenStorage::new(token), client_id, paths: Arc::new(PathCache::default()) }
     }
 
-    pub async fn refresh_token(&self) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn refresh_token(&self) -> Result<(), CrossroadsError> {
         let client = Self::new_client(self.client_id.clone());
-        if let Some(refresh_token) = self.token.get().await.clone().unwrap().refresh_token() {
-            let token = client.exchange_refresh_token(refresh_token).request_async(async_http_client).await?;
+        let token = self.token.get().await.ok_or(CrossroadsError::AuthenticationRequired)?;
+        if let Some(refresh_token) = token.refresh_token() {
+            let token = client.exchange_refresh_token(refresh_token).request_async(async_http_client).await
+                .map_err(|error| match error {
+                    RequestTokenError::Request(error) => CrossroadsError::Network(Box::new(error)),
+                    _ => CrossroadsError::AuthenticationRequired,
+                })?;
             self.token.set(Some(token)).await;
         } else {
             self.fetch_credentials().await?;
@@ -163,4 +176,11 @@ impl OneDrive {
     pub async fn get_token(&self) -> Option<OneDriveToken> {
         self.token.get().await
     }
+
+    pub(crate) async fn access_token(&self) -> Result<String, CrossroadsError> {
+        match self.token.get().await {
+            Some(token) => Ok(token.access_token().secret().clone()),
+            None => Err(CrossroadsError::AuthenticationRequired),
+        }
+    }
 }
\ No newline at end of file
@@ -1,120 +1,233 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
 use async_trait::async_trait;
-use reqwest::StatusCode;
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures::{stream, TryStreamExt};
+use reqwest::{header::LOCATION, redirect, Method, StatusCode};
+use serde::Deserialize;
 
-use crate::{interfaces::filesystem::{FileSystem, ObjectId, File, Metadata}, providers::onedrive::OneDrive};
+use crate::{error::CrossroadsError, interfaces::filesystem::{FileSystem, ObjectId, File, Metadata, MetadataField, MetadataUpdate, ByteStream, FileRange, DirectoryPage, ConflictPolicy, DeleteFailure}, providers::{chunked, conflict, path_cache, range, onedrive::{OneDrive, checksums}}, transfer};
 
-use oauth2::TokenResponse;
+use onedrive_api::{OneDrive as OneDriveApi, DriveLocation, ItemId, ItemLocation, FileName, ListChildrenFetcher, option::{CollectionOption, DriveItemPutOption}, resource::{DriveItem, Tag}};
 
-use onedrive_api::{OneDrive as OneDriveApi, DriveLocation, ItemId, ItemLocation, FileName, option::DriveItemPutOption, resource::DriveItem};
+/// Delay between two polls of the monitor of a copy.
+const COPY_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
-#[async_trait]
-impl FileSystem for OneDrive {
-    async fn read_file(&self, object_id: ObjectId) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+impl OneDrive {
+    /// Uploads through a session, with the content replaced only if its cTag is still `version` when one is given.
+    async fn upload(&self, object_id: ObjectId, content: ByteStream, size: Option<u64>, version: Option<String>) -> Result<(), CrossroadsError> {
         let drive = OneDriveApi::new(
-            self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+            self.access_token().await?, // Login token to Microsoft Graph.
             DriveLocation::me(),
         );
 
         let item_id : ItemId = object_id.clone().into();
 
-        let item_location = if object_id.to_string() == "".to_string() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
+        let item_location = if object_id.as_str().is_empty() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
 
-        let items_result = drive.get_item(item_location).await;
+        // Upload sessions need the total size in every Content-Range header.
+        let (content, content_len) = match size {
+            Some(size) => (content, size),
+            None => {
+                let buffer = chunked::collect(content).await?;
+                let size = buffer.len() as u64;
+                let content: ByteStream = Box::pin(stream::once(async move { Ok(buffer) }));
+                (content, size)
+            }
+        };
 
-        let item = match items_result {
+        if content_len == 0 {
+            return self.upload_empty(&drive, &object_id, version).await
+        }
+
+        let mut options = DriveItemPutOption::new();
+        options = options.conflict_behavior(onedrive_api::ConflictBehavior::Replace);
+
+        if let Some(version) = version {
+            options = options.if_match(&Tag(version));
+        }
+
+        let upload_session_result = drive.new_upload_session_with_option(item_location, options.clone()).await;
+
+        let upload_session = match upload_session_result {
             Ok(items) => Ok(items),
             Err(error) => {
                 if error.status_code() == Some(StatusCode::UNAUTHORIZED) {
                     self.refresh_token().await?;
                     let drive = OneDriveApi::new(
-                        self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+                        self.access_token().await?, // Login token to Microsoft Graph.
                         DriveLocation::me(),
                     );
-                    Ok(drive.get_item(item_location).await.unwrap())
+                    Ok(drive.new_upload_session_with_option(item_location, options).await?)
                 } else {
                     Err(error)
                 }
             }
         }?;
 
-        Ok(Vec::from(item.content.unwrap().as_str().as_deref().unwrap().as_bytes()))
+        // the size of each byte range MUST be a multiple of 320 KiB
+        let chunk_size: usize = 327_680 * 10;
+        let mut chunks = chunked::rechunk(content, chunk_size);
+        let mut offset: u64 = 0;
+
+        let client = reqwest::Client::new();
+
+        while let Some(chunk) = chunks.try_next().await? {
+            let chunk_len = chunk.len() as u64;
+
+            let request = client.put(upload_session.0.upload_url())
+                .header("Content-Length", chunk_len)
+                .header("Content-Range", format!("bytes {}-{}/{}", offset, offset + chunk_len - 1, content_len))
+                .body(chunk)
+                .bearer_auth(self.access_token().await?);
+
+            request.send().await?.error_for_status()?;
+
+            offset += chunk_len;
+        }
+
+        Ok(())
     }
 
-    async fn write_file(&self, object_id: ObjectId, content: Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
+    async fn get_item(&self, item_location: ItemLocation<'_>) -> Result<DriveItem, CrossroadsError> {
         let drive = OneDriveApi::new(
-            self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+            self.access_token().await?, // Login token to Microsoft Graph.
             DriveLocation::me(),
         );
 
-        let item_id : ItemId = object_id.clone().into();
+        match drive.get_item(item_location).await {
+            Ok(item) => Ok(item),
+            Err(error) => {
+                if error.status_code() == Some(StatusCode::UNAUTHORIZED) {
+                    self.refresh_token().await?;
+                    let drive = OneDriveApi::new(
+                        self.access_token().await?, // Login token to Microsoft Graph.
+                        DriveLocation::me(),
+                    );
+                    Ok(drive.get_item(item_location).await?)
+                } else {
+                    Err(error.into())
+                }
+            }
+        }
+    }
 
-        let item_location = if object_id.to_string() == "".to_string() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
+    /// Empty content cannot go through an upload session, so it is sent in a single request.
+    async fn upload_empty(&self, drive: &OneDriveApi, object_id: &ObjectId, version: Option<String>) -> Result<(), CrossroadsError> {
+        let Some(version) = version else {
+            let item_id: ItemId = object_id.clone().into();
+            drive.upload_small(ItemLocation::from_id(&item_id), Vec::new()).await?;
+            return Ok(())
+        };
 
-        let mut options = DriveItemPutOption::new();
-        options = options.conflict_behavior(onedrive_api::ConflictBehavior::Replace);
+        let response = reqwest::Client::new()
+            .put(format!("https://graph.microsoft.com/v1.0/me/drive/items/{}/content", object_id.as_str()))
+            .header(reqwest::header::IF_MATCH, version)
+            .header(reqwest::header::CONTENT_LENGTH, 0)
+            .bearer_auth(self.access_token().await?)
+            .send().await?;
 
-        let upload_session_result = drive.new_upload_session_with_option(item_location, options).await;
+        let status = response.status().as_u16();
 
-        let upload_session = match upload_session_result {
-            Ok(items) => Ok(items),
+        if !response.status().is_success() {
+            return Err(CrossroadsError::from_status(status, response.text().await.unwrap_or_default()))
+        }
+
+        Ok(())
+    }
+
+    async fn write_properties(&self, object_id: &ObjectId, properties: &BTreeMap<String, String>) -> Result<(), CrossroadsError> {
+        let url = properties_url(object_id);
+
+        if properties.is_empty() {
+            return match self.graph_request(Method::DELETE, &url, None).await {
+                Ok(_) | Err(CrossroadsError::NotFound(_)) => Ok(()),
+                Err(error) => Err(error),
+            }
+        }
+
+        self.graph_request(Method::PUT, &format!("{}/content", url), Some(&serde_json::json!(properties))).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FileSystem for OneDrive {
+    async fn read_file_stream(&self, object_id: ObjectId) -> Result<ByteStream, CrossroadsError> {
+        let drive = OneDriveApi::new(
+            self.access_token().await?, // Login token to Microsoft Graph.
+            DriveLocation::me(),
+        );
+
+        let item_id : ItemId = object_id.clone().into();
+
+        let item_location = if object_id.as_str().is_empty() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
+
+        let download_url_result = drive.get_item_download_url(item_location).await;
+
+        let download_url = match download_url_result {
+            Ok(url) => Ok(url),
             Err(error) => {
                 if error.status_code() == Some(StatusCode::UNAUTHORIZED) {
                     self.refresh_token().await?;
                     let drive = OneDriveApi::new(
-                        self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+                        self.access_token().await?, // Login token to Microsoft Graph.
                         DriveLocation::me(),
                     );
-                    Ok(drive.new_upload_session(item_location).await?)
+                    Ok(drive.get_item_download_url(item_location).await?)
                 } else {
                     Err(error)
                 }
             }
         }?;
 
-        // the size of each byte range MUST be a multiple of 320 KiB
-        let chunk_size: u64 = 327_680;
-        let content_len: u64 = content.len().try_into().unwrap();
-        let chunks = content.chunks_exact(chunk_size.try_into().unwrap());
-        let chunks_len: u64 = chunks.len().try_into().unwrap();
-        let remainder = chunks.remainder().to_vec();
-
-        println!("{}", chunks.len());
+        // The download URL is pre-authenticated and must not receive the bearer token.
+        let response = reqwest::get(download_url).await?.error_for_status()?;
 
-        let client = reqwest::Client::new();
-
-        for (i, chunk) in chunks.enumerate() {
-            let index: u64 = i.try_into().unwrap();
-            println!("Sending chunk {}", i);
-
-            let request = client.put(upload_session.0.upload_url())
-                .header("Content-Length", chunk_size)
-                .header("Content-Range", format!("bytes {}-{}/{}", index*chunk_size, (index+1)*chunk_size-1, content_len))
-                .body(chunk.to_vec())
-                .bearer_auth(self.token.get().await.clone().unwrap().access_token().secret());
+        Ok(Box::pin(response.bytes_stream().map_err(CrossroadsError::from)))
+    }
 
-            request.send().await?;
-        }
+    async fn write_file_stream(&self, object_id: ObjectId, content: ByteStream, size: Option<u64>) -> Result<(), CrossroadsError> {
+        self.upload(object_id, content, size, None).await
+    }
 
-        let request = client.put(upload_session.0.upload_url())
-                .header("Content-Length", remainder.len())
-                .header("Content-Range", format!("bytes {}-{}/{}", chunks_len*chunk_size, content_len-1, content_len))
-                .body(remainder)
-                .bearer_auth(self.token.get().await.clone().unwrap().access_token().secret());
+    /// Graph answers 412 when the cTag no longer matches.
+    async fn write_file_stream_if_match(&self, object_id: ObjectId, content: ByteStream, size: Option<u64>, version: String) -> Result<(), CrossroadsError> {
+        self.upload(object_id, content, size, Some(version)).await
+    }
 
-            request.send().await?;
+    async fn read_file_range(&self, object_id: ObjectId, offset: u64, length: u64) -> Result<FileRange, CrossroadsError> {
+        let client = reqwest::Client::new();
+        let url = format!("https://graph.microsoft.com/v1.0/me/drive/items/{}/content", object_id.as_str());
+
+        let response = client.get(&url)
+            .header(reqwest::header::RANGE, range::range_header(offset, length))
+            .bearer_auth(self.access_token().await?)
+            .send().await?;
+
+        let response = if response.status() == StatusCode::UNAUTHORIZED {
+            self.refresh_token().await?;
+            client.get(&url)
+                .header(reqwest::header::RANGE, range::range_header(offset, length))
+                .bearer_auth(self.access_token().await?)
+                .send().await?
+        } else {
+            response
+        };
 
-        Ok(())
+        range::range_from_response(response, offset, length).await
     }
 
-    async fn delete(&self, object_id: ObjectId) -> Result<(), Box<dyn std::error::Error>> {
+    async fn delete(&self, object_id: ObjectId) -> Result<(), CrossroadsError> {
         let drive = OneDriveApi::new(
-            self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+            self.access_token().await?, // Login token to Microsoft Graph.
             DriveLocation::me(),
         );
 
         let item_id : ItemId = object_id.clone().into();
 
-        let item_location = if object_id.to_string() == "".to_string() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
+        let item_location = if object_id.as_str().is_empty() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
 
         let request_result = drive.delete(item_location).await;
 
@@ -124,30 +237,38 @@ impl FileSystem for OneDrive {
                 if error.status_code() == Some(StatusCode::UNAUTHORIZED) {
                     self.refresh_token().await?;
                     let drive = OneDriveApi::new(
-                        self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+                        self.access_token().await?, // Login token to Microsoft Graph.
                         DriveLocation::me(),
                     );
-                    Ok(drive.delete(item_location).await.unwrap())
+                    Ok(drive.delete(item_location).await?)
                 } else {
                     Err(error)
                 }
             }
         }?;
 
+        self.paths.invalidate(&object_id);
+
         Ok(())
     }
 
-    async fn move_to(&self, object_id: ObjectId, new_parent_id: ObjectId) -> Result<ObjectId, Box<dyn std::error::Error>> {
+
+    async fn delete_recursive(&self, object_id: ObjectId) -> Result<Vec<DeleteFailure>, CrossroadsError> {
+        // Folders are deleted along with their content in a single call.
+        self.delete(object_id).await.map(|_| vec![])
+    }
+
+    async fn move_to(&self, object_id: ObjectId, new_parent_id: ObjectId) -> Result<ObjectId, CrossroadsError> {
         let drive = OneDriveApi::new(
-            self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+            self.access_token().await?, // Login token to Microsoft Graph.
             DriveLocation::me(),
         );
 
         let item_id : ItemId = object_id.clone().into();
-        let item_location = if object_id.to_string() == "".to_string() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
+        let item_location = if object_id.as_str().is_empty() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
 
         let parent_item_id : ItemId = new_parent_id.clone().into();
-        let parent_location = if object_id.to_string() == "".to_string() { ItemLocation::root() } else { ItemLocation::from_id(&parent_item_id) };
+        let parent_location = if new_parent_id.as_str().is_empty() { ItemLocation::root() } else { ItemLocation::from_id(&parent_item_id) };
 
         let request_result = drive.move_(item_location, parent_location, None).await;
 
@@ -157,27 +278,55 @@ impl FileSystem for OneDrive {
                 if error.status_code() == Some(StatusCode::UNAUTHORIZED) {
                     self.refresh_token().await?;
                     let drive = OneDriveApi::new(
-                        self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+                        self.access_token().await?, // Login token to Microsoft Graph.
                         DriveLocation::me(),
                     );
-                    Ok(drive.move_(item_location, parent_location, None).await.unwrap())
+                    Ok(drive.move_(item_location, parent_location, None).await?)
                 } else {
                     Err(error)
                 }
             }
         }?;
 
+        self.paths.invalidate(&object_id);
+
         Ok(ObjectId::new(item.id.unwrap().as_str().to_string(), object_id.file_type()))
     }
 
-    async fn rename(&self, object_id: ObjectId, new_name: String) -> Result<ObjectId, Box<dyn std::error::Error>> {
+    async fn copy(&self, object_id: ObjectId, new_parent_id: ObjectId, name: &str, conflict: ConflictPolicy) -> Result<ObjectId, CrossroadsError> {
+        let drive = OneDriveApi::new(
+            self.access_token().await?, // Login token to Microsoft Graph.
+            DriveLocation::me(),
+        );
+
+        if conflict == ConflictPolicy::Replace {
+            // The ids of the replaced entries are not known here.
+            self.paths.clear();
+        }
+
+        let copy_result = copy_item(&drive, &object_id, &new_parent_id, name, conflict).await;
+
+        match copy_result {
+            Err(CrossroadsError::AuthenticationRequired) => {
+                self.refresh_token().await?;
+                let drive = OneDriveApi::new(
+                    self.access_token().await?, // Login token to Microsoft Graph.
+                    DriveLocation::me(),
+                );
+                copy_item(&drive, &object_id, &new_parent_id, name, conflict).await
+            },
+            result => result,
+        }
+    }
+
+    async fn rename(&self, object_id: ObjectId, new_name: String) -> Result<ObjectId, CrossroadsError> {
         let drive = OneDriveApi::new(
-            self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+            self.access_token().await?, // Login token to Microsoft Graph.
             DriveLocation::me(),
         );
 
         let item_id : ItemId = object_id.clone().into();
-        let item_location = if object_id.to_string() == "".to_string() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
+        let item_location = if object_id.as_str().is_empty() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
 
         // let items_result = drive.get_item(item_location).await;
 
@@ -187,10 +336,10 @@ impl FileSystem for OneDrive {
         //         if error.status_code() == Some(StatusCode::UNAUTHORIZED) {
         //             self.refresh_token().await?;
         //             let drive = OneDriveApi::new(
-        //                 self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+        //                 self.access_token().await?, // Login token to Microsoft Graph.
         //                 DriveLocation::me(),
         //             );
-        //             Ok(drive.get_item(item_location).await.unwrap())
+        //             Ok(drive.get_item(item_location).await?)
         //         } else {
         //             Err(error)
         //         }
@@ -209,125 +358,80 @@ impl FileSystem for OneDrive {
                 if error.status_code() == Some(StatusCode::UNAUTHORIZED) {
                     self.refresh_token().await?;
                     let drive = OneDriveApi::new(
-                        self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+                        self.access_token().await?, // Login token to Microsoft Graph.
                         DriveLocation::me(),
                     );
-                    Ok(drive.update_item(item_location, &item).await.unwrap())
+                    Ok(drive.update_item(item_location, &item).await?)
                 } else {
                     Err(error)
                 }
             }
         }?;
 
+        self.paths.invalidate(&object_id);
+
         Ok(ObjectId::new(item.id.unwrap().as_str().to_string(), object_id.file_type()))
     }
 
-    async fn read_directory(&self, object_id: ObjectId) -> Result<Vec<File>, Box<dyn std::error::Error>> {
+    async fn read_directory_page(&self, object_id: ObjectId, page_size: usize, page_token: Option<String>) -> Result<DirectoryPage, CrossroadsError> {
         let drive = OneDriveApi::new(
-            self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+            self.access_token().await?, // Login token to Microsoft Graph.
             DriveLocation::me(),
         );
 
-        let item_id : ItemId = object_id.clone().into();
-
-        let item_location = if object_id.to_string() == "".to_string() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
-
-        let items_result = drive.list_children(item_location).await;
+        let page_result = list_children_page(&drive, &object_id, page_size, page_token.clone()).await;
 
-        let items = match items_result {
-            Ok(items) => items,
+        let (items, next_page_token) = match page_result {
+            Ok(page) => page,
             Err(error) => {
                 if error.status_code() == Some(StatusCode::UNAUTHORIZED) {
                     self.refresh_token().await?;
                     let drive = OneDriveApi::new(
-                        self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+                        self.access_token().await?, // Login token to Microsoft Graph.
                         DriveLocation::me(),
                     );
-                    drive.list_children(item_location).await.unwrap()
+                    list_children_page(&drive, &object_id, page_size, page_token).await?
                 } else {
-                    vec![]
+                    return Err(error.into())
                 }
             }
         };
 
         let files: Vec<File> = items.iter().map(|file| file.to_owned().into()).collect();
 
-        Ok(files)
+        Ok(DirectoryPage { files, next_page_token })
     }
 
-    async fn create(&self, parent_id: ObjectId, file: File) -> Result<(), Box<dyn std::error::Error>> {
+    async fn create(&self, parent_id: ObjectId, file: File) -> Result<ObjectId, CrossroadsError> {
         let drive = OneDriveApi::new(
-            self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+            self.access_token().await?, // Login token to Microsoft Graph.
             DriveLocation::me(),
         );
 
-        let item_id : ItemId = parent_id.clone().into();
-
-        let item_location = if parent_id.to_string() == "".to_string() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
-
-        let filename = FileName::new(&file.name);
-
-        if file.id.is_directory() {
-            println!("Creating a directory");
-            let items_result = drive.create_folder(item_location, filename.unwrap()).await;
-    
-            match items_result {
-                Ok(items) => Ok(items),
-                Err(error) => {
-                    if error.status_code() == Some(StatusCode::UNAUTHORIZED) {
-                        self.refresh_token().await?;
-                        let drive = OneDriveApi::new(
-                            self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
-                            DriveLocation::me(),
-                        );
-                        Ok(drive.get_item(item_location).await.unwrap())
-                    } else {
-                        println!("Got a different error");
-                        Err(error)
-                    }
-                }
-            }?;
-
-            return Ok(())
-        } else {
-            println!("Creating a file");
-
-            let client = reqwest::Client::new();
-
-            let response = client.put(format!("https://graph.microsoft.com/v1.0/me/drive/items/{}:/{}:/content", parent_id.as_str(), file.name.as_str()))
-                .header("Content-Type", "text/plain").header("Content-Length", "0").body("")
-                .bearer_auth(self.token.get().await.clone().unwrap().access_token().secret()).send().await;
-    
-            let result = match response {
-                Ok(items) => Ok(items),
-                Err(error) => {
-                    if error.status() == Some(StatusCode::UNAUTHORIZED) {
-                        self.refresh_token().await?;
-                        let retry_response = client.put(format!("https://graph.microsoft.com/v1.0/me/drive/items/{}:/{}:/content", parent_id.as_str(), file.name.as_str()))
-                            .header("Content-Type", "text/plain").body("")
-                            .bearer_auth(self.token.get().await.clone().unwrap().access_token().secret()).send().await?;
-                        Ok(retry_response)
-                    } else {
-                        Err(error)
-                    }
-                }
-            }?;
-
-            println!("{}", result.text().await.unwrap());
-
-            Ok(())
+        let create_result = create_item(&drive, &parent_id, &file).await;
+
+        match create_result {
+            Err(CrossroadsError::AuthenticationRequired) => {
+                self.refresh_token().await?;
+                let drive = OneDriveApi::new(
+                    self.access_token().await?, // Login token to Microsoft Graph.
+                    DriveLocation::me(),
+                );
+                create_item(&drive, &parent_id, &file).await
+            },
+            result => result,
         }
     }
 
-    async fn get_metadata(&self, object_id: ObjectId) -> Result<crate::interfaces::filesystem::Metadata, Box<dyn std::error::Error>> {
+    async fn get_metadata(&self, object_id: ObjectId) -> Result<crate::interfaces::filesystem::Metadata, CrossroadsError> {
         let drive = OneDriveApi::new(
-            self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+            self.access_token().await?, // Login token to Microsoft Graph.
             DriveLocation::me(),
         );
 
         let item_id : ItemId = object_id.clone().into();
 
-        let item_location = if object_id.to_string() == "".to_string() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
+        let item_location = if object_id.as_str().is_empty() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
 
         let items_result = drive.get_item(item_location).await;
 
@@ -337,18 +441,21 @@ impl FileSystem for OneDrive {
                 if error.status_code() == Some(StatusCode::UNAUTHORIZED) {
                     self.refresh_token().await?;
                     let drive = OneDriveApi::new(
-                        self.token.get().await.clone().unwrap().access_token().secret(), // Login token to Microsoft Graph.
+                        self.access_token().await?, // Login token to Microsoft Graph.
                         DriveLocation::me(),
                     );
-                    Ok(drive.get_item(item_location).await.unwrap())
+                    Ok(drive.get_item(item_location).await?)
                 } else {
                     Err(error)
                 }
             }
         }?;
 
+        let checksums = checksums(&item);
+
         Ok(Metadata {
             mime_type: None,
+            export_mime_type: None,
             open_path: Some(item.web_url.unwrap()),
             modified_at: None,
             created_at: None,
@@ -357,14 +464,295 @@ impl FileSystem for OneDrive {
             size: None,
             owner: None,
             permissions: None,
+            checksums,
+            version: item.c_tag.map(|tag| tag.0),
+            properties: None,
         })
     }
 
-    async fn read_link(&self, object_id: ObjectId) -> Result<ObjectId, Box<dyn std::error::Error>> {
-        todo!()
+    async fn resolve_path(&self, path: &str) -> Result<ObjectId, CrossroadsError> {
+        let path = path_cache::normalize(path);
+
+        if path.is_empty() {
+            return Ok(ObjectId::root())
+        }
+
+        if let Some(object_id) = self.paths.get(&path) {
+            return Ok(object_id)
+        }
+
+        let location = format!("/{}", path);
+        let item_location = ItemLocation::from_path(&location)
+            .ok_or_else(|| CrossroadsError::InvalidInput(format!("Invalid path {}", path)))?;
+
+        let object_id = File::from(self.get_item(item_location).await?).id;
+        self.paths.insert(path, object_id.clone());
+
+        Ok(object_id)
+    }
+
+    async fn path_of(&self, object_id: ObjectId) -> Result<String, CrossroadsError> {
+        if object_id.as_str().is_empty() {
+            return Ok(String::new())
+        }
+
+        if let Some(path) = self.paths.path_of(&object_id) {
+            return Ok(path)
+        }
+
+        let item_id: ItemId = object_id.clone().into();
+        let item = self.get_item(ItemLocation::from_id(&item_id)).await?;
+
+        // Percent-encoded path of the parent, as in `/drive/root:/Documents`.
+        let parent = item.parent_reference.as_ref()
+            .and_then(|parent| parent["path"].as_str())
+            .and_then(|path| path.split_once("root:"))
+            .map(|(_, path)| percent_decode(path.trim_matches('/')))
+            .unwrap_or_default();
+
+        let name = item.name.unwrap_or_default();
+        let path = if parent.is_empty() { name } else { format!("{}/{}", parent, name) };
+
+        self.paths.insert(path.clone(), object_id);
+
+        Ok(path)
+    }
+
+    /// Drive items take no custom metadata, so the properties of an item are kept in a JSON file
+    /// named after its id in the folder of the app. The file is left behind when the item is deleted.
+    async fn get_properties(&self, object_id: ObjectId) -> Result<BTreeMap<String, String>, CrossroadsError> {
+        match self.get_json(&format!("{}/content", properties_url(&object_id))).await {
+            Err(CrossroadsError::NotFound(_)) => Ok(BTreeMap::new()),
+            result => result,
+        }
+    }
+
+    async fn set_properties(&self, object_id: ObjectId, properties: BTreeMap<String, String>) -> Result<(), CrossroadsError> {
+        let mut current = self.get_properties(object_id.clone()).await?;
+        current.extend(properties);
+        self.write_properties(&object_id, &current).await
+    }
+
+    async fn remove_properties(&self, object_id: ObjectId, names: &[String]) -> Result<(), CrossroadsError> {
+        let mut current = self.get_properties(object_id.clone()).await?;
+        current.retain(|name, _| !names.contains(name));
+        self.write_properties(&object_id, &current).await
+    }
+
+    async fn set_modified_at(&self, object_id: ObjectId, modified_at: DateTime<Utc>) -> Result<(), CrossroadsError> {
+        self.set_metadata(object_id, MetadataUpdate { modified_at: Some(modified_at), ..Default::default() }).await
+    }
+
+    /// Patches the `fileSystemInfo` of the item, the times of the file on the device it came
+    /// from, which listings report over those of the upload.
+    async fn set_metadata(&self, object_id: ObjectId, update: MetadataUpdate) -> Result<(), CrossroadsError> {
+        update.check_supported("OneDrive", &[MetadataField::ModifiedAt, MetadataField::CreatedAt])?;
+
+        let mut file_system_info = serde_json::Map::new();
+
+        for (name, time) in [("lastModifiedDateTime", update.modified_at), ("createdDateTime", update.created_at)] {
+            if let Some(time) = time {
+                file_system_info.insert(name.to_string(), time.to_rfc3339_opts(SecondsFormat::Millis, true).into());
+            }
+        }
+
+        if file_system_info.is_empty() {
+            return Ok(())
+        }
+
+        let url = format!("https://graph.microsoft.com/v1.0/me/drive/items/{}", object_id.as_str());
+        self.graph_request(Method::PATCH, &url, Some(&serde_json::json!({ "fileSystemInfo": file_system_info }))).await?;
+
+        Ok(())
+    }
+
+    async fn read_link(&self, _object_id: ObjectId) -> Result<ObjectId, CrossroadsError> {
+        Err(CrossroadsError::unsupported("OneDrive read_link"))
     }
 
-    async fn create_link(&self, parent_id: ObjectId, name: &str, link_id: ObjectId) -> Result<ObjectId, Box<dyn std::error::Error>> {
-        todo!()
+    async fn create_link(&self, _parent_id: ObjectId, _name: &str, _link_id: ObjectId) -> Result<ObjectId, CrossroadsError> {
+        Err(CrossroadsError::unsupported("OneDrive create_link"))
     }
 }
+
+/// Fetches one page of children. The continuation token is the URL of the next page
+/// given by Microsoft Graph, which already carries the page size.
+async fn list_children_page(drive: &OneDriveApi, object_id: &ObjectId, page_size: usize, page_token: Option<String>) -> Result<(Vec<DriveItem>, Option<String>), onedrive_api::Error> {
+    let mut fetcher = match page_token {
+        Some(next_url) => ListChildrenFetcher::resume_from(next_url),
+        None => {
+            let item_id : ItemId = object_id.clone().into();
+            let item_location = if object_id.as_str().is_empty() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
+
+            let option = CollectionOption::new().page_size(page_size.max(1));
+
+            match drive.list_children_with_option(item_location, option).await? {
+                Some(fetcher) => fetcher,
+                None => return Ok((vec![], None)),
+            }
+        }
+    };
+
+    let items = fetcher.fetch_next_page(drive).await?.unwrap_or_default();
+
+    Ok((items, fetcher.next_url().map(str::to_string)))
+}
+
+/// File holding the properties of `object_id` in the folder of the app.
+fn properties_url(object_id: &ObjectId) -> String {
+    let id = if object_id.as_str().is_empty() { "root" } else { object_id.as_str() };
+    format!("https://graph.microsoft.com/v1.0/me/drive/special/approot:/properties/{}.json:", id)
+}
+
+fn file_name(name: &str) -> Result<&FileName, CrossroadsError> {
+    FileName::new(name).ok_or_else(|| CrossroadsError::InvalidInput(format!("Invalid OneDrive file name: {}", name)))
+}
+
+/// Item called `name` in the directory `parent_id`, if there is one.
+async fn child(drive: &OneDriveApi, parent_id: &ObjectId, name: &str) -> Result<Option<DriveItem>, CrossroadsError> {
+    let item_id : ItemId = parent_id.clone().into();
+    let path = format!("/{}", name);
+
+    let item_location = if parent_id.as_str().is_empty() {
+        ItemLocation::from_path(&path).ok_or_else(|| CrossroadsError::InvalidInput(format!("Invalid OneDrive file name: {}", name)))?
+    } else {
+        ItemLocation::child_of_id(&item_id, file_name(name)?)
+    };
+
+    match drive.get_item(item_location).await {
+        Ok(item) => Ok(Some(item)),
+        Err(error) if error.status_code() == Some(StatusCode::NOT_FOUND) => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Creates an empty file or a directory, depending on the type of `file.id`.
+async fn create_item(drive: &OneDriveApi, parent_id: &ObjectId, file: &File) -> Result<ObjectId, CrossroadsError> {
+    let item_id : ItemId = parent_id.clone().into();
+    let filename = file_name(&file.name)?;
+
+    let item = if file.id.is_directory() {
+        let item_location = if parent_id.as_str().is_empty() { ItemLocation::root() } else { ItemLocation::from_id(&item_id) };
+        drive.create_folder(item_location, filename).await?
+    } else {
+        let path = format!("/{}", file.name);
+
+        let item_location = if parent_id.as_str().is_empty() {
+            ItemLocation::from_path(&path).ok_or_else(|| CrossroadsError::InvalidInput(format!("Invalid OneDrive file name: {}", file.name)))?
+        } else {
+            ItemLocation::child_of_id(&item_id, filename)
+        };
+
+        drive.upload_small(item_location, Vec::new()).await?
+    };
+
+    let id = item.id.ok_or_else(|| CrossroadsError::Provider("OneDrive did not return the id of the new item".into()))?;
+
+    Ok(ObjectId::new(id.as_str().to_string(), file.id.file_type()))
+}
+
+/// Microsoft Graph resolves conflicts on copy in its own way depending on the destination,
+/// so the policy is applied here before the copy is started.
+async fn copy_item(drive: &OneDriveApi, object_id: &ObjectId, new_parent_id: &ObjectId, name: &str, conflict: ConflictPolicy) -> Result<ObjectId, CrossroadsError> {
+    let name = conflict::resolve_name(name, conflict, |candidate| async move {
+        Ok(child(drive, new_parent_id, &candidate).await?.is_some())
+    }).await?;
+
+    let existing = match conflict {
+        ConflictPolicy::Replace => child(drive, new_parent_id, &name).await?.and_then(|item| item.id),
+        _ => None,
+    };
+
+    if existing.as_ref().is_some_and(|existing_id| existing_id.as_str() == object_id.as_str()) {
+        return Err(CrossroadsError::InvalidInput(format!("Cannot copy {} into itself", object_id)))
+    }
+
+    // The entry replaced is only deleted once the copy, made under a temporary name, succeeded.
+    let copy_name = match existing {
+        Some(_) => transfer::temporary_name(&name),
+        None => name.clone(),
+    };
+
+    let item_id : ItemId = object_id.clone().into();
+    let parent_item_id : ItemId = new_parent_id.clone().into();
+    let parent_location = if new_parent_id.as_str().is_empty() { ItemLocation::root() } else { ItemLocation::from_id(&parent_item_id) };
+
+    let monitor = drive.copy(ItemLocation::from_id(&item_id), parent_location, file_name(&copy_name)?).await?;
+
+    let copy = ObjectId::new(wait_for_copy(monitor.monitor_url()).await?, object_id.file_type());
+
+    if let Some(existing_id) = existing {
+        let copy_id : ItemId = copy.clone().into();
+
+        if let Err(error) = drive.delete(ItemLocation::from_id(&existing_id)).await {
+            let _ = drive.delete(ItemLocation::from_id(&copy_id)).await;
+            return Err(error.into())
+        }
+
+        let mut item = DriveItem::default();
+        item.name = Some(name);
+        drive.update_item(ItemLocation::from_id(&copy_id), &item).await?;
+    }
+
+    Ok(copy)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyStatus {
+    status: String,
+    resource_id: Option<String>,
+}
+
+/// Polls the monitor of an asynchronous copy until it finishes and returns the id of the copy.
+/// The monitor is not authenticated, and redirects to the new item once the copy is done.
+async fn wait_for_copy(monitor_url: &str) -> Result<String, CrossroadsError> {
+    let client = reqwest::Client::builder().redirect(redirect::Policy::none()).build()?;
+
+    loop {
+        let response = client.get(monitor_url).send().await?;
+
+        if response.status() == StatusCode::SEE_OTHER {
+            let location = response.headers().get(LOCATION).and_then(|value| value.to_str().ok()).unwrap_or_default();
+
+            if let Some((_, id)) = location.rsplit_once("/items/") {
+                return Ok(id.trim_end_matches('/').to_string())
+            }
+        }
+
+        let copy_status: CopyStatus = response.error_for_status()?.json().await?;
+
+        match copy_status.status.as_str() {
+            "completed" => {
+                return copy_status.resource_id
+                    .ok_or_else(|| CrossroadsError::Provider("OneDrive did not return the id of the copy".into()))
+            },
+            "failed" => return Err(CrossroadsError::Provider(format!("OneDrive could not copy to {}", monitor_url).into())),
+            _ => tokio::time::sleep(COPY_POLL_INTERVAL).await,
+        }
+    }
+}
+
+pub(super) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%').then(|| text.get(i + 1..i + 3)).flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            },
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            },
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
@@ -0,0 +1,43 @@
+use async_trait::async_trait;
+use reqwest::Method;
+use serde_json::json;
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::ObjectId;
+use crate::interfaces::locking::{self, Lock, LockMode, LockOptions, Locking};
+use crate::providers::onedrive::OneDrive;
+
+/// Checks files out, which only SharePoint and business drives support. A checked out file is
+/// hidden from others in its latest version until it is checked in, and stays checked out with
+/// no lease until then.
+#[async_trait]
+impl Locking for OneDrive {
+    async fn lock(&self, object_id: ObjectId, options: LockOptions) -> Result<Lock, CrossroadsError> {
+        if options.mode == LockMode::Shared {
+            return Err(CrossroadsError::unsupported("OneDrive shared locks"))
+        }
+
+        let url = format!("https://graph.microsoft.com/v1.0/me/drive/items/{}/checkout", object_id.as_str());
+
+        locking::retry_lock(&object_id, options.wait, || async {
+            match self.graph_request(Method::POST, &url, None).await {
+                Ok(_) => Ok(Some(Lock {
+                    object_id: object_id.clone(),
+                    mode: LockMode::Exclusive,
+                    token: object_id.to_string(),
+                    expires_at: None,
+                })),
+                // Files checked out by someone else are locked.
+                Err(CrossroadsError::Conflict(_)) => Ok(None),
+                Err(error) => Err(error),
+            }
+        }).await
+    }
+
+    async fn unlock(&self, lock: Lock) -> Result<(), CrossroadsError> {
+        let url = format!("https://graph.microsoft.com/v1.0/me/drive/items/{}/checkin", lock.object_id.as_str());
+        self.graph_request(Method::POST, &url, Some(&json!({ "comment": "" }))).await?;
+
+        Ok(())
+    }
+}
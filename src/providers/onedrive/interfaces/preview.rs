@@ -0,0 +1,29 @@
+use async_trait::async_trait;
+use reqwest::{header::CONTENT_TYPE, Method};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::ObjectId;
+use crate::interfaces::preview::{Preview, Thumbnail, ThumbnailSize};
+use crate::providers::onedrive::OneDrive;
+use crate::providers::thumbnail;
+
+/// Graph renders thumbnails of images, videos and most documents. A custom size is requested
+/// so that they match the other providers, which Graph scales keeping the aspect ratio.
+#[async_trait]
+impl Preview for OneDrive {
+    async fn thumbnail(&self, object_id: ObjectId, size: ThumbnailSize) -> Result<Thumbnail, CrossroadsError> {
+        let url = format!(
+            "https://graph.microsoft.com/v1.0/me/drive/items/{}/thumbnails/0/c{}x{}/content",
+            object_id.as_str(), size.pixels(), size.pixels(),
+        );
+
+        let response = self.graph_request(Method::GET, &url, None).await?;
+
+        let mime_type = response.headers().get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("image/jpeg")
+            .to_string();
+
+        thumbnail::from_image(response.bytes().await?.to_vec(), mime_type)
+    }
+}
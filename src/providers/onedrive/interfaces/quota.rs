@@ -0,0 +1,21 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::error::CrossroadsError;
+use crate::interfaces::quota::{Quota, StorageQuota};
+use crate::providers::onedrive::OneDrive;
+
+#[async_trait]
+impl Quota for OneDrive {
+    async fn quota(&self) -> Result<StorageQuota, CrossroadsError> {
+        let drive: Value = self.get_json("https://graph.microsoft.com/v1.0/me/drive?$select=quota").await?;
+        let quota = &drive["quota"];
+
+        Ok(StorageQuota {
+            total: quota["total"].as_u64(),
+            used: quota["used"].as_u64(),
+            free: quota["remaining"].as_u64(),
+            trash: quota["deleted"].as_u64(),
+        })
+    }
+}
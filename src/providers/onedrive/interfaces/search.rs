@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use futures::{future, stream, TryStreamExt};
+use onedrive_api::resource::DriveItem;
+use serde::Deserialize;
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{File, FileStream, ObjectId};
+use crate::interfaces::search::{Search, SearchQuery};
+use crate::providers::onedrive::OneDrive;
+use crate::walker::{self, WalkOptions};
+
+#[derive(Deserialize)]
+struct SearchPage {
+    value: Vec<DriveItem>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+}
+
+#[async_trait]
+impl Search for OneDrive {
+    /// Graph looks the words of `full_text`, or else of the name, up in both the names and the
+    /// content of the files, and the other criteria are checked on its results. Without any
+    /// word to look up, the whole drive is walked instead.
+    async fn search(&self, query: SearchQuery) -> Result<FileStream<'_>, CrossroadsError> {
+        let filter = query.filter()?;
+
+        let Some(text) = query.full_text.clone().or_else(|| query.name_literal()) else {
+            let files = walker::walk(self, ObjectId::root(), WalkOptions::default())?
+                .map_ok(|entry| entry.file)
+                .try_filter(move |file| future::ready(filter.matches(file)));
+
+            return Ok(Box::pin(files))
+        };
+
+        let url = format!("https://graph.microsoft.com/v1.0/me/drive/root/search(q='{}')", text.replace('\'', "''"));
+
+        let pages = stream::try_unfold(Some(url), move |url: Option<String>| async move {
+            let Some(url) = url else {
+                return Ok::<_, CrossroadsError>(None)
+            };
+
+            let page = self.get_json::<SearchPage>(&url).await?;
+            let files: Vec<File> = page.value.into_iter().map(File::from).collect();
+
+            Ok(Some((files, page.next_link)))
+        });
+
+        let files = pages.map_ok(|files| stream::iter(files.into_iter().map(Ok::<_, CrossroadsError>))).try_flatten();
+
+        Ok(Box::pin(files.try_filter(move |file| future::ready(filter.matches(file)))))
+    }
+}
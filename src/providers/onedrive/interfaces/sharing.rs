@@ -0,0 +1,157 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::ObjectId;
+use crate::interfaces::sharing::{Grantee, Share, ShareRequest, ShareRole, Sharing};
+use crate::providers::onedrive::OneDrive;
+
+#[derive(Deserialize)]
+struct PermissionsPage {
+    value: Vec<Value>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+}
+
+fn item_url(object_id: &ObjectId) -> String {
+    format!("https://graph.microsoft.com/v1.0/me/drive/items/{}", object_id.as_str())
+}
+
+/// Reads a Graph `permission`, granted either through a link or to identities.
+fn share_of(permission: &Value) -> Share {
+    let roles: Vec<&str> = permission["roles"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+
+    let role = if roles.contains(&"owner") {
+        ShareRole::Owner
+    } else if roles.contains(&"write") {
+        ShareRole::Write
+    } else {
+        ShareRole::Read
+    };
+
+    let link = &permission["link"];
+    let identity = if permission["grantedToV2"].is_object() { &permission["grantedToV2"] } else { &permission["grantedTo"] };
+    let email = |identity: &Value| identity["email"].as_str().or(identity["displayName"].as_str()).unwrap_or_default().to_string();
+
+    let grantee = match link["scope"].as_str() {
+        Some("anonymous") => Grantee::Anyone,
+        Some("organization") => Grantee::Organization,
+        _ if identity["group"].is_object() => Grantee::Group(email(&identity["group"])),
+        _ => match permission["invitation"]["email"].as_str() {
+            Some(invited) => Grantee::User(invited.to_string()),
+            None => Grantee::User(email(&identity["user"])),
+        },
+    };
+
+    Share {
+        // Inherited permissions can only be revoked on the folder they come from.
+        id: if permission["inheritedFrom"].is_object() { None } else { permission["id"].as_str().map(str::to_string) },
+        url: link["webUrl"].as_str().map(str::to_string),
+        role,
+        grantee,
+        expires_at: permission["expirationDateTime"].as_str().and_then(|date| DateTime::<Utc>::from_str(date).ok()),
+    }
+}
+
+/// Links are made for anyone or the organization, invitations without email for users and
+/// groups. OneDrive has no comment-only role and no grant to a domain.
+#[async_trait]
+impl Sharing for OneDrive {
+    async fn share(&self, object_id: ObjectId, request: ShareRequest) -> Result<Share, CrossroadsError> {
+        let role = match request.role {
+            ShareRole::Read => "read",
+            ShareRole::Write => "write",
+            ShareRole::Comment | ShareRole::Owner => return Err(CrossroadsError::unsupported("OneDrive shares with comment or owner role")),
+        };
+
+        let expiration = request.expires_at.map(|date| date.to_rfc3339_opts(SecondsFormat::Secs, true));
+
+        let (url, mut body) = match &request.grantee {
+            Grantee::Anyone | Grantee::Organization => {
+                let scope = if request.grantee == Grantee::Anyone { "anonymous" } else { "organization" };
+                let link_type = if role == "write" { "edit" } else { "view" };
+
+                (format!("{}/createLink", item_url(&object_id)), json!({ "type": link_type, "scope": scope }))
+            }
+            Grantee::User(email) | Grantee::Group(email) => (
+                format!("{}/invite", item_url(&object_id)),
+                json!({ "recipients": [{ "email": email }], "roles": [role], "requireSignIn": true, "sendInvitation": false }),
+            ),
+            Grantee::Domain(_) => return Err(CrossroadsError::unsupported("OneDrive shares with a domain")),
+        };
+
+        if let Some(expiration) = expiration {
+            body["expirationDateTime"] = expiration.into();
+        }
+
+        let response: Value = self.graph_request(Method::POST, &url, Some(&body)).await?.json().await?;
+
+        // Invitations return the permission of each recipient.
+        let permission = match response["value"].as_array() {
+            Some(permissions) => permissions.first().cloned().unwrap_or_default(),
+            None => response,
+        };
+
+        Ok(share_of(&permission))
+    }
+
+    async fn list_shares(&self, object_id: ObjectId) -> Result<Vec<Share>, CrossroadsError> {
+        let mut url = Some(format!("{}/permissions", item_url(&object_id)));
+        let mut shares = vec![];
+
+        while let Some(current) = url {
+            let page: PermissionsPage = self.get_json(&current).await?;
+
+            shares.extend(page.value.iter().map(share_of));
+            url = page.next_link;
+        }
+
+        Ok(shares)
+    }
+
+    async fn revoke_share(&self, object_id: ObjectId, share_id: &str) -> Result<(), CrossroadsError> {
+        self.graph_request(Method::DELETE, &format!("{}/permissions/{}", item_url(&object_id), share_id), None).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::interfaces::sharing::{Grantee, ShareRole};
+
+    use super::share_of;
+
+    #[test]
+    fn share_of_reads_links_and_invitations() {
+        let link = share_of(&json!({
+            "id": "1",
+            "roles": ["write"],
+            "link": { "type": "edit", "scope": "anonymous", "webUrl": "https://1drv.ms/x" },
+            "expirationDateTime": "2022-06-01T00:00:00Z",
+        }));
+
+        assert_eq!(link.role, ShareRole::Write);
+        assert_eq!(link.grantee, Grantee::Anyone);
+        assert_eq!(link.url.as_deref(), Some("https://1drv.ms/x"));
+        assert!(link.expires_at.is_some());
+
+        let invitation = share_of(&json!({
+            "id": "2",
+            "roles": ["read"],
+            "grantedToV2": { "user": { "displayName": "Jane" } },
+            "invitation": { "email": "jane@example.com" },
+            "inheritedFrom": { "id": "folder" },
+        }));
+
+        assert_eq!(invitation.grantee, Grantee::User("jane@example.com".to_string()));
+        assert_eq!(invitation.id, None);
+    }
+}
@@ -1,10 +1,142 @@
+use std::str::FromStr;
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{FileSystem, ObjectId};
+use crate::interfaces::trash::{Trash, TrashedItem};
+use crate::providers::onedrive::OneDrive;
+
+use super::filesystem::percent_decode;
+
+/// Most items restored or deleted by a single request.
+const RECYCLE_BIN_BATCH_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct RecycleBinPage {
+    value: Vec<Value>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+}
+
+/// SharePoint site backing the drive, along with the path of the drive in the site.
+struct RecycleBin {
+    url: String,
+    drive_path: String,
+}
+
+impl OneDrive {
+    /// Personal accounts have no SharePoint site, so their recycle bin cannot be reached.
+    async fn recycle_bin(&self) -> Result<RecycleBin, CrossroadsError> {
+        let drive: Value = self.get_json("https://graph.microsoft.com/v1.0/me/drive?$select=sharePointIds,webUrl").await?;
+
+        let site_id = drive["sharePointIds"]["siteId"].as_str()
+            .ok_or_else(|| CrossroadsError::unsupported("OneDrive recycle bin of personal accounts"))?;
+
+        // The url of the drive is the one of its library, e.g. `https://contoso-my.sharepoint.com/personal/jane/Documents`.
+        let web_url = drive["webUrl"].as_str().unwrap_or_default();
+        let drive_path = web_url.splitn(4, '/').nth(3).map(percent_decode).unwrap_or_default();
+
+        Ok(RecycleBin { url: format!("https://graph.microsoft.com/beta/sites/{}/recycleBin/items", site_id), drive_path })
+    }
+
+    async fn recycle_bin_items(&self, recycle_bin: &RecycleBin) -> Result<Vec<TrashedItem>, CrossroadsError> {
+        let mut url = Some(recycle_bin.url.clone());
+        let mut items = vec![];
+
+        while let Some(current) = url {
+            let page: RecycleBinPage = self.get_json(&current).await?;
+
+            items.extend(page.value.iter().filter_map(|item| trashed_item(item, &recycle_bin.drive_path)));
+            url = page.next_link;
+        }
+
+        Ok(items)
+    }
+
+    async fn recycle_bin_batch(&self, recycle_bin: &RecycleBin, action: &str, ids: &[String]) -> Result<(), CrossroadsError> {
+        for batch in ids.chunks(RECYCLE_BIN_BATCH_SIZE) {
+            let url = format!("{}/{}", recycle_bin.url, action);
+            self.graph_request(Method::POST, &url, Some(&json!({ "ids": batch }))).await?;
+        }
+
+        Ok(())
+    }
+}
 
-use crate::{interfaces::trash::Trash, providers::onedrive::OneDrive};
+/// Reads a `recycleBinItem`, leaving out the items deleted from other libraries of the site.
+fn trashed_item(item: &Value, drive_path: &str) -> Option<TrashedItem> {
+    let location = item["deletedFromLocation"].as_str().unwrap_or_default().trim_matches('/');
+    let original_location = location.strip_prefix(drive_path.trim_matches('/'))?;
 
+    if !original_location.is_empty() && !original_location.starts_with('/') {
+        return None
+    }
+
+    Some(TrashedItem {
+        id: item["id"].as_str()?.to_string(),
+        name: item["title"].as_str().or(item["name"].as_str()).unwrap_or_default().to_string(),
+        original_location: Some(original_location.trim_start_matches('/').to_string()),
+        deleted_at: item["deletedDateTime"].as_str().and_then(|date| DateTime::<Utc>::from_str(date).ok()),
+        size: item["size"].as_u64(),
+    })
+}
+
+/// Deleted items already go to the recycle bin of the drive, which is the one of its SharePoint
+/// site and only exposed by the beta endpoint of Graph.
 #[async_trait]
 impl Trash for OneDrive {
-    async fn send_to_trash(&self, object_id: crate::interfaces::filesystem::ObjectId) -> Result<(), Box<dyn std::error::Error>> {
-        todo!()
+    async fn send_to_trash(&self, object_id: ObjectId) -> Result<(), CrossroadsError> {
+        self.delete(object_id).await
+    }
+
+    async fn list_trash(&self) -> Result<Vec<TrashedItem>, CrossroadsError> {
+        let recycle_bin = self.recycle_bin().await?;
+        self.recycle_bin_items(&recycle_bin).await
+    }
+
+    async fn restore_from_trash(&self, trashed_id: &str) -> Result<(), CrossroadsError> {
+        let recycle_bin = self.recycle_bin().await?;
+        self.recycle_bin_batch(&recycle_bin, "restore", &[trashed_id.to_string()]).await
+    }
+
+    async fn delete_from_trash(&self, trashed_id: &str) -> Result<(), CrossroadsError> {
+        let recycle_bin = self.recycle_bin().await?;
+        self.recycle_bin_batch(&recycle_bin, "delete", &[trashed_id.to_string()]).await
+    }
+
+    async fn empty_trash(&self) -> Result<(), CrossroadsError> {
+        let recycle_bin = self.recycle_bin().await?;
+        let ids: Vec<String> = self.recycle_bin_items(&recycle_bin).await?.into_iter().map(|item| item.id).collect();
+
+        self.recycle_bin_batch(&recycle_bin, "delete", &ids).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::trashed_item;
+
+    #[test]
+    fn trashed_item_is_located_in_the_drive() {
+        let item = json!({
+            "id": "1",
+            "title": "report.pdf",
+            "deletedFromLocation": "personal/jane_contoso_com/Documents/Reports 2022",
+            "deletedDateTime": "2022-06-01T10:00:00Z",
+            "size": 12,
+        });
+
+        let trashed = trashed_item(&item, "personal/jane_contoso_com/Documents").unwrap();
+        assert_eq!(trashed.original_location.as_deref(), Some("Reports 2022"));
+        assert_eq!(trashed.size, Some(12));
+
+        assert!(trashed_item(&item, "personal/jane_contoso_com/Doc").is_none());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,87 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use reqwest::Method;
+use serde::Deserialize;
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{ByteStream, ObjectId, User, UserId};
+use crate::interfaces::versions::{Revision, Versions};
+use crate::providers::onedrive::OneDrive;
+
+#[derive(Deserialize)]
+struct VersionsPage {
+    value: Vec<DriveItemVersion>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DriveItemVersion {
+    id: String,
+    last_modified_date_time: Option<String>,
+    size: Option<u64>,
+    last_modified_by: Option<serde_json::Value>,
+}
+
+impl From<DriveItemVersion> for Revision {
+    fn from(version: DriveItemVersion) -> Self {
+        let author = version.last_modified_by.as_ref().map(|identity| &identity["user"]).and_then(|user| {
+            let id = user["id"].as_str()?.to_string();
+
+            Some(User { id: UserId::UniqueId(id), name: user["displayName"].as_str().map(str::to_string) })
+        });
+
+        Revision {
+            id: version.id,
+            modified_at: version.last_modified_date_time.and_then(|date| DateTime::<Utc>::from_str(&date).ok()),
+            size: version.size,
+            author,
+            is_current: false,
+        }
+    }
+}
+
+fn versions_url(object_id: &ObjectId) -> String {
+    format!("https://graph.microsoft.com/v1.0/me/drive/items/{}/versions", object_id.as_str())
+}
+
+/// Only files have versions, folders fail with `CrossroadsError::NotFound`.
+#[async_trait]
+impl Versions for OneDrive {
+    async fn list_revisions(&self, object_id: ObjectId) -> Result<Vec<Revision>, CrossroadsError> {
+        let mut url = Some(versions_url(&object_id));
+        let mut revisions: Vec<Revision> = vec![];
+
+        while let Some(current) = url {
+            let page: VersionsPage = self.get_json(&current).await?;
+
+            revisions.extend(page.value.into_iter().map(Revision::from));
+            url = page.next_link;
+        }
+
+        // Graph lists the versions newest first, starting with the current content.
+        if let Some(current) = revisions.first_mut() {
+            current.is_current = true;
+        }
+
+        Ok(revisions)
+    }
+
+    async fn read_revision(&self, object_id: ObjectId, revision_id: &str) -> Result<ByteStream, CrossroadsError> {
+        let url = format!("{}/{}/content", versions_url(&object_id), revision_id);
+        let response = self.graph_request(Method::GET, &url, None).await?;
+
+        Ok(Box::pin(response.bytes_stream().map_err(CrossroadsError::from)))
+    }
+
+    async fn restore_revision(&self, object_id: ObjectId, revision_id: &str) -> Result<(), CrossroadsError> {
+        let url = format!("{}/{}/restoreVersion", versions_url(&object_id), revision_id);
+        self.graph_request(Method::POST, &url, None).await?;
+
+        Ok(())
+    }
+}
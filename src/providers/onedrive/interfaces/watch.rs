@@ -0,0 +1,112 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use onedrive_api::resource::DriveItem;
+use serde::{Serialize, Deserialize};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{File, FileType, ObjectId};
+use crate::interfaces::watch::{ChangeBatch, ChangeEvent, ChangeKind, Watch, WatchCursor};
+use crate::providers::onedrive::OneDrive;
+
+#[derive(Deserialize)]
+struct DeltaPage {
+    value: Vec<DriveItem>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+    #[serde(rename = "@odata.deltaLink")]
+    delta_link: Option<String>,
+}
+
+/// Delta link of the watched folder, along with when it was issued, as the delta does not
+/// tell created items from modified ones.
+#[derive(Serialize, Deserialize)]
+struct DeltaCursor {
+    delta_link: String,
+    since: DateTime<Utc>,
+}
+
+impl DeltaCursor {
+    fn encode(&self) -> Result<WatchCursor, CrossroadsError> {
+        serde_json::to_string(self)
+            .map(WatchCursor)
+            .map_err(|error| CrossroadsError::Provider(Box::new(error)))
+    }
+
+    fn decode(cursor: &WatchCursor) -> Result<DeltaCursor, CrossroadsError> {
+        serde_json::from_str(&cursor.0).map_err(|error| CrossroadsError::InvalidInput(format!("Invalid watch cursor: {}", error)))
+    }
+}
+
+fn delta_url(root: &ObjectId) -> String {
+    if root.as_str().is_empty() {
+        "https://graph.microsoft.com/v1.0/me/drive/root/delta".to_string()
+    } else {
+        format!("https://graph.microsoft.com/v1.0/me/drive/items/{}/delta", root.as_str())
+    }
+}
+
+fn change_of(item: DriveItem, since: DateTime<Utc>) -> ChangeEvent {
+    if item.deleted.is_some() {
+        let file_type = if item.folder.is_some() { FileType::Directory } else { FileType::File };
+        let object_id = ObjectId::new(item.id.map(|id| id.0).unwrap_or_default(), file_type);
+
+        return ChangeEvent { kind: ChangeKind::Deleted, object_id, file: None, previous_id: None }
+    }
+
+    let created_at = item.created_date_time.as_ref().and_then(|date| DateTime::<Utc>::from_str(date.as_str()).ok());
+    let file = File::from(item);
+
+    let kind = if created_at.is_some_and(|created_at| created_at > since) {
+        ChangeKind::Created
+    } else {
+        ChangeKind::Modified
+    };
+
+    ChangeEvent { kind, object_id: file.id.clone(), file: Some(file), previous_id: None }
+}
+
+/// Watches through the delta of the folder, whose link is the cursor. Business accounts only
+/// support the delta of the root of the drive. Renames and moves are reported as modifications.
+#[async_trait]
+impl Watch for OneDrive {
+    async fn watch_cursor(&self, root: ObjectId) -> Result<WatchCursor, CrossroadsError> {
+        let since = Utc::now();
+        let page: DeltaPage = self.get_json(&format!("{}?token=latest", delta_url(&root))).await?;
+
+        let delta_link = page.delta_link
+            .ok_or_else(|| CrossroadsError::Provider("OneDrive did not return a delta link".into()))?;
+
+        DeltaCursor { delta_link, since }.encode()
+    }
+
+    async fn changes_since(&self, _root: ObjectId, cursor: WatchCursor) -> Result<ChangeBatch, CrossroadsError> {
+        let cursor = DeltaCursor::decode(&cursor)?;
+        let now = Utc::now();
+
+        let mut url = cursor.delta_link;
+        let mut events = vec![];
+
+        loop {
+            let page: DeltaPage = self.get_json(&url).await?;
+
+            for item in page.value {
+                let event = change_of(item, cursor.since);
+
+                // Its path may have changed.
+                self.paths.invalidate(&event.object_id);
+                events.push(event);
+            }
+
+            match (page.next_link, page.delta_link) {
+                (Some(next_link), _) => url = next_link,
+                (None, Some(delta_link)) => {
+                    let cursor = DeltaCursor { delta_link, since: now };
+                    return Ok(ChangeBatch { events, cursor: cursor.encode()? })
+                }
+                (None, None) => return Err(CrossroadsError::Provider("OneDrive did not return the delta link of the changes".into())),
+            }
+        }
+    }
+}
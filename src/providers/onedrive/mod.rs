@@ -3,17 +3,24 @@ mod auth;
 pub mod token;
 
 use std::str::FromStr;
+use std::sync::Arc;
 
 use onedrive_api::{ItemId, resource::DriveItem};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
 
-use crate::interfaces::{filesystem::{ObjectId, File, FileSystem, Metadata, FileType}, Provider};
+use crate::error::CrossroadsError;
+use crate::providers::path_cache::PathCache;
+use crate::interfaces::{capabilities::{Capabilities, IdKind}, filesystem::{ObjectId, File, FileSystem, Metadata, FileType, Checksums}, locking::Locking, preview::Preview, search::Search, quota::Quota, sharing::Sharing, versions::Versions, watch::Watch, Provider};
 
 use self::token::TokenStorage;
 
 #[derive(Clone)]
 pub struct OneDrive {
     token: TokenStorage,
-    client_id: String
+    client_id: String,
+    /// Shared between clones, which talk to the same drive.
+    paths: Arc<PathCache>,
 }
 
 impl Provider for OneDrive {
@@ -24,6 +31,104 @@ impl Provider for OneDrive {
     fn as_trash(&self) -> Option<&dyn crate::interfaces::trash::Trash> {
         Some(self)
     }
+
+    fn as_search(&self) -> Option<&dyn Search> {
+        Some(self)
+    }
+
+    fn as_watch(&self) -> Option<&dyn Watch> {
+        Some(self)
+    }
+
+    fn as_versions(&self) -> Option<&dyn Versions> {
+        Some(self)
+    }
+
+    fn as_sharing(&self) -> Option<&dyn Sharing> {
+        Some(self)
+    }
+
+    fn as_quota(&self) -> Option<&dyn Quota> {
+        Some(self)
+    }
+
+    fn as_preview(&self) -> Option<&dyn Preview> {
+        Some(self)
+    }
+
+    fn as_locking(&self) -> Option<&dyn Locking> {
+        Some(self)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            symlinks: false,
+            trash: true,
+            versions: true,
+            sharing: true,
+            search: true,
+            watch: true,
+            quota: true,
+            preview: true,
+            locking: true,
+            shared_locks: false,
+            server_side_copy: true,
+            server_side_move: true,
+            ranged_reads: true,
+            case_sensitive: false,
+            max_file_name_length: Some(255),
+            forbidden_characters: vec!['"', '*', ':', '<', '>', '?', '/', '\\', '|'],
+            id_kind: IdKind::Opaque,
+            empty_directories: true,
+        }
+    }
+}
+
+impl OneDrive {
+    /// Sends a Graph request the API client has no call for, such as following the links of a page.
+    async fn graph_request(&self, method: Method, url: &str, body: Option<&serde_json::Value>) -> Result<reqwest::Response, CrossroadsError> {
+        let client = reqwest::Client::new();
+
+        let request = |token: String| {
+            let request = client.request(method.clone(), url).bearer_auth(token);
+
+            match body {
+                Some(body) => request.json(body),
+                None => request,
+            }
+        };
+
+        let mut response = request(self.access_token().await?).send().await?;
+
+        if response.status() == StatusCode::UNAUTHORIZED {
+            self.refresh_token().await?;
+            response = request(self.access_token().await?).send().await?;
+        }
+
+        let status = response.status();
+
+        if !status.is_success() {
+            return Err(CrossroadsError::from_status(status.as_u16(), response.text().await.unwrap_or_default()))
+        }
+
+        Ok(response)
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, CrossroadsError> {
+        Ok(self.graph_request(Method::GET, url, None).await?.json().await?)
+    }
+}
+
+impl From<onedrive_api::Error> for CrossroadsError {
+    fn from(error: onedrive_api::Error) -> Self {
+        let code = error.error_response().map(|response| response.code.clone());
+
+        match (error.status_code(), code.as_deref()) {
+            (_, Some("nameAlreadyExists")) => CrossroadsError::AlreadyExists(error.to_string()),
+            (Some(status), _) => CrossroadsError::from_status(status.as_u16(), error.to_string()),
+            (None, _) => CrossroadsError::Network(Box::new(error)),
+        }
+    }
 }
 
 impl From<ObjectId> for ItemId {
@@ -32,8 +137,29 @@ impl From<ObjectId> for ItemId {
     }
 }
 
+/// Hashes of the file facet of an item. Personal accounts report SHA-1 and SHA-256,
+/// business ones QuickXorHash.
+fn checksums(item: &DriveItem) -> Option<Checksums> {
+    let hashes = item.file.as_ref()?.get("hashes")?;
+
+    let hash = |name: &str| hashes.get(name).and_then(|hash| hash.as_str()).map(str::to_string);
+
+    Some(Checksums {
+        md5: None,
+        sha1: hash("sha1Hash").map(|hash| hash.to_lowercase()),
+        sha256: hash("sha256Hash").map(|hash| hash.to_lowercase()),
+        quick_xor: hash("quickXorHash"),
+    })
+}
+
 impl From<DriveItem> for File {
     fn from(item: DriveItem) -> Self {
+        let checksums = checksums(&item);
+        let mime_type = if item.folder.is_some() {
+            Some("directory".to_string())
+        } else {
+            item.file.as_ref().and_then(|file| file["mimeType"].as_str()).map(str::to_string)
+        };
         let file_type = if item.file.is_some() {
             FileType::File
         } else if item.folder.is_some() {
@@ -42,19 +168,32 @@ impl From<DriveItem> for File {
             FileType::Symlink
         };
 
+        // Times of the file on the device it was uploaded from, which `set_metadata` changes,
+        // rather than those of the upload.
+        let file_system_time = |name: &str| item.file_system_info.as_ref()
+            .and_then(|info| info[name].as_str())
+            .and_then(|time| chrono::DateTime::from_str(time).ok());
+        let created_at = file_system_time("createdDateTime");
+        let modified_at = file_system_time("lastModifiedDateTime");
+
         File {
             id: ObjectId::new(item.id.unwrap().as_str().to_string(), file_type),
             name: item.name.unwrap(),
             metadata: Some(Metadata {
-                mime_type: if item.folder.is_some() { Some("directory".to_string()) } else { None },
-                created_at: Some(chrono::DateTime::from_str(item.created_date_time.unwrap().as_str()).unwrap()),
-                modified_at: Some(chrono::DateTime::from_str(item.last_modified_date_time.unwrap().as_str()).unwrap()),
+                mime_type,
+                export_mime_type: None,
+                created_at: created_at.or_else(|| Some(chrono::DateTime::from_str(item.created_date_time.unwrap().as_str()).unwrap())),
+                modified_at: modified_at.or_else(|| Some(chrono::DateTime::from_str(item.last_modified_date_time.unwrap().as_str()).unwrap())),
                 meta_changed_at: None,
                 accessed_at: None,
                 size: Some(item.size.unwrap().unsigned_abs()),
                 open_path: None,
                 owner: None,
                 permissions: None,
+                version: item.c_tag.map(|tag| tag.0),
+                checksums,
+                // Kept apart from the item, see `get_properties`.
+                properties: None,
             })
         }
     }
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::interfaces::filesystem::ObjectId;
+
+/// Ids of the entries already resolved from their path, for providers whose ids are opaque
+/// and where resolving a path takes requests. Paths are stored without leading or trailing `/`.
+#[derive(Debug, Default)]
+pub(crate) struct PathCache {
+    entries: Mutex<HashMap<String, ObjectId>>,
+}
+
+impl PathCache {
+    pub(crate) fn get(&self, path: &str) -> Option<ObjectId> {
+        self.entries.lock().unwrap().get(path).cloned()
+    }
+
+    pub(crate) fn path_of(&self, object_id: &ObjectId) -> Option<String> {
+        self.entries.lock().unwrap().iter()
+            .find(|(_, id)| id.as_str() == object_id.as_str())
+            .map(|(path, _)| path.clone())
+    }
+
+    pub(crate) fn insert(&self, path: String, object_id: ObjectId) {
+        self.entries.lock().unwrap().insert(path, object_id);
+    }
+
+    /// Forgets `object_id` along with everything below it, as their paths change with its own.
+    pub(crate) fn invalidate(&self, object_id: &ObjectId) {
+        let mut entries = self.entries.lock().unwrap();
+
+        let removed: Vec<String> = entries.iter()
+            .filter(|(_, id)| id.as_str() == object_id.as_str())
+            .map(|(path, _)| path.clone() + "/")
+            .collect();
+
+        entries.retain(|path, _| !removed.iter().any(|prefix| (path.clone() + "/").starts_with(prefix.as_str())));
+    }
+
+    /// Used when entries were replaced without their ids being known.
+    pub(crate) fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+/// `path` without empty components, such as the ones around leading, trailing or doubled `/`.
+pub(crate) fn normalize(path: &str) -> String {
+    path.split('/').filter(|name| !name.is_empty()).collect::<Vec<_>>().join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interfaces::filesystem::ObjectId;
+
+    use super::{normalize, PathCache};
+
+    #[test]
+    fn path_cache_invalidates_descendants() {
+        let cache = PathCache::default();
+        cache.insert("Documents".to_string(), ObjectId::directory("1".to_string()));
+        cache.insert("Documents/report.pdf".to_string(), ObjectId::plain_text("2".to_string()));
+        cache.insert("Documents2".to_string(), ObjectId::directory("3".to_string()));
+
+        assert_eq!(cache.path_of(&ObjectId::plain_text("2".to_string())).as_deref(), Some("Documents/report.pdf"));
+
+        cache.invalidate(&ObjectId::directory("1".to_string()));
+        assert!(cache.get("Documents").is_none());
+        assert!(cache.get("Documents/report.pdf").is_none());
+        assert!(cache.get("Documents2").is_some());
+
+        assert_eq!(normalize("/Documents//report.pdf/"), "Documents/report.pdf");
+    }
+}
@@ -0,0 +1,79 @@
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::FileRange;
+
+/// Value of the `Range` header requesting `length` bytes starting at `offset`.
+pub(crate) fn range_header(offset: u64, length: u64) -> String {
+    format!("bytes={}-{}", offset, offset + length.max(1) - 1)
+}
+
+/// Extracts the total size from a `Content-Range` header such as `bytes 0-99/1234`
+/// or `bytes */1234`.
+pub(crate) fn content_range_total(header: &str) -> Option<u64> {
+    header.rsplit_once('/').and_then(|(_, total)| total.trim().parse().ok())
+}
+
+/// Builds the range from the response of a ranged GET. Servers that ignore the
+/// `Range` header answer with the whole content, which is sliced here instead.
+pub(crate) async fn range_from_response(response: reqwest::Response, offset: u64, length: u64) -> Result<FileRange, CrossroadsError> {
+    let status = response.status().as_u16();
+
+    let content_range = response.headers().get(reqwest::header::CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(content_range_total);
+
+    if status == 416 {
+        return Ok(FileRange { content: vec![], offset, total_size: content_range.unwrap_or(offset) })
+    }
+
+    let response = response.error_for_status()?;
+    let content = response.bytes().await?.to_vec();
+
+    Ok(slice_content(content, status == 206, content_range, offset, length))
+}
+
+/// Range out of the body of a response, which holds the requested bytes when `partial`, and
+/// the whole content otherwise.
+fn slice_content(mut content: Vec<u8>, partial: bool, content_range: Option<u64>, offset: u64, length: u64) -> FileRange {
+    let total_size = match content_range {
+        Some(total_size) if partial => total_size,
+        _ => content.len() as u64,
+    };
+
+    if !partial {
+        content = content.into_iter().skip(offset as usize).collect();
+    }
+
+    content.truncate(length as usize);
+
+    FileRange { content, offset, total_size }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{content_range_total, range_header, slice_content};
+
+    #[test]
+    fn parses_content_range() {
+        assert_eq!(content_range_total("bytes 0-99/1234"), Some(1234));
+        assert_eq!(content_range_total("bytes */42"), Some(42));
+        assert_eq!(content_range_total("bytes 0-99/*"), None);
+    }
+
+    #[test]
+    fn formats_range_header() {
+        assert_eq!(range_header(0, 100), "bytes=0-99");
+        assert_eq!(range_header(10, 1), "bytes=10-10");
+    }
+
+    #[test]
+    fn slices_whole_content_when_range_is_ignored() {
+        let whole = slice_content(b"0123456789".to_vec(), false, None, 4, 3);
+        assert_eq!(whole.content, b"456");
+        assert_eq!(whole.total_size, 10);
+
+        let partial = slice_content(b"456".to_vec(), true, Some(10), 4, 3);
+        assert_eq!(partial, whole);
+
+        assert!(slice_content(b"0123".to_vec(), false, None, 8, 3).content.is_empty());
+    }
+}
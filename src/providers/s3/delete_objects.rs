@@ -0,0 +1,103 @@
+use regex::Regex;
+use s3::bucket::Bucket;
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{DeleteFailure, FileType, ObjectId};
+
+use super::signing::sign;
+
+/// Most keys a single `DeleteObjects` request accepts.
+pub(super) const MAX_DELETE_OBJECTS_KEYS: usize = 1000;
+
+/// Deletes up to `MAX_DELETE_OBJECTS_KEYS` keys in one request and returns the keys that could not be deleted.
+///
+/// The bucket does not expose `DeleteObjects`, so the request is signed here.
+pub(super) async fn delete_objects(bucket: &Bucket, keys: &[String]) -> Result<Vec<DeleteFailure>, CrossroadsError> {
+    let body = request_body(keys);
+    let content_md5 = base64::encode(md5::compute(&body).0);
+
+    let request = sign(bucket, "POST", "/", &[("delete", "")], &[("content-md5", content_md5)], &body)?;
+
+    let response = request.build(&reqwest::Client::new(), reqwest::Method::POST)
+        .body(body)
+        .send().await?;
+
+    let status = response.status().as_u16();
+    let text = response.text().await?;
+
+    if !(200..300).contains(&status) {
+        return Err(CrossroadsError::from_status(status, text))
+    }
+
+    Ok(parse_errors(&text))
+}
+
+/// In quiet mode, the response only lists the keys that could not be deleted.
+fn request_body(keys: &[String]) -> Vec<u8> {
+    let objects: String = keys.iter()
+        .map(|key| format!("<Object><Key>{}</Key></Object>", escape(key)))
+        .collect();
+
+    format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?><Delete><Quiet>true</Quiet>{}</Delete>", objects).into_bytes()
+}
+
+fn parse_errors(response: &str) -> Vec<DeleteFailure> {
+    let error = Regex::new(r"(?s)<Error>\s*<Key>(.*?)</Key>\s*<Code>(.*?)</Code>\s*<Message>(.*?)</Message>").unwrap();
+
+    error.captures_iter(response)
+        .map(|captures| {
+            let key = unescape(&captures[1]);
+            let message = format!("{}: {}", key, unescape(&captures[3]));
+
+            let error = match &captures[2] {
+                "AccessDenied" => CrossroadsError::PermissionDenied(message),
+                "SlowDown" => CrossroadsError::RateLimited(None),
+                code => CrossroadsError::Provider(format!("{} ({})", message, code).into()),
+            };
+
+            let file_type = if key.ends_with('/') { FileType::Directory } else { FileType::File };
+
+            DeleteFailure { object_id: ObjectId::new("/".to_string() + key.trim_end_matches('/'), file_type), error }
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+pub(super) fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::CrossroadsError;
+
+    use super::{parse_errors, request_body};
+
+    #[test]
+    fn delete_objects_escapes_keys_and_parses_errors() {
+        let body = String::from_utf8(request_body(&["a&b.txt".to_string()])).unwrap();
+        assert!(body.contains("<Quiet>true</Quiet><Object><Key>a&amp;b.txt</Key></Object>"));
+
+        let response = "<DeleteResult>\
+            <Error><Key>locked/a&amp;b.txt</Key><Code>AccessDenied</Code><Message>Access Denied</Message></Error>\
+            <Error><Key>other.txt</Key><Code>InternalError</Code><Message>Try again</Message></Error>\
+            </DeleteResult>";
+
+        let failures = parse_errors(response);
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].object_id.as_str(), "/locked/a&b.txt");
+        assert!(matches!(failures[0].error, CrossroadsError::PermissionDenied(_)));
+        assert!(matches!(failures[1].error, CrossroadsError::Provider(_)));
+    }
+}
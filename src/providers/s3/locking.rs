@@ -0,0 +1,145 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::ObjectId;
+use crate::interfaces::locking::{self, Lock, LockMode, LockOptions, Locking};
+
+use super::signing::sign;
+use super::S3;
+
+/// Suffix of the key of the object holding the lock of another.
+const LOCK_SUFFIX: &str = ".lock";
+
+/// Content of a lock object.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+struct LockRecord {
+    token: String,
+    expires_at: DateTime<Utc>,
+}
+
+fn lock_key(object_id: &ObjectId) -> String {
+    format!("{}{}", object_id.as_str(), LOCK_SUFFIX)
+}
+
+impl S3 {
+    async fn send_lock_request(&self, method: reqwest::Method, key: &str, headers: &[(&str, String)], payload: Vec<u8>) -> Result<reqwest::Response, CrossroadsError> {
+        let request = sign(&self.bucket()?, method.as_str(), key, &[], headers, &payload)?;
+        Ok(request.build(&reqwest::Client::new(), method).body(payload).send().await?)
+    }
+
+    /// Current lock object of `key` with its ETag, `None` when there is none.
+    async fn read_lock_record(&self, key: &str) -> Result<Option<(LockRecord, String)>, CrossroadsError> {
+        let response = self.send_lock_request(reqwest::Method::GET, key, &[], vec![]).await?;
+        let status = response.status().as_u16();
+
+        if status == 404 {
+            return Ok(None)
+        }
+
+        if !(200..300).contains(&status) {
+            return Err(CrossroadsError::from_status(status, key.to_string()))
+        }
+
+        let etag = response.headers().get("etag").and_then(|etag| etag.to_str().ok()).unwrap_or_default().to_string();
+        let record = serde_json::from_slice(&response.bytes().await?)
+            .map_err(|error| CrossroadsError::Provider(format!("Invalid lock object {}: {}", key, error).into()))?;
+
+        Ok(Some((record, etag)))
+    }
+
+    /// Writes the lock object under `condition`, `false` when another holder wrote it first.
+    async fn write_lock_record(&self, key: &str, record: &LockRecord, condition: (&str, String)) -> Result<bool, CrossroadsError> {
+        let payload = serde_json::to_vec(record).map_err(|error| CrossroadsError::Provider(Box::new(error)))?;
+        let headers = [condition, ("content-type", "application/json".to_string())];
+
+        let response = self.send_lock_request(reqwest::Method::PUT, key, &headers, payload).await?;
+
+        match response.status().as_u16() {
+            // 409 is returned to the loser of concurrent conditional writes.
+            409 | 412 => Ok(false),
+            status if (200..300).contains(&status) => Ok(true),
+            status => Err(CrossroadsError::from_status(status, response.text().await.unwrap_or_default())),
+        }
+    }
+}
+
+/// Locks are objects next to the locked one, named after it with `.lock`, which are created
+/// only when missing through conditional writes. They hold the token of their holder and
+/// their expiry, after which another holder takes them over, as the holder may be gone.
+/// Buckets must support conditional writes, and only exclusive locks are taken.
+#[async_trait]
+impl Locking for S3 {
+    async fn lock(&self, object_id: ObjectId, options: LockOptions) -> Result<Lock, CrossroadsError> {
+        if options.mode == LockMode::Shared {
+            return Err(CrossroadsError::unsupported("S3 shared locks"))
+        }
+
+        let lease = chrono::Duration::from_std(options.lease)
+            .map_err(|error| CrossroadsError::InvalidInput(error.to_string()))?;
+        let key = lock_key(&object_id);
+        let token = format!("{:032x}", rand::random::<u128>());
+
+        locking::retry_lock(&object_id, options.wait, || async {
+            let record = LockRecord { token: token.clone(), expires_at: Utc::now() + lease };
+
+            let mut locked = self.write_lock_record(&key, &record, ("if-none-match", "*".to_string())).await?;
+
+            if !locked {
+                if let Some((current, etag)) = self.read_lock_record(&key).await? {
+                    if current.expires_at <= Utc::now() {
+                        locked = self.write_lock_record(&key, &record, ("if-match", etag)).await?;
+                    }
+                }
+            }
+
+            Ok(locked.then(|| Lock {
+                object_id: object_id.clone(),
+                mode: LockMode::Exclusive,
+                token: token.clone(),
+                expires_at: Some(record.expires_at),
+            }))
+        }).await
+    }
+
+    /// Fails with `CrossroadsError::Conflict` when the lock expired and was taken over.
+    async fn unlock(&self, lock: Lock) -> Result<(), CrossroadsError> {
+        let key = lock_key(&lock.object_id);
+
+        let etag = match self.read_lock_record(&key).await? {
+            Some((record, etag)) if record.token == lock.token => etag,
+            Some(_) => return Err(CrossroadsError::Conflict(format!("lock of {} was taken over", lock.object_id))),
+            None => return Err(CrossroadsError::NotFound(format!("lock of {}", lock.object_id))),
+        };
+
+        // Deleting only that version of the lock object keeps a new holder's one, where supported.
+        let response = self.send_lock_request(reqwest::Method::DELETE, &key, &[("if-match", etag)], vec![]).await?;
+        let status = response.status().as_u16();
+
+        if !(200..300).contains(&status) {
+            return Err(CrossroadsError::from_status(status, response.text().await.unwrap_or_default()))
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::interfaces::filesystem::ObjectId;
+
+    use super::{lock_key, LockRecord};
+
+    #[test]
+    fn lock_objects_are_named_after_the_locked_one() {
+        assert_eq!(lock_key(&ObjectId::plain_text("reports/summary.pdf".to_string())), "reports/summary.pdf.lock");
+
+        let record = LockRecord { token: "f3c1".to_string(), expires_at: Utc.with_ymd_and_hms(2022, 6, 1, 12, 0, 0).unwrap() };
+        let content = serde_json::to_string(&record).unwrap();
+        assert_eq!(content, r#"{"token":"f3c1","expires_at":"2022-06-01T12:00:00Z"}"#);
+        assert_eq!(serde_json::from_str::<LockRecord>(&content).unwrap(), record);
+    }
+}
@@ -0,0 +1,967 @@
+mod delete_objects;
+mod locking;
+mod properties;
+mod sharing;
+mod signing;
+mod versions;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Read;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use eyre::Result;
+use futures::{future, stream, TryStreamExt};
+use s3::{creds::Credentials, bucket::Bucket, serde_types::Object, blocking::AttoRequest, command::Command, error::S3Error, request_trait::{Request, ResponseData}};
+use serde::{Serialize, Deserialize};
+use tokio::{runtime::Handle, sync::mpsc, task};
+
+use crate::error::CrossroadsError;
+use crate::mime;
+use crate::providers::{chunked, conflict, range, thumbnail};
+use crate::interfaces::capabilities::{Capabilities, IdKind};
+use crate::interfaces::{filesystem::{FileSystem, ObjectId, File, Metadata, MetadataField, MetadataUpdate, FileType, ByteStream, FileRange, DirectoryPage, ConflictPolicy, DeleteFailure, Checksums, FileStream}, search::{Search, SearchQuery}, Provider};
+use crate::interfaces::locking::Locking;
+use crate::interfaces::preview::{Preview, Thumbnail, ThumbnailSize};
+use crate::interfaces::quota::{Quota, StorageQuota};
+use crate::interfaces::sharing::Sharing;
+use crate::interfaces::versions::Versions;
+use crate::interfaces::watch::{ChangeBatch, Watch, WatchCursor};
+use crate::providers::snapshot::{self, Snapshot};
+
+use self::delete_objects::{delete_objects, MAX_DELETE_OBJECTS_KEYS};
+
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+/// Largest object a single `CopyObject` request accepts.
+const MAX_COPY_OBJECT_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct S3Credentials {
+    pub region: String,
+    pub endpoint: String,
+    pub access_key: String,
+    pub secret_key: String
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct S3 {
+    pub credentials: S3Credentials,
+    pub bucket: String,
+    /// Size the bucket is allowed to grow to, S3 itself having no limit.
+    #[serde(default)]
+    pub size_limit: Option<u64>,
+}
+
+impl S3 {
+    pub fn new(bucket: String, credentials: S3Credentials) -> S3 {
+        S3 { credentials, bucket, size_limit: None }
+    }
+
+    pub fn with_size_limit(self, size_limit: u64) -> S3 {
+        S3 { size_limit: Some(size_limit), ..self }
+    }
+
+    fn bucket(&self) -> Result<Bucket, CrossroadsError> {
+        let mut bucket = Bucket::new(
+            self.bucket.as_str(),
+            s3::region::Region::Custom { region: self.credentials.region.clone(), endpoint: self.credentials.endpoint.clone() },
+            Credentials {
+                access_key: Some(self.credentials.access_key.clone()),
+                secret_key: Some(self.credentials.secret_key.clone()),
+                security_token: None, session_token: None, expiration: None
+            }
+        )?;
+
+        bucket.set_path_style();
+
+        Ok(bucket)
+    }
+
+    /// Copies a single object, streaming it through when it is too large for `CopyObject`.
+    async fn copy_object(&self, bucket: &Bucket, from: &str, to: &str, size: u64) -> Result<(), CrossroadsError> {
+        if size > MAX_COPY_OBJECT_SIZE {
+            let content = self.read_file_stream(ObjectId::plain_text(from.to_string())).await?;
+            return self.write_file_stream(ObjectId::plain_text(to.to_string()), content, Some(size)).await
+        }
+
+        match bucket.copy_object_internal(from, to)? {
+            200..=299 => Ok(()),
+            status => Err(CrossroadsError::from_status(status, format!("Unable to copy {} to {}", from, to))),
+        }
+    }
+
+    /// Deletes every object whose key starts with `prefix` but those `kept`, in batches of
+    /// `DeleteObjects`.
+    async fn delete_prefix(&self, bucket: &Bucket, prefix: &str, kept: &BTreeSet<String>) -> Result<Vec<DeleteFailure>, CrossroadsError> {
+        let mut failures = vec![];
+        let mut page_token = None;
+
+        loop {
+            let (result, _) = bucket.list_page(prefix.to_string(), None, page_token, None, Some(MAX_DELETE_OBJECTS_KEYS))?;
+            let keys: Vec<String> = result.contents.into_iter().map(|object| object.key).filter(|key| !kept.contains(key)).collect();
+
+            if !keys.is_empty() {
+                failures.extend(delete_objects(bucket, &keys).await?);
+            }
+
+            page_token = match result.next_continuation_token {
+                Some(token) if result.is_truncated => Some(token),
+                _ => break,
+            };
+        }
+
+        Ok(failures)
+    }
+
+    /// Deletes what was at `key` before the `copied` objects replaced it, that is the object
+    /// `key` and the objects under it they did not overwrite.
+    async fn delete_replaced(&self, bucket: &Bucket, key: &str, copied: &BTreeSet<String>) -> Result<(), CrossroadsError> {
+        let mut failures = self.delete_prefix(bucket, &(key.to_string() + "/"), copied).await?;
+
+        if !copied.contains(key) && (200..300).contains(&bucket.head_object(key)?.1) {
+            failures.extend(delete_objects(bucket, &[key.to_string()]).await?);
+        }
+
+        match failures.into_iter().next() {
+            Some(failure) => Err(failure.error),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Id of `object_id` once renamed to `new_name`, which keeps its parent as on the other
+/// providers, and the leading "/" of the id if it has one.
+fn renamed_id(object_id: &ObjectId, new_name: &str) -> ObjectId {
+    let (parent, _) = object_id.as_str().rsplit_once('/').unwrap_or(("", ""));
+    let separator = if parent.is_empty() && !object_id.as_str().starts_with('/') { "" } else { "/" };
+
+    ObjectId::new(format!("{}{}{}", parent, separator, new_name.trim_start_matches('/')), object_id.file_type())
+}
+
+/// Whether `key` is an object or the prefix of a directory.
+fn key_exists(bucket: &Bucket, key: &str) -> Result<bool, CrossroadsError> {
+    let (result, _) = bucket.list_page(key.to_string() + "/", None, None, None, Some(1))?;
+
+    if !result.contents.is_empty() {
+        return Ok(true)
+    }
+
+    let (_, status) = bucket.head_object(key)?;
+    Ok((200..300).contains(&status))
+}
+
+/// The ETag of an object is the MD5 of its content unless it was uploaded in parts, in which
+/// case it ends with the number of parts, or encrypted with a KMS key.
+fn md5_from_etag(etag: &str) -> Option<String> {
+    let etag = etag.trim_matches('"');
+
+    if etag.len() == 32 && etag.chars().all(|character| character.is_ascii_hexdigit()) {
+        Some(etag.to_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Checksums of composite objects end with the number of parts and are not decoded.
+fn hex_from_base64(checksum: &str) -> Option<String> {
+    base64::decode(checksum).ok().map(hex::encode)
+}
+
+fn file_from_object(object: Object, name: String) -> File {
+    File {
+        id: ObjectId::new("/".to_string() + &object.key, FileType::File),
+        name,
+        metadata: Some(Metadata {
+            mime_type: mime::from_name(&object.key),
+            export_mime_type: None,
+            created_at: None,
+            modified_at: chrono::DateTime::from_str(object.last_modified.as_str()).ok(),
+            meta_changed_at: None,
+            accessed_at: None,
+            size: Some(object.size),
+            open_path: None,
+            owner: None,
+            permissions: None,
+            checksums: object.e_tag.as_deref().and_then(md5_from_etag).map(|md5| Checksums { md5: Some(md5), ..Default::default() }),
+            version: object.e_tag,
+            // Only returned by `HeadObject`, one object at a time.
+            properties: None,
+        })
+    }
+}
+
+/// Key prefix shared by the objects of a directory, empty for the root of the bucket.
+fn directory_prefix(object_id: &ObjectId) -> String {
+    let path = object_id.as_str().trim_matches('/');
+
+    if path.is_empty() {
+        String::new()
+    } else {
+        path.to_string() + "/"
+    }
+}
+
+/// Blocking reader over a `ByteStream`, used to feed the synchronous multipart
+/// upload of the bucket from a blocking task.
+struct StreamReader {
+    content: ByteStream,
+    chunk: Bytes,
+    runtime: Handle,
+}
+
+impl Read for StreamReader {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.runtime.block_on(self.content.try_next()) {
+                Ok(Some(chunk)) => self.chunk = chunk,
+                Ok(None) => return Ok(0),
+                Err(error) => return Err(std::io::Error::other(error)),
+            }
+        }
+
+        let length = buffer.len().min(self.chunk.len());
+        buffer[..length].copy_from_slice(&self.chunk.split_to(length));
+
+        Ok(length)
+    }
+}
+
+impl From<S3Error> for CrossroadsError {
+    fn from(error: S3Error) -> Self {
+        match error {
+            S3Error::Http(status, message) => CrossroadsError::from_status(status, message),
+            S3Error::Io(error) => error.into(),
+            S3Error::Atto(error) => CrossroadsError::Network(Box::new(error)),
+            S3Error::Credentials(_) => CrossroadsError::AuthenticationRequired,
+            error => CrossroadsError::Provider(Box::new(error)),
+        }
+    }
+}
+
+/// The bucket is used without the `fail-on-err` feature, so non-2xx responses
+/// come back as data and have to be turned into errors here.
+fn check_status(response: ResponseData) -> Result<ResponseData, CrossroadsError> {
+    let status = response.status_code();
+
+    if (200..300).contains(&status) {
+        return Ok(response)
+    }
+
+    let message = String::from_utf8_lossy(response.bytes()).to_string();
+
+    if status == 503 && message.contains("<Code>SlowDown</Code>") {
+        return Err(CrossroadsError::RateLimited(None))
+    }
+
+    Err(CrossroadsError::from_status(status, message))
+}
+
+#[async_trait]
+impl Search for S3 {
+    /// Every key of the bucket is listed and filtered here, S3 having no search of its own.
+    /// Listings have no content type, so MIME types are matched against the ones guessed
+    /// from the keys.
+    async fn search(&self, query: SearchQuery) -> Result<FileStream<'_>, CrossroadsError> {
+        if query.full_text.is_some() {
+            return Err(CrossroadsError::unsupported("S3 search by content"))
+        }
+
+        let filter = query.filter()?;
+        let bucket = self.bucket()?;
+
+        let pages = stream::try_unfold(Some(None), move |page_token: Option<Option<String>>| {
+            let bucket = bucket.clone();
+
+            async move {
+                let Some(page_token) = page_token else {
+                    return Ok::<_, CrossroadsError>(None)
+                };
+
+                let (result, _) = bucket.list_page(String::new(), None, page_token, None, None)?;
+
+                let files: Vec<File> = result.contents.into_iter()
+                    // Marker objects of directories.
+                    .filter(|object| !object.key.ends_with('/'))
+                    .map(|object| {
+                        let name = object.key.rsplit('/').next().unwrap_or_default().to_string();
+                        file_from_object(object, name)
+                    })
+                    .collect();
+
+                let next_page_token = match result.next_continuation_token {
+                    Some(token) if result.is_truncated => Some(Some(token)),
+                    _ => None,
+                };
+
+                Ok(Some((files, next_page_token)))
+            }
+        });
+
+        let files = pages.map_ok(|files| stream::iter(files.into_iter().map(Ok::<_, CrossroadsError>))).try_flatten();
+
+        Ok(Box::pin(files.try_filter(move |file| future::ready(filter.matches(file)))))
+    }
+}
+
+/// The used space is computed by listing every key of the bucket, former versions of the
+/// objects being left out. The total is the configured `size_limit`.
+#[async_trait]
+impl Quota for S3 {
+    async fn quota(&self) -> Result<StorageQuota, CrossroadsError> {
+        let bucket = self.bucket()?;
+        let mut used = 0;
+        let mut page_token = None;
+
+        loop {
+            let (result, _) = bucket.list_page(String::new(), None, page_token, None, None)?;
+            used += result.contents.iter().map(|object| object.size).sum::<u64>();
+
+            page_token = match result.next_continuation_token {
+                Some(token) if result.is_truncated => Some(token),
+                _ => break,
+            };
+        }
+
+        Ok(StorageQuota {
+            total: self.size_limit,
+            used: Some(used),
+            free: self.size_limit.map(|limit| limit.saturating_sub(used)),
+            trash: None,
+        })
+    }
+}
+
+/// S3 has no change feed in its API, so the keys are listed again every time and compared with
+/// the snapshot held by the cursor. Copies keep the ETag, which tells renames apart.
+/// Thumbnails of images are rendered here, see `thumbnail::generate`. They are cached under
+/// the URL of the object.
+#[async_trait]
+impl Preview for S3 {
+    async fn thumbnail(&self, object_id: ObjectId, size: ThumbnailSize) -> Result<Thumbnail, CrossroadsError> {
+        let uri = self.bucket()?.url() + &signing::encode_path(object_id.as_str());
+        thumbnail::generate(self, object_id, &uri, size).await
+    }
+}
+
+#[async_trait]
+impl Watch for S3 {
+    async fn watch_cursor(&self, root: ObjectId) -> Result<WatchCursor, CrossroadsError> {
+        Snapshot::of(&snapshot::walk(self, root).await?).to_cursor()
+    }
+
+    async fn changes_since(&self, root: ObjectId, cursor: WatchCursor) -> Result<ChangeBatch, CrossroadsError> {
+        Snapshot::from_cursor(&cursor)?.diff(snapshot::walk(self, root).await?)
+    }
+}
+
+impl Provider for S3 {
+    fn as_filesystem(&self) -> Option<&dyn crate::interfaces::filesystem::FileSystem> {
+        Some(self)
+    }
+
+    fn as_trash(&self) -> Option<&dyn crate::interfaces::trash::Trash> {
+        None
+    }
+
+    fn as_search(&self) -> Option<&dyn Search> {
+        Some(self)
+    }
+
+    fn as_watch(&self) -> Option<&dyn Watch> {
+        Some(self)
+    }
+
+    fn as_versions(&self) -> Option<&dyn Versions> {
+        Some(self)
+    }
+
+    fn as_sharing(&self) -> Option<&dyn Sharing> {
+        Some(self)
+    }
+
+    fn as_quota(&self) -> Option<&dyn Quota> {
+        Some(self)
+    }
+
+    fn as_preview(&self) -> Option<&dyn Preview> {
+        Some(self)
+    }
+
+    fn as_locking(&self) -> Option<&dyn Locking> {
+        Some(self)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            symlinks: false,
+            trash: false,
+            versions: true,
+            sharing: true,
+            search: true,
+            watch: true,
+            quota: true,
+            preview: true,
+            locking: true,
+            shared_locks: false,
+            server_side_copy: true,
+            server_side_move: false,
+            ranged_reads: true,
+            case_sensitive: true,
+            // Limit of the whole key, which includes the parent prefixes.
+            max_file_name_length: Some(1024),
+            forbidden_characters: vec!['/'],
+            id_kind: IdKind::Path,
+            // Directories are only prefixes of the keys they contain.
+            empty_directories: false,
+        }
+    }
+}
+
+#[async_trait]
+impl FileSystem for S3 {
+    async fn read_file(&self, object_id: ObjectId) -> Result<Vec<u8>, CrossroadsError>
+    {
+        let bucket = self.bucket()?;
+
+        let val = check_status(bucket.get_object(object_id.to_string())?)?;
+
+        Ok(val.bytes().to_vec())
+    }
+
+    async fn read_file_stream(&self, object_id: ObjectId) -> Result<ByteStream, CrossroadsError> {
+        let bucket = self.bucket()?;
+        let path = object_id.to_string();
+
+        let mut response = AttoRequest::new(&bucket, &path, Command::GetObject).response()?;
+
+        if !response.is_success() {
+            let status = response.status().as_u16();
+            return Err(CrossroadsError::from_status(status, response.text().unwrap_or_default()))
+        }
+
+        let (sender, receiver) = mpsc::channel(4);
+
+        task::spawn_blocking(move || {
+            loop {
+                let mut buffer = vec![0; READ_CHUNK_SIZE];
+                let chunk = match response.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(length) => {
+                        buffer.truncate(length);
+                        Ok(Bytes::from(buffer))
+                    },
+                    Err(error) => Err(CrossroadsError::from(error)),
+                };
+
+                let failed = chunk.is_err();
+                if sender.blocking_send(chunk).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        let content = stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|chunk| (chunk, receiver))
+        });
+
+        Ok(Box::pin(content))
+    }
+
+    async fn write_file_stream(&self, object_id: ObjectId, content: ByteStream, _size: Option<u64>) -> Result<(), CrossroadsError> {
+        let bucket = self.bucket()?;
+        let path = object_id.to_string();
+        let mut reader = StreamReader { content, chunk: Bytes::new(), runtime: Handle::current() };
+
+        // Guessed from the name, or kept from the object replaced when the name tells nothing.
+        let content_type = match mime::from_name(&path) {
+            Some(mime_type) => mime_type,
+            None => bucket.head_object(path.trim_start_matches('/')).ok()
+                .filter(|(_, status)| (200..300).contains(status))
+                .and_then(|(head, _)| head.content_type)
+                .unwrap_or_else(|| mime::OCTET_STREAM.to_string()),
+        };
+
+        let status = task::spawn_blocking(move || bucket.put_object_stream_with_content_type(&mut reader, path, content_type))
+            .await
+            .map_err(|error| CrossroadsError::Provider(Box::new(error)))??;
+
+        match status {
+            200..=299 => Ok(()),
+            status => Err(CrossroadsError::from_status(status, format!("Unable to upload {}", object_id))),
+        }
+    }
+
+    /// Sent as a single `PutObject` with `If-Match`, which requires the content to be buffered.
+    async fn write_file_stream_if_match(&self, object_id: ObjectId, content: ByteStream, _size: Option<u64>, version: String) -> Result<(), CrossroadsError> {
+        let mut bucket = self.bucket()?;
+        let content = chunked::collect(content).await?;
+
+        let if_match = version.parse().map_err(|_| CrossroadsError::InvalidInput(format!("Invalid version {}", version)))?;
+        bucket.extra_headers.insert("if-match", if_match);
+
+        // S3 answers 412 when the ETag no longer matches.
+        check_status(bucket.put_object(object_id.as_str().trim_start_matches('/'), &content)?)?;
+
+        Ok(())
+    }
+
+    async fn read_file_range(&self, object_id: ObjectId, offset: u64, length: u64) -> Result<FileRange, CrossroadsError> {
+        let headers = [("range", range::range_header(offset, length))];
+        let response = signing::sign(&self.bucket()?, "GET", object_id.as_str(), &[], &headers, &[])?
+            .build(&reqwest::Client::new(), reqwest::Method::GET)
+            .send().await?;
+
+        range::range_from_response(response, offset, length).await
+    }
+
+    async fn delete(&self, object_id: ObjectId) -> Result<(), CrossroadsError> {
+        let bucket = self.bucket()?;
+
+        if !object_id.is_directory() {
+            check_status(bucket.delete_object(object_id.as_str().trim_start_matches('/'))?)?;
+            return Ok(())
+        }
+
+        let prefix = directory_prefix(&object_id);
+        let (result, _) = bucket.list_page(prefix.clone(), None, None, None, Some(2))?;
+
+        if result.contents.iter().any(|object| object.key != prefix) {
+            return Err(CrossroadsError::Conflict(format!("{} is not empty", object_id)))
+        }
+
+        // Only the marker object, if any, is left.
+        let response = bucket.delete_object(&prefix)?;
+
+        // Deleting a missing key succeeds on S3 but answers 404 on some compatible services.
+        if response.status_code() != 404 {
+            check_status(response)?;
+        }
+
+        Ok(())
+    }
+
+    async fn delete_recursive(&self, object_id: ObjectId) -> Result<Vec<DeleteFailure>, CrossroadsError> {
+        if !object_id.is_directory() {
+            return self.delete(object_id).await.map(|_| vec![])
+        }
+
+        let bucket = self.bucket()?;
+        let prefix = directory_prefix(&object_id);
+
+        if prefix.is_empty() {
+            return Err(CrossroadsError::InvalidInput("Cannot delete the root of the bucket".to_string()))
+        }
+
+        self.delete_prefix(&bucket, &prefix, &BTreeSet::new()).await
+    }
+
+    async fn create(&self, parent_id: ObjectId, file: File) -> Result<ObjectId, CrossroadsError> {
+        let bucket = self.bucket()?;
+        let key = directory_prefix(&parent_id) + &file.name;
+
+        if file.id.is_directory() {
+            // Marker object, so that the directory is listed before anything is put in it.
+            check_status(bucket.put_object(key.clone() + "/", &[])?)?;
+        } else {
+            let content_type = file.metadata.and_then(|metadata| metadata.mime_type)
+                .or_else(|| mime::from_name(&file.name))
+                .unwrap_or_else(|| mime::OCTET_STREAM.to_string());
+
+            check_status(bucket.put_object_with_content_type(&key, &[], &content_type)?)?;
+        }
+
+        Ok(ObjectId::new("/".to_string() + &key, file.id.file_type()))
+    }
+
+    async fn rename(&self, object_id: ObjectId, new_name: String) -> Result<ObjectId, CrossroadsError> {
+        if object_id.is_directory() {
+            return Err(CrossroadsError::unsupported("S3 rename of directories"))
+        }
+
+        let renamed = renamed_id(&object_id, &new_name);
+        let path = object_id.as_str().trim_start_matches('/').to_string();
+        let new_path = renamed.as_str().trim_start_matches('/').to_string();
+
+        let bucket = self.bucket()?;
+
+        // The source is only deleted once the copy is known to have succeeded.
+        match bucket.copy_object_internal(&path, new_path.clone())? {
+            200..=299 => {},
+            status => return Err(CrossroadsError::from_status(status, format!("Unable to copy {} to {}", path, new_path))),
+        }
+
+        check_status(bucket.delete_object(path)?)?;
+
+        Ok(renamed)
+    }
+
+    async fn move_to(&self, _object_id: ObjectId, _new_parent_id: ObjectId) -> Result<ObjectId, CrossroadsError> {
+        Err(CrossroadsError::unsupported("S3 move_to"))
+    }
+
+    async fn copy(&self, object_id: ObjectId, new_parent_id: ObjectId, name: &str, conflict: ConflictPolicy) -> Result<ObjectId, CrossroadsError> {
+        let bucket = self.bucket()?;
+        let parent = directory_prefix(&new_parent_id);
+
+        let name = conflict::resolve_name(name, conflict, |candidate| {
+            let taken = key_exists(&bucket, &(parent.clone() + &candidate));
+            async move { taken }
+        }).await?;
+
+        let destination = parent + &name;
+
+        if object_id.is_directory() {
+            let source = directory_prefix(&object_id);
+            let destination_prefix = destination.clone() + "/";
+
+            if destination_prefix.starts_with(&source) {
+                return Err(CrossroadsError::InvalidInput(format!("Cannot copy {} into itself", object_id)))
+            }
+
+            let mut copied = BTreeSet::new();
+            let mut page_token = None;
+
+            loop {
+                let (result, _) = bucket.list_page(source.clone(), None, page_token, None, None)?;
+
+                for object in result.contents {
+                    let target = destination_prefix.clone() + &object.key[source.len()..];
+                    self.copy_object(&bucket, &object.key, &target, object.size).await?;
+                    copied.insert(target);
+                }
+
+                page_token = match result.next_continuation_token {
+                    Some(token) if result.is_truncated => Some(token),
+                    _ => break,
+                };
+            }
+
+            // Objects are overwritten by the copy, the others of what it replaces only being
+            // deleted once it succeeded.
+            if conflict == ConflictPolicy::Replace {
+                self.delete_replaced(&bucket, &destination, &copied).await?;
+            }
+        } else {
+            let source = object_id.as_str().trim_start_matches('/');
+
+            if source == destination {
+                return Err(CrossroadsError::InvalidInput(format!("Cannot copy {} into itself", object_id)))
+            }
+
+            let (head, status) = bucket.head_object(source)?;
+
+            if !(200..300).contains(&status) {
+                return Err(CrossroadsError::from_status(status, source.to_string()))
+            }
+
+            // `CopyObject` overwrites the object, but not a directory of the same name.
+            self.copy_object(&bucket, source, &destination, head.content_length.unwrap_or(0).unsigned_abs()).await?;
+
+            if conflict == ConflictPolicy::Replace {
+                self.delete_replaced(&bucket, &destination, &BTreeSet::from([destination.clone()])).await?;
+            }
+        }
+
+        Ok(ObjectId::new("/".to_string() + &destination, object_id.file_type()))
+    }
+
+    async fn read_directory_page(&self, object_id: ObjectId, page_size: usize, page_token: Option<String>) -> Result<DirectoryPage, CrossroadsError> {
+        let prefix = directory_prefix(&object_id);
+
+        let bucket = self.bucket()?;
+
+        // With a delimiter, objects nested deeper are rolled up into one common prefix per subdirectory.
+        let (result, _) = bucket.list_page(prefix.clone(), Some("/".to_string()), page_token, None, Some(page_size))?;
+
+        let mut files = vec![];
+
+        for object in result.contents {
+            let name = object.key.strip_prefix(&prefix).unwrap_or(&object.key);
+
+            // Marker object of the directory itself.
+            if name.is_empty() || name.ends_with('/') {
+                continue
+            }
+
+            let name = name.to_string();
+            files.push(file_from_object(object, name));
+        }
+
+        for common_prefix in result.common_prefixes.unwrap_or_default() {
+            let path = common_prefix.prefix.trim_end_matches('/');
+            let name = path.strip_prefix(&prefix).unwrap_or(path);
+
+            files.push(File {
+                id: ObjectId::directory("/".to_string() + path),
+                name: name.to_string(),
+                metadata: None,
+            });
+        }
+
+        let next_page_token = if result.is_truncated { result.next_continuation_token } else { None };
+
+        Ok(DirectoryPage { files, next_page_token })
+    }
+
+    async fn get_metadata(&self, object_id: ObjectId) -> Result<crate::interfaces::filesystem::Metadata, CrossroadsError> {
+        // Directories are only prefixes, there is nothing to describe them.
+        if object_id.is_directory() {
+            return Ok(Metadata::default())
+        }
+
+        let mut bucket = self.bucket()?;
+        // Additional checksums are only returned on request.
+        bucket.extra_headers.insert("x-amz-checksum-mode", "ENABLED".parse().expect("valid header value"));
+
+        let path = object_id.to_string();
+        let response = AttoRequest::new(&bucket, &path, Command::HeadObject).response()?;
+        let status = response.status().as_u16();
+
+        if !(200..300).contains(&status) {
+            return Err(CrossroadsError::from_status(status, object_id.to_string()))
+        }
+
+        let header = |name: &str| response.headers().get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        let etag = header("etag");
+
+        let checksums = Checksums {
+            md5: etag.as_deref().and_then(md5_from_etag),
+            sha1: header("x-amz-checksum-sha1").and_then(|checksum| hex_from_base64(&checksum)),
+            sha256: header("x-amz-checksum-sha256").and_then(|checksum| hex_from_base64(&checksum)),
+            quick_xor: None,
+        };
+
+        let properties = properties::stored_properties(response.headers().iter().filter_map(|(name, value)| Some((name.as_str(), value.to_str().ok()?))));
+
+        let mut metadata = Metadata {
+            // The stored type, unless it is the default one of objects uploaded without.
+            mime_type: header("content-type").filter(|mime_type| !mime::is_generic(mime_type)).or_else(|| mime::from_name(object_id.as_str())),
+            modified_at: header("last-modified").and_then(|date| chrono::DateTime::parse_from_rfc2822(&date).ok()).map(|date| date.with_timezone(&chrono::Utc)),
+            size: header("content-length").and_then(|size| size.parse().ok()),
+            version: etag,
+            checksums: Some(checksums),
+            ..Default::default()
+        };
+
+        properties::apply_metadata_properties(&mut metadata, &properties);
+        metadata.properties = Some(properties::properties_of(&properties));
+
+        Ok(metadata)
+    }
+
+    async fn resolve_path(&self, path: &str) -> Result<ObjectId, CrossroadsError> {
+        let key = path.trim_matches('/');
+
+        if key.is_empty() {
+            return Ok(ObjectId::root())
+        }
+
+        let bucket = self.bucket()?;
+        let (_, status) = bucket.head_object(key)?;
+
+        if (200..300).contains(&status) {
+            return Ok(ObjectId::plain_text("/".to_string() + key))
+        }
+
+        if key_exists(&bucket, key)? {
+            Ok(ObjectId::directory("/".to_string() + key))
+        } else {
+            Err(CrossroadsError::NotFound(path.to_string()))
+        }
+    }
+
+    async fn path_of(&self, object_id: ObjectId) -> Result<String, CrossroadsError> {
+        Ok(object_id.as_str().trim_matches('/').to_string())
+    }
+
+    /// Names are lowercased, as S3 stores them. Those of the properties holding the metadata
+    /// set by `set_metadata` are rejected.
+    async fn set_properties(&self, object_id: ObjectId, properties: BTreeMap<String, String>) -> Result<(), CrossroadsError> {
+        properties.keys().try_for_each(|name| properties::check_user_property(name))?;
+
+        self.update_properties(&object_id, |current| {
+            current.extend(properties.into_iter().map(|(name, value)| (name.to_lowercase(), value)))
+        }).await
+    }
+
+    async fn remove_properties(&self, object_id: ObjectId, names: &[String]) -> Result<(), CrossroadsError> {
+        names.iter().try_for_each(|name| properties::check_user_property(name))?;
+
+        self.update_properties(&object_id, |current| {
+            for name in names {
+                current.remove(&name.to_lowercase());
+            }
+        }).await
+    }
+
+    async fn set_modified_at(&self, object_id: ObjectId, modified_at: chrono::DateTime<chrono::Utc>) -> Result<(), CrossroadsError> {
+        self.set_metadata(object_id, MetadataUpdate { modified_at: Some(modified_at), ..Default::default() }).await
+    }
+
+    /// The object is copied over itself with the fields kept in its properties, see
+    /// `update_properties`, as S3 sets the time of the last write itself and has no owner or
+    /// mode of its own. Listings still report the time of the last write.
+    async fn set_metadata(&self, object_id: ObjectId, update: MetadataUpdate) -> Result<(), CrossroadsError> {
+        update.check_supported("S3", &[MetadataField::ModifiedAt, MetadataField::AccessedAt, MetadataField::Permissions, MetadataField::Owner])?;
+
+        let fields = properties::metadata_properties(&update)?;
+
+        if fields.is_empty() {
+            return Ok(())
+        }
+
+        self.update_properties(&object_id, |current| current.extend(fields)).await
+    }
+
+    async fn read_link(&self, _object_id: ObjectId) -> Result<ObjectId, CrossroadsError> {
+        Err(CrossroadsError::unsupported("S3 read_link"))
+    }
+
+    async fn create_link(&self, _parent_id: ObjectId, _name: &str, _link_id: ObjectId) -> Result<ObjectId, CrossroadsError> {
+        Err(CrossroadsError::unsupported("S3 create_link"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::providers::s3::*;
+    use crate::interfaces::filesystem::FileSystem;
+
+    #[tokio::test]
+    async fn s3_request_works() {
+        let x = S3 {
+            credentials: S3Credentials {
+                access_key: String::from("admin"),
+                secret_key: String::from("password"),
+                region: String::from(""),
+                endpoint: String::from("http://localhost:9000")
+            },
+            bucket: String::from("test"),
+            size_limit: None,
+        };
+        let result = x.read_file(ObjectId::new(String::from("hello-world.txt"), FileType::File)).await;
+        assert!(result.is_ok());
+        assert_eq!(String::from_utf8(result.unwrap().to_vec()).unwrap(), String::from("hello world!"));
+    }
+
+    #[tokio::test]
+    async fn s3_list_folder_content() {
+        let x = S3 {
+            credentials: S3Credentials {
+                access_key: String::from("admin"),
+                secret_key: String::from("password"),
+                region: String::from(""),
+                endpoint: String::from("http://localhost:9000")
+            },
+            bucket: String::from("test"),
+            size_limit: None,
+        };
+
+        let result = x.read_directory(ObjectId::new(String::from("/"), FileType::Directory)).await;
+
+        assert!(result.is_ok());
+
+        assert!(result.as_ref().unwrap().len() == 2);
+
+        assert_eq!("hello-world.txt", result.as_ref().unwrap()[0].name);
+        assert_eq!("level1", result.as_ref().unwrap()[1].name);
+    }
+
+    #[tokio::test]
+    async fn s3_list_folder_content_one_level_deep() {
+        let x = S3 {
+            credentials: S3Credentials {
+                access_key: String::from("admin"),
+                secret_key: String::from("password"),
+                region: String::from(""),
+                endpoint: String::from("http://localhost:9000")
+            },
+            bucket: String::from("test"),
+            size_limit: None,
+        };
+
+        let result = x.read_directory(ObjectId::new(String::from("/level1/"), FileType::Directory)).await;
+
+        assert!(result.is_ok());
+
+        assert!(result.as_ref().unwrap().len() == 2);
+
+        assert_eq!("level1_file.txt", result.as_ref().unwrap()[0].name);
+        assert_eq!("level2", result.as_ref().unwrap()[1].name);
+    }
+
+    #[test]
+    fn renamed_id_keeps_the_form_of_the_id() {
+        assert_eq!(renamed_id(&ObjectId::plain_text("/test.txt".to_string()), "/renamed.txt").as_str(), "/renamed.txt");
+        assert_eq!(renamed_id(&ObjectId::plain_text("/folder/test.txt".to_string()), "renamed.txt").as_str(), "/folder/renamed.txt");
+        assert_eq!(renamed_id(&ObjectId::plain_text("folder/test.txt".to_string()), "renamed.txt").as_str(), "folder/renamed.txt");
+        assert_eq!(renamed_id(&ObjectId::plain_text("test.txt".to_string()), "renamed.txt").as_str(), "renamed.txt");
+    }
+
+    #[tokio::test]
+    async fn s3_rename_file() {
+        let x = S3 {
+            credentials: S3Credentials {
+                access_key: String::from("admin"),
+                secret_key: String::from("password"),
+                region: String::from(""),
+                endpoint: String::from("http://localhost:9000")
+            },
+            bucket: String::from("test"),
+            size_limit: None,
+        };
+
+        let original_id = ObjectId::new(String::from("/test.txt"), FileType::File);
+
+        let result = x.rename(original_id, String::from("/test_renamed.txt")).await;
+
+        assert!(result.is_ok());
+
+        let new_id = ObjectId::new(String::from("/test_renamed.txt"), FileType::File);
+
+        let file = x.read_file(new_id.clone()).await;
+
+        assert!(file.is_ok());
+
+        let file_unwrapped = file.unwrap();
+
+        let content = std::str::from_utf8(&file_unwrapped);
+        assert!(content.is_ok());
+        dbg!(content.unwrap());
+        assert!(content.unwrap() == "Lorem ipsum\n");
+
+        let reverse_rename = x.rename(new_id, String::from("/test.txt")).await;
+
+        assert!(reverse_rename.is_ok());
+    }
+
+    #[tokio::test]
+    async fn s3_write_file_stream_content_type() {
+        let x = S3 {
+            credentials: S3Credentials {
+                access_key: String::from("admin"),
+                secret_key: String::from("password"),
+                region: String::from(""),
+                endpoint: String::from("http://localhost:9000")
+            },
+            bucket: String::from("test"),
+            size_limit: None,
+        };
+
+        let object_id = ObjectId::new(String::from("/image.png"), FileType::File);
+        let content: ByteStream = Box::pin(futures::stream::once(async { Ok(Bytes::from_static(b"\x89PNG\r\n\x1a\n")) }));
+
+        x.write_file_stream(object_id.clone(), content, None).await.unwrap();
+
+        // `get_metadata` would guess it from the name, so the stored type is read instead.
+        let (head, _) = x.bucket().unwrap().head_object("image.png").unwrap();
+        assert_eq!(head.content_type.as_deref(), Some("image/png"));
+
+        x.delete(object_id).await.unwrap();
+    }
+}
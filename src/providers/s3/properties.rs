@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{Metadata, MetadataUpdate, ObjectId, Permissions, User, UserId};
+
+use super::signing::{encode_path, sign};
+use super::{S3, MAX_COPY_OBJECT_SIZE};
+
+/// Prefix of the headers carrying the user metadata of an object.
+const PROPERTY_HEADER_PREFIX: &str = "x-amz-meta-";
+/// Headers of the object that would be lost when its metadata is replaced.
+const KEPT_HEADERS: [&str; 6] = ["cache-control", "content-disposition", "content-encoding", "content-language", "content-type", "expires"];
+
+/// Properties s3fs keeps file system metadata in, which S3 has no place for, so that mounted
+/// buckets show what `set_metadata` sets. Times are in seconds since the epoch, and the mode is
+/// a decimal `st_mode` along with the type of the file.
+const MODIFIED_AT_PROPERTY: &str = "mtime";
+const ACCESSED_AT_PROPERTY: &str = "atime";
+const MODE_PROPERTY: &str = "mode";
+const UID_PROPERTY: &str = "uid";
+const GID_PROPERTY: &str = "gid";
+/// Properties holding metadata, which are left out of those of the user.
+const METADATA_PROPERTIES: [&str; 5] = [MODIFIED_AT_PROPERTY, ACCESSED_AT_PROPERTY, MODE_PROPERTY, UID_PROPERTY, GID_PROPERTY];
+/// Type bits of `st_mode` for regular files, added to modes given without a type.
+const REGULAR_FILE_MODE: u32 = 0o100000;
+const FILE_TYPE_MASK: u32 = 0o170000;
+
+/// Properties holding the fields of `update`.
+pub(super) fn metadata_properties(update: &MetadataUpdate) -> Result<BTreeMap<String, String>, CrossroadsError> {
+    let mut properties = BTreeMap::new();
+
+    if let Some(modified_at) = update.modified_at {
+        properties.insert(MODIFIED_AT_PROPERTY.to_string(), modified_at.timestamp().to_string());
+    }
+
+    if let Some(accessed_at) = update.accessed_at {
+        properties.insert(ACCESSED_AT_PROPERTY.to_string(), accessed_at.timestamp().to_string());
+    }
+
+    if let Some(Permissions::Unix(mode)) = update.permissions {
+        let mode = if mode & FILE_TYPE_MASK == 0 { mode | REGULAR_FILE_MODE } else { mode };
+        properties.insert(MODE_PROPERTY.to_string(), mode.to_string());
+    }
+
+    match &update.owner {
+        Some(UserId::UserAndGroup(uid, gid)) => {
+            properties.insert(UID_PROPERTY.to_string(), uid.to_string());
+            properties.insert(GID_PROPERTY.to_string(), gid.to_string());
+        },
+        Some(owner) => return Err(CrossroadsError::InvalidInput(format!("S3 owners are a uid and gid, not {:?}", owner))),
+        None => {},
+    }
+
+    Ok(properties)
+}
+
+/// Fills `metadata` with the fields kept in its properties, over those of the object itself.
+pub(super) fn apply_metadata_properties(metadata: &mut Metadata, properties: &BTreeMap<String, String>) {
+    let number = |name: &str| properties.get(name).and_then(|value| value.parse::<i64>().ok());
+    let time = |name: &str| number(name).and_then(|seconds| DateTime::<Utc>::from_timestamp(seconds, 0));
+
+    if let Some(modified_at) = time(MODIFIED_AT_PROPERTY) {
+        metadata.modified_at = Some(modified_at);
+    }
+
+    metadata.accessed_at = time(ACCESSED_AT_PROPERTY).or(metadata.accessed_at);
+
+    if let Some(mode) = number(MODE_PROPERTY).and_then(|mode| u32::try_from(mode).ok()) {
+        metadata.permissions = Some(Permissions::Unix(mode));
+    }
+
+    let id = |name: &str| number(name).and_then(|id| u32::try_from(id).ok());
+
+    if let (Some(uid), Some(gid)) = (id(UID_PROPERTY), id(GID_PROPERTY)) {
+        metadata.owner = Some(User { id: UserId::UserAndGroup(uid, gid), name: None });
+    }
+}
+
+/// User metadata among the response headers of an object, including the properties holding
+/// metadata.
+pub(super) fn stored_properties<'a>(headers: impl Iterator<Item = (&'a str, &'a str)>) -> BTreeMap<String, String> {
+    headers
+        .filter_map(|(name, value)| Some((name.to_lowercase().strip_prefix(PROPERTY_HEADER_PREFIX)?.to_string(), value.to_string())))
+        .collect()
+}
+
+/// Properties of the user among `stored`, see `stored_properties`.
+pub(super) fn properties_of(stored: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+    stored.iter()
+        .filter(|(name, _)| !METADATA_PROPERTIES.contains(&name.as_str()))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+/// Fails for the properties holding metadata, which only `set_metadata` changes.
+pub(super) fn check_user_property(name: &str) -> Result<(), CrossroadsError> {
+    if METADATA_PROPERTIES.contains(&name.to_lowercase().as_str()) {
+        return Err(CrossroadsError::InvalidInput(format!("S3 property {} holds metadata, set through set_metadata", name)))
+    }
+
+    Ok(())
+}
+
+/// Metadata names are header names, which S3 lowercases, and values are sent as they are.
+fn check_property(name: &str, value: &str) -> Result<(), CrossroadsError> {
+    if name.is_empty() || !name.chars().all(|character| character.is_ascii_alphanumeric() || character == '-' || character == '_') {
+        return Err(CrossroadsError::InvalidInput(format!("Invalid S3 metadata name: {}", name)))
+    }
+
+    if !value.chars().all(|character| character == ' ' || character.is_ascii_graphic()) {
+        return Err(CrossroadsError::InvalidInput(format!("Invalid S3 metadata value for {}: {}", name, value)))
+    }
+
+    Ok(())
+}
+
+impl S3 {
+    /// Metadata of an object is only set along with its content, so the object is copied over
+    /// itself with `update` applied to its properties. This takes objects up to 5 GB, and makes
+    /// a new version on versioned buckets.
+    pub(super) async fn update_properties(&self, object_id: &ObjectId, update: impl FnOnce(&mut BTreeMap<String, String>)) -> Result<(), CrossroadsError> {
+        let bucket = self.bucket()?;
+        let client = reqwest::Client::new();
+
+        let head = sign(&bucket, "HEAD", object_id.as_str(), &[], &[], &[])?
+            .build(&client, reqwest::Method::HEAD)
+            .send().await?;
+
+        let status = head.status().as_u16();
+
+        if !(200..300).contains(&status) {
+            return Err(CrossroadsError::from_status(status, object_id.to_string()))
+        }
+
+        let headers: Vec<(&str, &str)> = head.headers().iter()
+            .filter_map(|(name, value)| Some((name.as_str(), value.to_str().ok()?)))
+            .collect();
+
+        let size: u64 = headers.iter().find(|(name, _)| *name == "content-length").and_then(|(_, size)| size.parse().ok()).unwrap_or(0);
+
+        if size > MAX_COPY_OBJECT_SIZE {
+            return Err(CrossroadsError::unsupported("S3 properties of objects over 5 GB"))
+        }
+
+        let mut properties = stored_properties(headers.iter().copied());
+        update(&mut properties);
+
+        let mut request_headers = vec![
+            ("x-amz-copy-source".to_string(), format!("/{}{}", bucket.name(), encode_path(object_id.as_str()))),
+            ("x-amz-metadata-directive".to_string(), "REPLACE".to_string()),
+        ];
+
+        request_headers.extend(headers.iter()
+            .filter(|(name, _)| KEPT_HEADERS.contains(name))
+            .map(|(name, value)| (name.to_string(), value.to_string())));
+
+        for (name, value) in properties {
+            check_property(&name, &value)?;
+            request_headers.push((format!("{}{}", PROPERTY_HEADER_PREFIX, name), value));
+        }
+
+        let request_headers: Vec<(&str, String)> = request_headers.iter().map(|(name, value)| (name.as_str(), value.clone())).collect();
+        let request = sign(&bucket, "PUT", object_id.as_str(), &[], &request_headers, &[])?;
+        let response = request.build(&client, reqwest::Method::PUT).send().await?;
+
+        let status = response.status().as_u16();
+        let text = response.text().await?;
+
+        // Copies can fail after the response started, with an error in a successful response.
+        if !(200..300).contains(&status) || text.contains("<Error>") {
+            return Err(CrossroadsError::from_status(status, text))
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::interfaces::filesystem::{Metadata, MetadataUpdate, Permissions, UserId};
+
+    use super::{apply_metadata_properties, check_property, check_user_property, metadata_properties, properties_of, stored_properties};
+
+    #[test]
+    fn properties_of_reads_user_metadata_headers() {
+        let headers = [("content-type", "text/plain"), ("x-amz-meta-project", "apollo"), ("X-Amz-Meta-Owner", "jane"), ("x-amz-meta-mtime", "1654084800")];
+        let stored = stored_properties(headers.into_iter());
+        let properties = properties_of(&stored);
+
+        assert_eq!(stored.len(), 3);
+        assert_eq!(properties.len(), 2);
+        assert_eq!(properties["project"], "apollo");
+        assert_eq!(properties["owner"], "jane");
+        assert!(check_property("project", "apollo 11").is_ok());
+        assert!(check_property("project name", "apollo").is_err());
+        assert!(check_property("project", "café").is_err());
+        assert!(check_user_property("project").is_ok());
+        assert!(check_user_property("MTime").is_err());
+    }
+
+    #[test]
+    fn metadata_is_kept_in_s3fs_properties() {
+        let modified_at = Utc.with_ymd_and_hms(2022, 6, 1, 12, 0, 0).unwrap();
+        let update = MetadataUpdate {
+            modified_at: Some(modified_at),
+            permissions: Some(Permissions::Unix(0o640)),
+            owner: Some(UserId::UserAndGroup(1000, 100)),
+            ..Default::default()
+        };
+
+        let properties = metadata_properties(&update).unwrap();
+        assert_eq!(properties["mtime"], "1654084800");
+        assert_eq!(properties["mode"], "33184");
+        assert_eq!(properties["uid"], "1000");
+
+        let mut metadata = Metadata::default();
+        apply_metadata_properties(&mut metadata, &properties);
+        assert_eq!(metadata.modified_at, Some(modified_at));
+        assert_eq!(metadata.permissions, Some(Permissions::Unix(0o100640)));
+        assert_eq!(metadata.owner.unwrap().id, UserId::UserAndGroup(1000, 100));
+        assert_eq!(metadata.accessed_at, None);
+
+        let update = MetadataUpdate { owner: Some(UserId::UniqueId("jane".to_string())), ..Default::default() };
+        assert!(metadata_properties(&update).is_err());
+    }
+}
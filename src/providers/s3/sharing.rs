@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::ObjectId;
+use crate::interfaces::sharing::{Grantee, Share, ShareRequest, ShareRole, Sharing};
+
+use super::S3;
+
+/// Longest validity of a presigned URL signed with AWS Signature Version 4.
+const MAX_PRESIGN_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// Shares are presigned GET URLs, which S3 does not record: they can be neither listed nor
+/// revoked, and expire at the latest after a week.
+#[async_trait]
+impl Sharing for S3 {
+    async fn share(&self, object_id: ObjectId, request: ShareRequest) -> Result<Share, CrossroadsError> {
+        if request.role != ShareRole::Read || request.grantee != Grantee::Anyone {
+            return Err(CrossroadsError::unsupported("S3 shares other than read links for anyone"))
+        }
+
+        let now = Utc::now();
+        let expires_at = request.expires_at
+            .ok_or_else(|| CrossroadsError::InvalidInput("S3 links need an expiry".to_string()))?;
+        let seconds = (expires_at - now).num_seconds();
+
+        if !(1..=MAX_PRESIGN_SECONDS).contains(&seconds) {
+            return Err(CrossroadsError::InvalidInput(format!("S3 links expire within {} seconds", MAX_PRESIGN_SECONDS)))
+        }
+
+        let url = self.bucket()?.presign_get(object_id.as_str().trim_start_matches('/'), seconds as u32, None)?;
+
+        Ok(Share { id: None, url: Some(url), role: ShareRole::Read, grantee: Grantee::Anyone, expires_at: Some(expires_at) })
+    }
+
+    async fn list_shares(&self, _object_id: ObjectId) -> Result<Vec<Share>, CrossroadsError> {
+        Err(CrossroadsError::unsupported("S3 listing of shares"))
+    }
+
+    async fn revoke_share(&self, _object_id: ObjectId, _share_id: &str) -> Result<(), CrossroadsError> {
+        Err(CrossroadsError::unsupported("S3 revoking of shares"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use crate::error::CrossroadsError;
+    use crate::interfaces::filesystem::ObjectId;
+    use crate::interfaces::sharing::{Grantee, ShareRequest, ShareRole, Sharing};
+    use crate::providers::s3::{S3, S3Credentials};
+
+    #[tokio::test]
+    async fn s3_share_presigns_links() {
+        let s3 = S3::new("bucket".to_string(), S3Credentials {
+            region: "us-east-1".to_string(),
+            endpoint: "http://localhost:9000".to_string(),
+            access_key: "access".to_string(),
+            secret_key: "secret".to_string(),
+        });
+
+        let report = ObjectId::plain_text("/reports/report.pdf".to_string());
+        let request = ShareRequest { role: ShareRole::Read, grantee: Grantee::Anyone, expires_at: Some(Utc::now() + Duration::hours(1)) };
+
+        let share = s3.share(report.clone(), request.clone()).await.unwrap();
+        let url = share.url.unwrap();
+        assert!(url.starts_with("http://localhost:9000/bucket/reports/report.pdf?"));
+        assert!(url.contains("X-Amz-Expires=3"));
+
+        let result = s3.share(report.clone(), ShareRequest { role: ShareRole::Write, ..request.clone() }).await;
+        assert!(matches!(result, Err(CrossroadsError::Unsupported(_))));
+
+        let result = s3.share(report, ShareRequest { expires_at: Some(Utc::now() + Duration::days(8)), ..request }).await;
+        assert!(matches!(result, Err(CrossroadsError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,110 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use s3::bucket::Bucket;
+use sha2::{Digest, Sha256};
+
+use crate::error::CrossroadsError;
+
+/// Request to the bucket signed with AWS Signature Version 4, for the requests the bucket
+/// does not expose.
+pub(super) struct SignedRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+}
+
+/// Signs a request to `path` in the bucket, `/` being the bucket itself. `query` and `path`
+/// are encoded here, and `headers` are given with lowercase names.
+pub(super) fn sign(bucket: &Bucket, method: &str, path: &str, query: &[(&str, &str)], headers: &[(&str, String)], payload: &[u8]) -> Result<SignedRequest, CrossroadsError> {
+    let now = Utc::now();
+    let date_time = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let content_sha256 = hex::encode(Sha256::digest(payload));
+
+    let url = bucket.url();
+    let host = bucket.host();
+    // Path of the bucket, the part of the url that follows the host.
+    let bucket_path = url.split_once(&host).map_or("", |(_, path)| path).to_string();
+    let path = encode_path(path);
+
+    let mut query: Vec<String> = query.iter().map(|(name, value)| format!("{}={}", uri_encode(name), uri_encode(value))).collect();
+    query.sort();
+    let query = query.join("&");
+
+    let mut headers: Vec<(String, String)> = headers.iter().map(|(name, value)| (name.to_string(), value.clone())).collect();
+    headers.push(("host".to_string(), host.clone()));
+    headers.push(("x-amz-content-sha256".to_string(), content_sha256.clone()));
+    headers.push(("x-amz-date".to_string(), date_time.clone()));
+    headers.sort();
+
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value.trim())).collect();
+    let signed_headers = headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+
+    let canonical_request = format!(
+        "{}\n{}{}\n{}\n{}\n{}\n{}",
+        method, bucket_path, path, query, canonical_headers, signed_headers, content_sha256,
+    );
+
+    let region = bucket.region().to_string();
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", date_time, scope, hex::encode(Sha256::digest(canonical_request.as_bytes())));
+
+    let secret_key = bucket.secret_key().ok_or(CrossroadsError::AuthenticationRequired)?;
+    let access_key = bucket.access_key().ok_or(CrossroadsError::AuthenticationRequired)?;
+
+    let mut signing_key = hmac(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    for part in [region.as_str(), "s3", "aws4_request"] {
+        signing_key = hmac(&signing_key, part.as_bytes());
+    }
+    let signature = hex::encode(hmac(&signing_key, string_to_sign.as_bytes()));
+
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{},SignedHeaders={},Signature={}",
+        access_key, scope, signed_headers, signature,
+    );
+
+    // The client sets the host itself.
+    headers.retain(|(name, _)| name != "host");
+    headers.push(("authorization".to_string(), authorization));
+
+    let separator = if query.is_empty() { "" } else { "?" };
+
+    Ok(SignedRequest { url: format!("{}{}{}{}", url, path, separator, query), headers })
+}
+
+impl SignedRequest {
+    pub(super) fn build(self, client: &reqwest::Client, method: reqwest::Method) -> reqwest::RequestBuilder {
+        self.headers.into_iter().fold(client.request(method, self.url), |request, (name, value)| request.header(name, value))
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Encodes each segment of `path`, keeping the `/` between them.
+pub(super) fn encode_path(path: &str) -> String {
+    path.split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+}
+
+/// Percent-encodes everything but the unreserved characters, as the signature expects.
+pub(super) fn uri_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::uri_encode;
+
+    #[test]
+    fn uri_encode_keeps_unreserved_characters() {
+        assert_eq!(uri_encode("Reports 2022/a+b~.txt"), "Reports%202022%2Fa%2Bb~.txt");
+    }
+}
@@ -0,0 +1,167 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use regex::Regex;
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{ByteStream, ObjectId, User, UserId};
+use crate::interfaces::versions::{Revision, Versions};
+
+use super::delete_objects::unescape;
+use super::signing::{encode_path, sign, uri_encode};
+use super::S3;
+
+/// Versions of the keys starting with the listed prefix, and where the next page starts.
+struct VersionsPage {
+    versions: Vec<(String, Revision)>,
+    next: Option<(String, String)>,
+}
+
+fn parse_versions(response: &str) -> VersionsPage {
+    let version = Regex::new(r"(?s)<Version>(.*?)</Version>").unwrap();
+    let field = |content: &str, name: &str| {
+        Regex::new(&format!(r"(?s)<{}>(.*?)</{}>", name, name)).unwrap()
+            .captures(content)
+            .map(|captures| unescape(&captures[1]))
+    };
+
+    let versions = version.captures_iter(response)
+        .filter_map(|captures| {
+            let content = &captures[1];
+            let key = field(content, "Key")?;
+
+            let author = field(content, "ID").map(|id| User {
+                id: UserId::UniqueId(id),
+                name: field(content, "DisplayName"),
+            });
+
+            let revision = Revision {
+                id: field(content, "VersionId")?,
+                modified_at: field(content, "LastModified").and_then(|date| date.parse::<DateTime<Utc>>().ok()),
+                size: field(content, "Size").and_then(|size| size.parse().ok()),
+                author,
+                is_current: field(content, "IsLatest").as_deref() == Some("true"),
+            };
+
+            Some((key, revision))
+        })
+        .collect();
+
+    let next = match (field(response, "IsTruncated").as_deref(), field(response, "NextKeyMarker"), field(response, "NextVersionIdMarker")) {
+        (Some("true"), Some(key), Some(version_id)) => Some((key, version_id)),
+        _ => None,
+    };
+
+    VersionsPage { versions, next }
+}
+
+/// Needs versioning to be enabled on the bucket, otherwise every object has the single
+/// version `null`. The bucket does not expose versions, so the requests are signed here.
+#[async_trait]
+impl Versions for S3 {
+    /// Delete markers are left out, as they have no content.
+    async fn list_revisions(&self, object_id: ObjectId) -> Result<Vec<Revision>, CrossroadsError> {
+        let bucket = self.bucket()?;
+        let key = object_id.as_str().trim_start_matches('/').to_string();
+        let client = reqwest::Client::new();
+
+        let mut revisions = vec![];
+        let mut marker: Option<(String, String)> = None;
+
+        loop {
+            let mut query = vec![("versions", ""), ("prefix", key.as_str())];
+
+            if let Some((key_marker, version_id_marker)) = &marker {
+                query.push(("key-marker", key_marker));
+                query.push(("version-id-marker", version_id_marker));
+            }
+
+            let request = sign(&bucket, "GET", "/", &query, &[], &[])?;
+            let response = request.build(&client, reqwest::Method::GET).send().await?;
+
+            let status = response.status().as_u16();
+            let text = response.text().await?;
+
+            if !(200..300).contains(&status) {
+                return Err(CrossroadsError::from_status(status, text))
+            }
+
+            let page = parse_versions(&text);
+
+            // Versions come by key, then newest first.
+            revisions.extend(page.versions.into_iter().filter(|(version_key, _)| *version_key == key).map(|(_, revision)| revision));
+
+            match page.next {
+                Some(next) if next.0 == key => marker = Some(next),
+                _ => break,
+            }
+        }
+
+        if revisions.is_empty() {
+            return Err(CrossroadsError::NotFound(object_id.to_string()))
+        }
+
+        Ok(revisions)
+    }
+
+    async fn read_revision(&self, object_id: ObjectId, revision_id: &str) -> Result<ByteStream, CrossroadsError> {
+        let bucket = self.bucket()?;
+        let request = sign(&bucket, "GET", object_id.as_str(), &[("versionId", revision_id)], &[], &[])?;
+        let response = request.build(&reqwest::Client::new(), reqwest::Method::GET).send().await?;
+
+        let status = response.status().as_u16();
+
+        if !(200..300).contains(&status) {
+            return Err(CrossroadsError::from_status(status, response.text().await.unwrap_or_default()))
+        }
+
+        Ok(Box::pin(response.bytes_stream().map_err(CrossroadsError::from)))
+    }
+
+    /// Copies the revision over the object, which makes it the newest version. `CopyObject`
+    /// only takes revisions up to 5 GB.
+    async fn restore_revision(&self, object_id: ObjectId, revision_id: &str) -> Result<(), CrossroadsError> {
+        let bucket = self.bucket()?;
+        let source = format!("/{}{}?versionId={}", bucket.name(), encode_path(object_id.as_str()), uri_encode(revision_id));
+
+        let request = sign(&bucket, "PUT", object_id.as_str(), &[], &[("x-amz-copy-source", source)], &[])?;
+        let response = request.build(&reqwest::Client::new(), reqwest::Method::PUT).send().await?;
+
+        let status = response.status().as_u16();
+        let text = response.text().await?;
+
+        // Copies can fail after the response started, with an error in a successful response.
+        if !(200..300).contains(&status) || text.contains("<Error>") {
+            return Err(CrossroadsError::from_status(status, text))
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_versions;
+
+    #[test]
+    fn parse_versions_reads_revisions_and_markers() {
+        let response = "<ListVersionsResult>\
+            <IsTruncated>true</IsTruncated>\
+            <NextKeyMarker>report.pdf</NextKeyMarker><NextVersionIdMarker>v1</NextVersionIdMarker>\
+            <Version><Key>report.pdf</Key><VersionId>v2</VersionId><IsLatest>true</IsLatest>\
+            <LastModified>2022-06-01T10:00:00.000Z</LastModified><Size>12</Size>\
+            <Owner><ID>owner</ID><DisplayName>Jane</DisplayName></Owner></Version>\
+            <DeleteMarker><Key>report.pdf</Key><VersionId>v0</VersionId></DeleteMarker>\
+            <Version><Key>report.pdf</Key><VersionId>v1</VersionId><IsLatest>false</IsLatest><Size>8</Size></Version>\
+            </ListVersionsResult>";
+
+        let page = parse_versions(response);
+        assert_eq!(page.versions.len(), 2);
+        assert_eq!(page.versions[0].1.id, "v2");
+        assert!(page.versions[0].1.is_current);
+        assert_eq!(page.versions[0].1.size, Some(12));
+        assert_eq!(page.versions[0].1.author.as_ref().and_then(|author| author.name.as_deref()), Some("Jane"));
+        assert!(!page.versions[1].1.is_current);
+        assert_eq!(page.next, Some(("report.pdf".to_string(), "v1".to_string())));
+    }
+}
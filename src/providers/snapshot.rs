@@ -0,0 +1,233 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use serde::{Serialize, Deserialize};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{File, FileSystem, FileType, ObjectId};
+use crate::interfaces::watch::{ChangeBatch, ChangeEvent, ChangeKind, WatchCursor};
+use crate::walker::{self, WalkOptions};
+
+/// State of every entry below a directory, for providers without a change feed, whose
+/// changes are found by comparing two listings. It is the cursor of their `Watch`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub(crate) struct Snapshot {
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+struct Entry {
+    file_type: FileType,
+    version: Option<String>,
+    size: Option<u64>,
+    modified_at: Option<DateTime<Utc>>,
+}
+
+impl Entry {
+    fn of(file: &File) -> Entry {
+        let metadata = file.metadata.as_ref();
+
+        Entry {
+            file_type: file.id.file_type(),
+            version: metadata.and_then(|metadata| metadata.version.clone()),
+            size: metadata.and_then(|metadata| metadata.size),
+            modified_at: metadata.and_then(|metadata| metadata.modified_at),
+        }
+    }
+}
+
+/// Walks everything below `root`. Failing to list any directory fails the whole walk, as
+/// its content would otherwise look deleted.
+pub(crate) async fn walk(filesystem: &dyn FileSystem, root: ObjectId) -> Result<Vec<File>, CrossroadsError> {
+    walker::walk(filesystem, root, WalkOptions::default())?
+        .map_ok(|entry| entry.file)
+        .try_collect()
+        .await
+}
+
+impl Snapshot {
+    pub(crate) fn of(files: &[File]) -> Snapshot {
+        Snapshot {
+            entries: files.iter().map(|file| (file.id.as_str().to_string(), Entry::of(file))).collect(),
+        }
+    }
+
+    pub(crate) fn directories(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter()
+            .filter(|(_, entry)| entry.file_type == FileType::Directory)
+            .map(|(id, _)| id.as_str())
+    }
+
+    pub(crate) fn to_cursor(&self) -> Result<WatchCursor, CrossroadsError> {
+        serde_json::to_string(self)
+            .map(WatchCursor)
+            .map_err(|error| CrossroadsError::Provider(Box::new(error)))
+    }
+
+    pub(crate) fn from_cursor(cursor: &WatchCursor) -> Result<Snapshot, CrossroadsError> {
+        serde_json::from_str(&cursor.0).map_err(|error| CrossroadsError::InvalidInput(format!("Invalid watch cursor: {}", error)))
+    }
+
+    /// Changes from `self` to the walked `files`. An entry that disappeared while a single one
+    /// with the same version appeared is reported renamed, which holds for the entries below a
+    /// renamed directory too.
+    pub(crate) fn diff(&self, files: Vec<File>) -> Result<ChangeBatch, CrossroadsError> {
+        let (events, next) = self.changes(files);
+
+        Ok(ChangeBatch { events, cursor: next.to_cursor()? })
+    }
+
+    /// Changes to the `listed` directories and their entries, which are now `files`, and to
+    /// the entries below those of their directories that are gone. Other entries are left alone.
+    pub(crate) fn update(&mut self, listed: &BTreeSet<String>, files: Vec<File>) -> Vec<ChangeEvent> {
+        let directories: BTreeSet<&str> = files.iter()
+            .filter(|file| file.id.file_type() == FileType::Directory)
+            .map(|file| file.id.as_str())
+            .collect();
+
+        let gone: Vec<String> = self.directories()
+            .filter(|id| listed.contains(parent_of(id)) && !directories.contains(id))
+            .map(|id| id.to_string() + "/")
+            .collect();
+
+        let within = |id: &str| listed.contains(id) || listed.contains(parent_of(id)) || gone.iter().any(|prefix| id.starts_with(prefix.as_str()));
+
+        let previous = Snapshot {
+            entries: self.entries.iter().filter(|(id, _)| within(id)).map(|(id, entry)| (id.clone(), entry.clone())).collect(),
+        };
+
+        let (events, next) = previous.changes(files);
+        self.entries.retain(|id, _| !within(id));
+        self.entries.extend(next.entries);
+
+        events
+    }
+
+    fn changes(&self, files: Vec<File>) -> (Vec<ChangeEvent>, Snapshot) {
+        let next = Snapshot::of(&files);
+        let mut files: BTreeMap<String, File> = files.into_iter().map(|file| (file.id.as_str().to_string(), file)).collect();
+        let mut created: Vec<String> = next.entries.keys().filter(|id| !self.entries.contains_key(*id)).cloned().collect();
+        let mut events = vec![];
+
+        for (id, entry) in &self.entries {
+            match next.entries.get(id) {
+                Some(current) if current != entry => events.push(ChangeEvent {
+                    kind: ChangeKind::Modified,
+                    object_id: ObjectId::new(id.clone(), current.file_type.clone()),
+                    file: files.get(id).cloned(),
+                    previous_id: None,
+                }),
+                Some(_) => {}
+                None => {
+                    let previous_id = ObjectId::new(id.clone(), entry.file_type.clone());
+
+                    let mut same_version = created.iter().enumerate().filter(|(_, created_id)| {
+                        let candidate = &next.entries[*created_id];
+                        entry.version.is_some() && candidate.version == entry.version && candidate.file_type == entry.file_type
+                    });
+
+                    let renamed = match (same_version.next(), same_version.next()) {
+                        (Some((index, _)), None) => Some(index),
+                        _ => None,
+                    };
+
+                    events.push(match renamed {
+                        Some(index) => {
+                            let new_id = created.remove(index);
+
+                            ChangeEvent {
+                                kind: ChangeKind::Renamed,
+                                object_id: ObjectId::new(new_id.clone(), entry.file_type.clone()),
+                                file: files.remove(&new_id),
+                                previous_id: Some(previous_id),
+                            }
+                        }
+                        None => ChangeEvent { kind: ChangeKind::Deleted, object_id: previous_id, file: None, previous_id: None },
+                    });
+                }
+            }
+        }
+
+        events.extend(created.into_iter().filter_map(|id| files.remove(&id)).map(|file| ChangeEvent {
+            kind: ChangeKind::Created,
+            object_id: file.id.clone(),
+            file: Some(file),
+            previous_id: None,
+        }));
+
+        (events, next)
+    }
+}
+
+/// Id of the directory holding `id`, ids being paths.
+fn parent_of(id: &str) -> &str {
+    id.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::interfaces::filesystem::{File, Metadata, ObjectId};
+    use crate::interfaces::watch::ChangeKind;
+
+    use super::Snapshot;
+
+    fn file(id: &str, version: &str) -> File {
+        File {
+            id: ObjectId::plain_text(id.to_string()),
+            name: id.rsplit('/').next().unwrap().to_string(),
+            metadata: Some(Metadata { version: Some(version.to_string()), ..Default::default() }),
+        }
+    }
+
+    #[test]
+    fn snapshot_diff_reports_every_kind() {
+        let before = Snapshot::of(&[file("kept.txt", "1"), file("edited.txt", "2"), file("old.txt", "3"), file("gone.txt", "4")]);
+        let cursor = before.to_cursor().unwrap();
+        assert_eq!(Snapshot::from_cursor(&cursor).unwrap(), before);
+
+        let batch = before.diff(vec![file("kept.txt", "1"), file("edited.txt", "5"), file("new.txt", "3"), file("added.txt", "6")]).unwrap();
+        let events: Vec<(ChangeKind, &str)> = batch.events.iter().map(|event| (event.kind, event.object_id.as_str())).collect();
+
+        assert_eq!(events, vec![
+            (ChangeKind::Modified, "edited.txt"),
+            (ChangeKind::Deleted, "gone.txt"),
+            (ChangeKind::Renamed, "new.txt"),
+            (ChangeKind::Created, "added.txt"),
+        ]);
+        assert_eq!(batch.events[2].previous_id.as_ref().map(ObjectId::as_str), Some("old.txt"));
+
+        let after = Snapshot::from_cursor(&batch.cursor).unwrap();
+        assert!(after.diff(vec![file("kept.txt", "1"), file("edited.txt", "5"), file("new.txt", "3"), file("added.txt", "6")]).unwrap().events.is_empty());
+    }
+
+    #[test]
+    fn snapshot_update_leaves_unlisted_directories() {
+        let folder = |id: &str, version: &str| File { id: ObjectId::directory(id.to_string()), ..file(id, version) };
+
+        let mut snapshot = Snapshot::of(&[
+            folder("kept", "1"), file("kept/a.txt", "2"),
+            folder("moved", "3"), file("moved/b.txt", "4"), folder("moved/inner", "5"), file("moved/inner/c.txt", "6"),
+            folder("other", "7"),
+        ]);
+
+        // `moved` went into `other` and lost `inner`, `kept` is not listed.
+        let listed = BTreeSet::from(["".to_string(), "other".to_string(), "other/moved".to_string()]);
+        let events = snapshot.update(&listed, vec![folder("kept", "1"), folder("other", "8"), folder("other/moved", "3"), file("other/moved/b.txt", "4")]);
+        let events: Vec<(ChangeKind, &str)> = events.iter().map(|event| (event.kind, event.object_id.as_str())).collect();
+
+        assert_eq!(events, vec![
+            (ChangeKind::Renamed, "other/moved"),
+            (ChangeKind::Renamed, "other/moved/b.txt"),
+            (ChangeKind::Deleted, "moved/inner"),
+            (ChangeKind::Deleted, "moved/inner/c.txt"),
+            (ChangeKind::Modified, "other"),
+        ]);
+        assert_eq!(snapshot, Snapshot::of(&[
+            folder("kept", "1"), file("kept/a.txt", "2"),
+            folder("other", "8"), folder("other/moved", "3"), file("other/moved/b.txt", "4"),
+        ]));
+    }
+}
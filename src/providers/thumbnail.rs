@@ -0,0 +1,182 @@
+use std::fs;
+use std::io::Cursor;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+use tokio::task;
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{FileSystem, ObjectId};
+use crate::interfaces::preview::{Thumbnail, ThumbnailSize};
+
+/// Largest file decoded into a thumbnail, as the whole image is held in memory.
+const MAX_SOURCE_SIZE: u64 = 64 * 1024 * 1024;
+const PNG_MIME_TYPE: &str = "image/png";
+
+/// Thumbnail of an image fetched from a provider, whose dimensions are read from its header.
+pub(crate) fn from_image(content: Vec<u8>, mime_type: String) -> Result<Thumbnail, CrossroadsError> {
+    let (width, height) = image::ImageReader::new(Cursor::new(&content))
+        .with_guessed_format()?
+        .into_dimensions()
+        .map_err(|error| CrossroadsError::Provider(Box::new(error)))?;
+
+    Ok(Thumbnail { content, mime_type, width, height })
+}
+
+/// Renders the image `object_id` for providers without thumbnails of their own. The result is
+/// cached as the freedesktop thumbnail specification describes, under the data directory of
+/// the application rather than the shared one, and regenerated once the original is modified.
+/// `uri` identifies the original in the cache.
+pub(crate) async fn generate(filesystem: &dyn FileSystem, object_id: ObjectId, uri: &str, size: ThumbnailSize) -> Result<Thumbnail, CrossroadsError> {
+    if object_id.is_directory() {
+        return Err(CrossroadsError::unsupported("thumbnails of directories"))
+    }
+
+    let metadata = filesystem.get_metadata(object_id.clone()).await?;
+
+    if metadata.size.is_some_and(|size| size > MAX_SOURCE_SIZE) {
+        return Err(CrossroadsError::Unsupported(format!("thumbnails of files over {} bytes", MAX_SOURCE_SIZE)))
+    }
+
+    let modified_at = metadata.modified_at.map(|modified_at| modified_at.timestamp()).unwrap_or_default();
+    let path = cache_path(uri, size);
+
+    if let Some(thumbnail) = path.as_deref().and_then(|path| cached(path, uri, modified_at)) {
+        return Ok(thumbnail)
+    }
+
+    let content = filesystem.read_file(object_id).await?;
+    let uri = uri.to_string();
+
+    let thumbnail = task::spawn_blocking(move || render(&content, &uri, modified_at, size))
+        .await
+        .map_err(|error| CrossroadsError::Provider(Box::new(error)))??;
+
+    // The thumbnail is still returned when it cannot be cached.
+    if let Some(path) = path {
+        let _ = store(&path, &thumbnail.content);
+    }
+
+    Ok(thumbnail)
+}
+
+fn cache_path(uri: &str, size: ThumbnailSize) -> Option<PathBuf> {
+    let directories = ProjectDirs::from("", "Orbital", "Files")?;
+
+    let size = match size {
+        ThumbnailSize::Normal => "normal",
+        ThumbnailSize::Large => "large",
+        ThumbnailSize::XLarge => "x-large",
+        ThumbnailSize::XXLarge => "xx-large",
+    };
+
+    Some(directories.data_dir().join("thumbnails").join(size).join(format!("{:x}.png", md5::compute(uri))))
+}
+
+/// Cached thumbnail at `path`, if it was made from the current content of `uri`.
+fn cached(path: &Path, uri: &str, modified_at: i64) -> Option<Thumbnail> {
+    let content = fs::read(path).ok()?;
+    let reader = png::Decoder::new(Cursor::new(&content)).read_info().ok()?;
+    let info = reader.info();
+
+    let text = |keyword: &str| info.uncompressed_latin1_text.iter()
+        .find(|chunk| chunk.keyword == keyword)
+        .map(|chunk| chunk.text.clone());
+
+    if text("Thumb::URI").as_deref() != Some(uri) || text("Thumb::MTime") != Some(modified_at.to_string()) {
+        return None
+    }
+
+    let (width, height) = (info.width, info.height);
+
+    Some(Thumbnail { content, mime_type: PNG_MIME_TYPE.to_string(), width, height })
+}
+
+/// Scales the image down to fit `size`, and encodes it as a PNG carrying the attributes the
+/// specification requires to check it against the original.
+fn render(content: &[u8], uri: &str, modified_at: i64, size: ThumbnailSize) -> Result<Thumbnail, CrossroadsError> {
+    let image = image::load_from_memory(content)
+        .map_err(|error| CrossroadsError::Unsupported(format!("thumbnail of {}: {}", uri, error)))?;
+
+    let pixels = size.pixels();
+    let image = if image.width() > pixels || image.height() > pixels { image.thumbnail(pixels, pixels) } else { image };
+    let image = image.into_rgba8();
+    let (width, height) = image.dimensions();
+
+    let encoding_error = |error: png::EncodingError| CrossroadsError::Provider(Box::new(error));
+    let mut content = vec![];
+
+    let mut encoder = png::Encoder::new(&mut content, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.add_text_chunk("Thumb::URI".to_string(), uri.to_string()).map_err(encoding_error)?;
+    encoder.add_text_chunk("Thumb::MTime".to_string(), modified_at.to_string()).map_err(encoding_error)?;
+
+    let mut writer = encoder.write_header().map_err(encoding_error)?;
+    writer.write_image_data(&image).map_err(encoding_error)?;
+    writer.finish().map_err(encoding_error)?;
+
+    Ok(Thumbnail { content, mime_type: PNG_MIME_TYPE.to_string(), width, height })
+}
+
+/// Writes to a temporary file first so that other readers never see a partial thumbnail, with
+/// the permissions of the specification.
+fn store(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+        fs::set_permissions(directory, fs::Permissions::from_mode(0o700))?;
+    }
+
+    let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&temporary, content)?;
+    fs::set_permissions(&temporary, fs::Permissions::from_mode(0o600))?;
+    fs::rename(&temporary, path)
+}
+
+/// Percent-encodes `path` for a `file://` URI, the form the specification names thumbnails after.
+pub(crate) fn file_uri(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let encoded: String = path.as_os_str().as_bytes().iter()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (*byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect();
+
+    format!("file://{}", encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::path::Path;
+
+    use crate::interfaces::preview::ThumbnailSize;
+
+    use super::{cached, file_uri, from_image, render};
+
+    #[test]
+    fn render_scales_down_and_validates_against_the_original() {
+        let mut original = vec![];
+        image::RgbImage::new(300, 150).write_to(&mut Cursor::new(&mut original), image::ImageFormat::Png).unwrap();
+
+        let uri = file_uri(Path::new("/photos/summer 2022.png"));
+        assert_eq!(uri, "file:///photos/summer%202022.png");
+
+        let thumbnail = render(&original, &uri, 1_650_000_000, ThumbnailSize::Normal).unwrap();
+        assert_eq!((thumbnail.width, thumbnail.height), (128, 64));
+        assert_eq!(from_image(thumbnail.content.clone(), thumbnail.mime_type.clone()).unwrap(), thumbnail);
+
+        let path = std::env::temp_dir().join(format!("crossroads-thumbnail-{}.png", std::process::id()));
+        std::fs::write(&path, &thumbnail.content).unwrap();
+        assert_eq!(cached(&path, &uri, 1_650_000_000), Some(thumbnail));
+        assert_eq!(cached(&path, &uri, 1_650_000_001), None);
+        std::fs::remove_file(path).unwrap();
+
+        let small = render(&original, &uri, 0, ThumbnailSize::XLarge).unwrap();
+        assert_eq!((small.width, small.height), (300, 150));
+        assert!(render(b"not an image", &uri, 0, ThumbnailSize::Normal).is_err());
+    }
+}
@@ -1,9 +1,12 @@
+use crate::error::CrossroadsError;
 use crate::interfaces::Provider;
 use crate::interfaces::filesystem::{FileSystem, File, ObjectId, Metadata};
 use crate::providers::onedrive::OneDrive;
 use crate::providers::onedrive::token::OneDriveToken;
 use crate::providers::s3::S3Credentials;
 use crate::providers::{s3::S3, google_drive::GoogleDrive, native_fs::NativeFs};
+use crate::checksum;
+use crate::transfer::{self, TransferMode, TransferOptions};
 use google_drive3::oauth2::storage::TokenInfo;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -13,6 +16,7 @@ use std::str::FromStr;
 use directories::ProjectDirs;
 use std::sync::Arc;
 
+#[allow(dead_code)]
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
 struct ArbitraryData(serde_json::Value);
 
@@ -85,6 +89,45 @@ impl ProvidersMap {
         }
     }
 
+    /// Copies or moves an entry into `destination`, which is a provider and the id of a directory
+    /// on it. Copies and moves within a single provider are left to the provider, which can make
+    /// them on its side; everything else is streamed through `transfer::transfer`.
+    pub async fn transfer(&self, source: (ProviderId, ObjectId), destination: (ProviderId, ObjectId), name: &str, options: TransferOptions) -> Result<ObjectId, CrossroadsError> {
+        let (source_id, object_id) = source;
+        let (destination_id, parent_id) = destination;
+
+        let source_provider = self.providers.get(&source_id)
+            .ok_or_else(|| CrossroadsError::NotFound(format!("Provider {}", source_id.id)))?;
+        let destination_provider = self.providers.get(&destination_id)
+            .ok_or_else(|| CrossroadsError::NotFound(format!("Provider {}", destination_id.id)))?;
+
+        let source_filesystem = source_provider.as_filesystem()
+            .ok_or_else(|| CrossroadsError::unsupported("FileSystem"))?;
+        let destination_filesystem = destination_provider.as_filesystem()
+            .ok_or_else(|| CrossroadsError::unsupported("FileSystem"))?;
+
+        if source_id == destination_id && options.mode == TransferMode::Copy {
+            let copy = source_filesystem.copy(object_id.clone(), parent_id, name, options.conflict).await?;
+
+            if options.verify_checksums && !object_id.is_directory() {
+                let expected = source_filesystem.checksums(object_id).await?;
+                checksum::verify(name, &expected, &source_filesystem.checksums(copy.clone()).await?)?;
+            }
+
+            return Ok(copy)
+        }
+
+        if source_id == destination_id && options.mode == TransferMode::Move {
+            match transfer::move_within(source_filesystem, object_id.clone(), parent_id.clone(), name, options.conflict).await {
+                Err(CrossroadsError::Unsupported(_)) => {},
+                result => return result,
+            }
+        }
+
+        transfer::transfer(source_filesystem, object_id, destination_filesystem, parent_id, name, options).await
+    }
+
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub async fn add_google_drive(&mut self, provider_id: ProviderId, tokens: HashMap<String, TokenInfo>) -> Result<(), ()> {
         dbg!(&tokens);
         let google_drive = GoogleDrive::new(self.keys.google_api_key.clone().unwrap().to_string(), tokens).await.unwrap();
@@ -120,7 +163,7 @@ impl ProvidersMap {
     }
 
     pub async fn add_native_fs(&mut self, provider_id: ProviderId, root: String) -> Result<(), ()> {
-        let native_fs = NativeFs { root: root.clone() };
+        let native_fs = NativeFs::new(root.clone());
 
         self.save(&provider_id, serde_json::to_value(&native_fs).unwrap()).await;
         self.providers.insert(provider_id.clone(), Arc::new(native_fs));
@@ -128,8 +171,9 @@ impl ProvidersMap {
         Ok(())
     }
 
+    #[allow(clippy::expect_fun_call)]
     pub async fn save(&mut self, provider_id: &ProviderId, value: serde_json::Value) {
-        let storage = NativeFs { root : "".to_string() };
+        let storage = NativeFs::new("".to_string());
         if let Some(proj_dirs) = ProjectDirs::from("", "Orbital", "Files") {
             let path = (proj_dirs.data_dir().to_string_lossy() + "/").to_string();
     
@@ -140,6 +184,7 @@ impl ProvidersMap {
                 name: file_name.clone(),
                 metadata: Some(Metadata {
                     mime_type: Some("text/plain".to_string()),
+                    export_mime_type: None,
                     created_at: Some(chrono::Utc::now()),
                     modified_at: Some(chrono::Utc::now()),
                     meta_changed_at: Some(chrono::Utc::now()),
@@ -148,6 +193,9 @@ impl ProvidersMap {
                     size: None,
                     owner: None,
                     permissions: None,
+                    version: None,
+                    checksums: None,
+                    properties: None,
                 })
             };
     
@@ -189,7 +237,7 @@ impl ProvidersMap {
 
     pub async fn remove_provider(&self, provider_id: ProviderId) -> ProvidersMap {
         if let Some(proj_dirs) = ProjectDirs::from("", "Orbital", "Files") {
-            let storage = NativeFs { root : "".to_string() };
+            let storage = NativeFs::new("".to_string());
             let path = (proj_dirs.data_dir().to_string_lossy() + "/").to_string();
     
             storage.delete(ObjectId::plain_text(path + provider_id.id.as_str() + "." + provider_id.provider_type.to_string().as_str())).await.expect("Unable to remove provider.");
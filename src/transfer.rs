@@ -0,0 +1,464 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use futures::{TryStreamExt, StreamExt};
+use serde::{Serialize, Deserialize};
+
+use crate::checksum;
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{ConflictPolicy, File, FileSystem, FileType, Metadata, ObjectId};
+use crate::providers::conflict;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TransferMode {
+    Copy,
+    /// Each entry of the source is deleted once its copy is verified.
+    Move,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TransferOptions {
+    pub mode: TransferMode,
+    pub conflict: ConflictPolicy,
+    /// Compare the checksums of each copied file with those of the content that was read,
+    /// on top of the sizes which are always compared.
+    pub verify_checksums: bool,
+    /// Transfer links as the entry they point to, rather than leaving them out. The entry
+    /// itself is only copied, a move deleting the link alone.
+    pub follow_links: bool,
+}
+
+impl Default for TransferOptions {
+    fn default() -> Self {
+        TransferOptions {
+            mode: TransferMode::Copy,
+            conflict: ConflictPolicy::Fail,
+            verify_checksums: false,
+            follow_links: true,
+        }
+    }
+}
+
+/// Makes the names entries are transferred under before replacing others unique.
+static TEMPORARY_NAME_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Hidden name, unique to this process, under which an entry called `name` is written before
+/// it replaces another.
+pub(crate) fn temporary_name(name: &str) -> String {
+    format!(".{}.{}-{}.tmp", name, std::process::id(), TEMPORARY_NAME_COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Copies or moves `object_id` from `source` into `parent_id` on `destination` under `name`,
+/// streaming the content of files and recreating directory trees. Mime types and modification
+/// dates are kept where the destination supports them. Returns the id of the new entry.
+///
+/// An entry replaced with `ConflictPolicy::Replace` is only deleted once the new one has been
+/// transferred under a temporary name, which it then takes.
+pub async fn transfer(
+    source: &dyn FileSystem,
+    object_id: ObjectId,
+    destination: &dyn FileSystem,
+    parent_id: ObjectId,
+    name: &str,
+    options: TransferOptions,
+) -> Result<ObjectId, CrossroadsError> {
+    let conflict = options.conflict;
+
+    let existing: Vec<File> = destination.read_directory_stream(parent_id.clone()).try_collect().await?;
+
+    let name = conflict::resolve_name(name, conflict, |candidate| {
+        let taken = existing.iter().any(|file| file.name == candidate);
+        async move { Ok(taken) }
+    }).await?;
+
+    let replaced: Vec<ObjectId> = existing.into_iter()
+        .filter(|file| conflict == ConflictPolicy::Replace && file.name == name)
+        .map(|file| file.id)
+        .collect();
+
+    let metadata = match source.get_metadata(object_id.clone()).await {
+        Ok(metadata) => Some(metadata),
+        Err(CrossroadsError::Unsupported(_)) => None,
+        Err(error) => return Err(error),
+    };
+
+    if replaced.is_empty() {
+        return transfer_entry(source, File { id: object_id, name, metadata }, destination, parent_id, options, vec![]).await
+    }
+
+    let temporary_name = temporary_name(&name);
+
+    let file = File { id: object_id, name: temporary_name.clone(), metadata };
+
+    let transferred = match transfer_entry(source, file, destination, parent_id.clone(), options, vec![]).await {
+        Ok(transferred) => transferred,
+        Err(error) => {
+            // What was transferred before the failure is removed, the replaced entry being kept.
+            let partial: Vec<File> = destination.read_directory_stream(parent_id).try_collect().await.unwrap_or_default();
+
+            for file in partial.into_iter().filter(|file| file.name == temporary_name) {
+                let _ = destination.delete_recursive(file.id).await;
+            }
+
+            return Err(error)
+        },
+    };
+
+    for id in replaced {
+        delete_entry(destination, id).await?;
+    }
+
+    rename_entry(destination, transferred, parent_id, &name).await
+}
+
+/// Moves `object_id` into `parent_id` under `name` on its own file system, renaming it in
+/// place when its parent does not change, so that no content goes through. Fails with
+/// `CrossroadsError::Unsupported` before anything is moved when the provider can do neither.
+pub(crate) async fn move_within(
+    filesystem: &dyn FileSystem,
+    object_id: ObjectId,
+    parent_id: ObjectId,
+    name: &str,
+    conflict: ConflictPolicy,
+) -> Result<ObjectId, CrossroadsError> {
+    let existing: Vec<File> = filesystem.read_directory_stream(parent_id.clone()).try_collect().await?;
+
+    let path = filesystem.path_of(object_id.clone()).await?;
+    let (source_parent, source_name) = path.rsplit_once('/').unwrap_or(("", path.as_str()));
+    let same_parent = source_parent == filesystem.path_of(parent_id.clone()).await?;
+
+    if same_parent && source_name == name {
+        return Ok(object_id)
+    }
+
+    let name = conflict::resolve_name(name, conflict, |candidate| {
+        let taken = existing.iter().any(|file| file.name == candidate);
+        async move { Ok(taken) }
+    }).await?;
+
+    let replaced: Vec<ObjectId> = existing.iter()
+        .filter(|file| conflict == ConflictPolicy::Replace && file.name == name)
+        .map(|file| file.id.clone())
+        .collect();
+
+    // Entries are kept until the moved one is in place beside them.
+    let target_name = if replaced.is_empty() {
+        name.clone()
+    } else {
+        temporary_name(&name)
+    };
+
+    let moved = if same_parent {
+        filesystem.rename(object_id, target_name.clone()).await?
+    } else {
+        // Moving keeps the name, which must not be taken in the new parent.
+        if source_name != target_name && existing.iter().any(|file| file.name == source_name) {
+            return Err(CrossroadsError::Unsupported(format!("moving {} next to an entry of the same name", source_name)))
+        }
+
+        let moved = filesystem.move_to(object_id, parent_id.clone()).await?;
+
+        if source_name == target_name { moved } else { filesystem.rename(moved, target_name.clone()).await? }
+    };
+
+    if replaced.is_empty() {
+        return Ok(moved)
+    }
+
+    for id in replaced {
+        delete_entry(filesystem, id).await?;
+    }
+
+    rename_entry(filesystem, moved, parent_id, &name).await
+}
+
+fn transfer_entry<'a>(
+    source: &'a dyn FileSystem,
+    file: File,
+    destination: &'a dyn FileSystem,
+    parent_id: ObjectId,
+    options: TransferOptions,
+    ancestors: Vec<ObjectId>,
+) -> BoxFuture<'a, Result<ObjectId, CrossroadsError>> {
+    Box::pin(async move {
+        let (target, metadata, content_options) = if file.id.file_type() == FileType::Symlink {
+            if !options.follow_links {
+                return Err(CrossroadsError::Unsupported(format!("transferring the link {} without following it", file.name)))
+            }
+
+            let target = source.read_link(file.id.clone()).await?;
+
+            if ancestors.contains(&target) {
+                return Err(CrossroadsError::InvalidInput(format!("{} links to a directory that contains it", file.name)))
+            }
+
+            let metadata = match source.get_metadata(target.clone()).await {
+                Ok(metadata) => Some(metadata),
+                Err(CrossroadsError::Unsupported(_)) => None,
+                Err(error) => return Err(error),
+            };
+
+            (target, metadata, TransferOptions { mode: TransferMode::Copy, ..options })
+        } else {
+            (file.id.clone(), file.metadata.clone(), options)
+        };
+
+        let file_type = if target.is_directory() { FileType::Directory } else { FileType::File };
+
+        let template = File {
+            id: ObjectId::new(String::new(), file_type),
+            name: file.name.clone(),
+            metadata: metadata.clone(),
+        };
+
+        let created = destination.create(parent_id, template).await?;
+
+        if target.is_directory() {
+            // Listed up front, so that the directory does not change while it is being read.
+            let children: Vec<File> = source.read_directory_stream(target.clone()).try_collect().await?;
+
+            let mut ancestors = ancestors;
+            ancestors.push(target.clone());
+
+            for child in children {
+                if child.id.file_type() == FileType::Symlink && !options.follow_links {
+                    continue
+                }
+
+                transfer_entry(source, child, destination, created.clone(), content_options, ancestors.clone()).await?;
+            }
+        } else {
+            let resolved = File { id: target, name: file.name.clone(), metadata: metadata.clone() };
+            transfer_content(source, &resolved, destination, &created, content_options).await?;
+        }
+
+        if let Some(modified_at) = metadata.as_ref().and_then(|metadata| metadata.modified_at) {
+            match destination.set_modified_at(created.clone(), modified_at).await {
+                Ok(()) | Err(CrossroadsError::Unsupported(_)) => {},
+                Err(error) => return Err(error),
+            }
+        }
+
+        if options.mode == TransferMode::Move {
+            source.delete(file.id).await?;
+        }
+
+        Ok(created)
+    })
+}
+
+async fn delete_entry(filesystem: &dyn FileSystem, object_id: ObjectId) -> Result<(), CrossroadsError> {
+    match filesystem.delete_recursive(object_id).await?.into_iter().next() {
+        Some(failure) => Err(failure.error),
+        None => Ok(()),
+    }
+}
+
+/// Gives `object_id` the name `name` in `parent_id`, copying it on providers that cannot rename it.
+async fn rename_entry(filesystem: &dyn FileSystem, object_id: ObjectId, parent_id: ObjectId, name: &str) -> Result<ObjectId, CrossroadsError> {
+    match filesystem.rename(object_id.clone(), name.to_string()).await {
+        Err(CrossroadsError::Unsupported(_)) => {
+            let renamed = filesystem.copy(object_id.clone(), parent_id, name, ConflictPolicy::Fail).await?;
+            delete_entry(filesystem, object_id).await?;
+            Ok(renamed)
+        },
+        result => result,
+    }
+}
+
+/// Streams the content of `file` and checks that the number of bytes read matches the size
+/// announced by the source and, before a move deletes the source, the size of the copy.
+/// Checksums are compared the same way when requested.
+async fn transfer_content(
+    source: &dyn FileSystem,
+    file: &File,
+    destination: &dyn FileSystem,
+    created: &ObjectId,
+    options: TransferOptions,
+) -> Result<(), CrossroadsError> {
+    let size = file.metadata.as_ref().and_then(|metadata| metadata.size);
+    let transferred = Arc::new(AtomicU64::new(0));
+
+    let counter = transferred.clone();
+    let content = source.read_file_stream(file.id.clone()).await?
+        .inspect(move |chunk| {
+            if let Ok(chunk) = chunk {
+                counter.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+            }
+        });
+
+    let (content, hasher) = checksum::hashing(Box::pin(content));
+
+    destination.write_file_stream(created.clone(), content, size).await?;
+
+    let transferred = transferred.load(Ordering::Relaxed);
+
+    if let Some(size) = size.filter(|size| *size != transferred) {
+        return Err(mismatch(file, size, transferred))
+    }
+
+    if options.verify_checksums {
+        let read = checksum::finish(hasher);
+
+        if let Some(announced) = file.metadata.as_ref().and_then(|metadata| metadata.checksums.as_ref()) {
+            checksum::verify(&file.name, announced, &read)?;
+        }
+
+        checksum::verify(&file.name, &read, &destination.checksums(created.clone()).await?)?;
+    }
+
+    if options.mode == TransferMode::Move {
+        match destination.get_metadata(created.clone()).await {
+            Ok(Metadata { size: Some(size), .. }) if size != transferred => return Err(mismatch(file, size, transferred)),
+            Ok(_) | Err(CrossroadsError::Unsupported(_)) => {},
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(())
+}
+
+fn mismatch(file: &File, expected: u64, transferred: u64) -> CrossroadsError {
+    CrossroadsError::VerificationFailed(format!("{} bytes of {} were transferred instead of {}", transferred, file.name, expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    use chrono::{TimeZone, Utc};
+
+    use crate::error::CrossroadsError;
+
+    use crate::interfaces::filesystem::{ConflictPolicy, FileSystem, ObjectId};
+    use crate::providers::native_fs::NativeFs;
+
+    use super::{move_within, transfer, TransferMode, TransferOptions};
+
+    fn temporary_root(name: &str) -> String {
+        let root = std::env::temp_dir().join(format!("crossroads-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        root.to_string_lossy().to_string() + "/"
+    }
+
+    #[tokio::test]
+    async fn transfer_moves_trees_between_file_systems() {
+        let source = NativeFs::new(temporary_root("transfer-source"));
+        let destination = NativeFs::new(temporary_root("transfer-destination"));
+
+        fs::create_dir_all(source.root.clone() + "album/raw").unwrap();
+        fs::write(source.root.clone() + "album/cover.jpg", "cover").unwrap();
+        fs::write(source.root.clone() + "album/raw/track.wav", "track").unwrap();
+
+        let modified_at = Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap();
+        source.set_modified_at(ObjectId::plain_text("album/cover.jpg".to_string()), modified_at).await.unwrap();
+
+        let album = ObjectId::directory("album".to_string());
+
+        let copy = transfer(&source, album.clone(), &destination, ObjectId::root(), "album", TransferOptions::default()).await.unwrap();
+        assert_eq!(copy, album);
+        assert_eq!(fs::read_to_string(destination.root.clone() + "album/raw/track.wav").unwrap(), "track");
+
+        let metadata = destination.get_metadata(ObjectId::plain_text("album/cover.jpg".to_string())).await.unwrap();
+        assert_eq!(metadata.modified_at, Some(modified_at));
+
+        let options = TransferOptions { mode: TransferMode::Move, conflict: ConflictPolicy::Rename, verify_checksums: true, follow_links: true };
+        let moved = transfer(&source, album, &destination, ObjectId::root(), "album", options).await.unwrap();
+        assert_eq!(moved.as_str(), "album (1)");
+        assert_eq!(fs::read_to_string(destination.root.clone() + "album (1)/cover.jpg").unwrap(), "cover");
+        assert!(fs::metadata(source.root.clone() + "album").is_err());
+
+        fs::remove_dir_all(source.root).unwrap();
+        fs::remove_dir_all(destination.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn transfer_follows_links() {
+        let source = NativeFs::new(temporary_root("links-source"));
+        let destination = NativeFs::new(temporary_root("links-destination"));
+
+        fs::create_dir_all(source.root.clone() + "docs").unwrap();
+        fs::create_dir_all(source.root.clone() + "library").unwrap();
+        fs::write(source.root.clone() + "docs/report.txt", "a longer report").unwrap();
+        fs::write(source.root.clone() + "library/book.txt", "book").unwrap();
+        symlink(source.root.clone() + "docs/report.txt", source.root.clone() + "docs/latest.txt").unwrap();
+        symlink(source.root.clone() + "library", source.root.clone() + "docs/library").unwrap();
+
+        let docs = ObjectId::directory("docs".to_string());
+
+        transfer(&source, docs.clone(), &destination, ObjectId::root(), "docs", TransferOptions::default()).await.unwrap();
+        assert_eq!(fs::read_to_string(destination.root.clone() + "docs/latest.txt").unwrap(), "a longer report");
+        assert_eq!(fs::read_to_string(destination.root.clone() + "docs/library/book.txt").unwrap(), "book");
+        assert!(!fs::symlink_metadata(destination.root.clone() + "docs/library").unwrap().is_symlink());
+
+        let options = TransferOptions { follow_links: false, conflict: ConflictPolicy::Rename, ..Default::default() };
+        transfer(&source, docs.clone(), &destination, ObjectId::root(), "docs", options).await.unwrap();
+        assert_eq!(fs::read_dir(destination.root.clone() + "docs (1)").unwrap().count(), 1);
+
+        // Moving deletes the links, not what they point to.
+        let options = TransferOptions { mode: TransferMode::Move, conflict: ConflictPolicy::Rename, ..Default::default() };
+        transfer(&source, docs, &destination, ObjectId::root(), "docs", options).await.unwrap();
+        assert_eq!(fs::read_to_string(destination.root.clone() + "docs (2)/library/book.txt").unwrap(), "book");
+        assert!(fs::metadata(source.root.clone() + "docs").is_err());
+        assert!(fs::metadata(source.root.clone() + "library/book.txt").is_ok());
+
+        fs::remove_dir_all(source.root).unwrap();
+        fs::remove_dir_all(destination.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn transfer_replaces_only_once_transferred() {
+        let source = NativeFs::new(temporary_root("replace-source"));
+        let destination = NativeFs::new(temporary_root("replace-destination"));
+
+        fs::create_dir_all(source.root.clone() + "docs").unwrap();
+        fs::write(source.root.clone() + "docs/report.txt", "new report").unwrap();
+        symlink("/nonexistent/crossroads", source.root.clone() + "docs/broken").unwrap();
+        fs::create_dir_all(destination.root.clone() + "docs").unwrap();
+        fs::write(destination.root.clone() + "docs/report.txt", "old report").unwrap();
+
+        let options = TransferOptions { conflict: ConflictPolicy::Replace, ..Default::default() };
+        let docs = ObjectId::directory("docs".to_string());
+
+        assert!(transfer(&source, docs.clone(), &destination, ObjectId::root(), "docs", options).await.is_err());
+        assert_eq!(fs::read_to_string(destination.root.clone() + "docs/report.txt").unwrap(), "old report");
+        assert_eq!(fs::read_dir(&destination.root).unwrap().count(), 1);
+
+        fs::remove_file(source.root.clone() + "docs/broken").unwrap();
+
+        let replaced = transfer(&source, docs, &destination, ObjectId::root(), "docs", options).await.unwrap();
+        assert_eq!(replaced.as_str(), "docs");
+        assert_eq!(fs::read_to_string(destination.root.clone() + "docs/report.txt").unwrap(), "new report");
+        assert_eq!(fs::read_dir(&destination.root).unwrap().count(), 1);
+
+        fs::remove_dir_all(source.root).unwrap();
+        fs::remove_dir_all(destination.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn move_within_renames_and_replaces() {
+        let filesystem = NativeFs::new(temporary_root("move-within"));
+
+        fs::create_dir_all(filesystem.root.clone() + "archive").unwrap();
+        fs::write(filesystem.root.clone() + "draft.txt", "draft").unwrap();
+        fs::write(filesystem.root.clone() + "archive/final.txt", "former").unwrap();
+
+        let draft = ObjectId::plain_text("draft.txt".to_string());
+        let renamed = move_within(&filesystem, draft, ObjectId::root(), "notes.txt", ConflictPolicy::Fail).await.unwrap();
+        assert_eq!(renamed.as_str(), "notes.txt");
+
+        let archive = ObjectId::directory("archive".to_string());
+        let result = move_within(&filesystem, renamed.clone(), archive.clone(), "final.txt", ConflictPolicy::Fail).await;
+        assert!(matches!(result, Err(CrossroadsError::AlreadyExists(_))));
+
+        let moved = move_within(&filesystem, renamed, archive, "final.txt", ConflictPolicy::Replace).await.unwrap();
+        assert_eq!(moved.as_str(), "archive/final.txt");
+        assert_eq!(fs::read_to_string(filesystem.root.clone() + "archive/final.txt").unwrap(), "draft");
+        assert_eq!(fs::read_dir(filesystem.root.clone() + "archive").unwrap().count(), 1);
+        assert_eq!(fs::read_dir(&filesystem.root).unwrap().count(), 1);
+
+        fs::remove_dir_all(filesystem.root).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::{HashSet, VecDeque};
+use std::pin::Pin;
+
+use futures::future::BoxFuture;
+use futures::stream::{self, FuturesUnordered};
+use futures::{Stream, StreamExt};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::error::CrossroadsError;
+use crate::interfaces::filesystem::{File, FileSystem, FileType, ObjectId};
+
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    /// Deepest level yielded, the children of the starting directory being at depth 1.
+    pub max_depth: Option<usize>,
+    /// Glob patterns matched against the path of an entry relative to the starting directory.
+    /// When not empty, only matching entries are yielded but every directory is still traversed.
+    pub include: Vec<String>,
+    /// Entries matching one of these patterns are skipped along with their content.
+    pub exclude: Vec<String>,
+    pub follow_symlinks: bool,
+    /// Maximum number of directories being listed at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions {
+            max_depth: None,
+            include: vec![],
+            exclude: vec![],
+            follow_symlinks: false,
+            concurrency: 4,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WalkEntry {
+    pub file: File,
+    /// Names of the ancestors of the entry up to the starting directory, joined with `/`.
+    pub path: String,
+    pub depth: usize,
+}
+
+pub type WalkStream<'a> = Pin<Box<dyn Stream<Item = Result<WalkEntry, CrossroadsError>> + Send + 'a>>;
+
+struct Directory {
+    id: ObjectId,
+    path: String,
+    depth: usize,
+}
+
+/// Content of a directory, along with the directory each entry leads to if it should be traversed.
+type Listing<'a> = BoxFuture<'a, (Directory, Result<Vec<(File, Option<ObjectId>)>, CrossroadsError>)>;
+
+struct Walker<'a> {
+    filesystem: &'a dyn FileSystem,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    concurrency: usize,
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    pending: VecDeque<Directory>,
+    listings: FuturesUnordered<Listing<'a>>,
+    ready: VecDeque<Result<WalkEntry, CrossroadsError>>,
+    visited: HashSet<ObjectId>,
+}
+
+/// Yields every entry below `root`, directories being listed concurrently up to
+/// `options.concurrency`. Each directory is traversed once, so links leading back
+/// to one of their ancestors do not loop. A directory that cannot be listed yields
+/// an error and the walk carries on with the others.
+pub fn walk<'a>(filesystem: &'a dyn FileSystem, root: ObjectId, options: WalkOptions) -> Result<WalkStream<'a>, CrossroadsError> {
+    let include = if options.include.is_empty() {
+        None
+    } else {
+        Some(glob_set(&options.include)?)
+    };
+
+    let mut walker = Walker {
+        filesystem,
+        max_depth: options.max_depth,
+        follow_symlinks: options.follow_symlinks,
+        concurrency: options.concurrency.max(1),
+        include,
+        exclude: glob_set(&options.exclude)?,
+        pending: VecDeque::new(),
+        listings: FuturesUnordered::new(),
+        ready: VecDeque::new(),
+        visited: HashSet::new(),
+    };
+
+    if options.max_depth != Some(0) {
+        walker.visited.insert(root.clone());
+        walker.pending.push_back(Directory { id: root, path: String::new(), depth: 0 });
+    }
+
+    Ok(Box::pin(stream::unfold(walker, |mut walker| async move {
+        let entry = walker.next_entry().await?;
+        Some((entry, walker))
+    })))
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet, CrossroadsError> {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|error| CrossroadsError::InvalidInput(error.to_string()))?;
+        builder.add(glob);
+    }
+
+    builder.build().map_err(|error| CrossroadsError::InvalidInput(error.to_string()))
+}
+
+fn list(filesystem: &dyn FileSystem, directory: Directory, follow_symlinks: bool) -> Listing<'_> {
+    Box::pin(async move {
+        let files = match filesystem.read_directory(directory.id.clone()).await {
+            Ok(files) => files,
+            Err(error) => return (directory, Err(error)),
+        };
+
+        let mut children = Vec::with_capacity(files.len());
+
+        for file in files {
+            let target = match file.id.file_type() {
+                FileType::Directory => Some(file.id.clone()),
+                // Dangling links and providers without links are yielded without being followed.
+                FileType::Symlink if follow_symlinks => filesystem.read_link(file.id.clone()).await
+                    .ok()
+                    .filter(ObjectId::is_directory),
+                _ => None,
+            };
+
+            children.push((file, target));
+        }
+
+        (directory, Ok(children))
+    })
+}
+
+impl<'a> Walker<'a> {
+    async fn next_entry(&mut self) -> Option<Result<WalkEntry, CrossroadsError>> {
+        loop {
+            if let Some(entry) = self.ready.pop_front() {
+                return Some(entry)
+            }
+
+            while self.listings.len() < self.concurrency {
+                match self.pending.pop_front() {
+                    Some(directory) => self.listings.push(list(self.filesystem, directory, self.follow_symlinks)),
+                    None => break,
+                }
+            }
+
+            match self.listings.next().await? {
+                (directory, Ok(children)) => self.enqueue(directory, children),
+                (_, Err(error)) => self.ready.push_back(Err(error)),
+            }
+        }
+    }
+
+    fn enqueue(&mut self, directory: Directory, children: Vec<(File, Option<ObjectId>)>) {
+        let depth = directory.depth + 1;
+
+        for (file, target) in children {
+            let path = if directory.path.is_empty() {
+                file.name.clone()
+            } else {
+                format!("{}/{}", directory.path, file.name)
+            };
+
+            if self.exclude.is_match(&path) {
+                continue
+            }
+
+            let descend = self.max_depth.is_none_or(|max_depth| depth < max_depth);
+
+            if let Some(target) = target {
+                if descend && self.visited.insert(target.clone()) {
+                    self.pending.push_back(Directory { id: target, path: path.clone(), depth });
+                }
+            }
+
+            if self.include.as_ref().is_none_or(|include| include.is_match(&path)) {
+                self.ready.push_back(Ok(WalkEntry { file, path, depth }));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    use futures::TryStreamExt;
+
+    use crate::interfaces::filesystem::ObjectId;
+    use crate::providers::native_fs::NativeFs;
+
+    use super::{walk, WalkOptions};
+
+    fn temporary_tree(name: &str) -> String {
+        let root = std::env::temp_dir().join(format!("crossroads-{}-{}", name, std::process::id()));
+        fs::create_dir_all(root.join("docs/nested")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("docs/b.md"), "b").unwrap();
+        fs::write(root.join("docs/nested/c.txt"), "c").unwrap();
+        fs::write(root.join("target/d.txt"), "d").unwrap();
+        symlink("..", root.join("docs/loop")).unwrap();
+        root.to_string_lossy().to_string() + "/"
+    }
+
+    async fn walked_paths(filesystem: &NativeFs, options: WalkOptions) -> HashSet<String> {
+        walk(filesystem, ObjectId::root(), options).unwrap()
+            .map_ok(|entry| entry.path)
+            .try_collect().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn walk_follows_links_without_looping() {
+        let x = NativeFs::new(temporary_tree("walk-links"));
+
+        let options = WalkOptions { follow_symlinks: true, concurrency: 2, ..Default::default() };
+        let paths = walked_paths(&x, options).await;
+
+        let expected = ["a.txt", "docs", "docs/b.md", "docs/loop", "docs/nested", "docs/nested/c.txt", "target", "target/d.txt"];
+        assert_eq!(paths, expected.iter().map(|path| path.to_string()).collect());
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn walk_applies_depth_and_filters() {
+        let x = NativeFs::new(temporary_tree("walk-filters"));
+
+        let options = WalkOptions { max_depth: Some(1), ..Default::default() };
+        let paths = walked_paths(&x, options).await;
+        assert_eq!(paths, ["a.txt", "docs", "target"].iter().map(|path| path.to_string()).collect());
+
+        let options = WalkOptions {
+            include: vec!["*.txt".to_string()],
+            exclude: vec!["target".to_string()],
+            ..Default::default()
+        };
+        let paths = walked_paths(&x, options).await;
+        assert_eq!(paths, ["a.txt", "docs/nested/c.txt"].iter().map(|path| path.to_string()).collect());
+
+        assert!(walk(&x, ObjectId::root(), WalkOptions { include: vec!["[".to_string()], ..Default::default() }).is_err());
+
+        fs::remove_dir_all(x.root).unwrap();
+    }
+}